@@ -0,0 +1,193 @@
+//! Fenced code blocks: inventory by language and extraction to files.
+
+use crate::config::ScanOptions;
+use crate::scan::{markdown_files, normalize_path, read_note, relative_path};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+struct CodeBlock {
+    language: String,
+    line: usize,
+    code: String,
+}
+
+#[derive(Serialize)]
+struct CodeLocation {
+    path: String,
+    line: usize,
+    lines: usize,
+}
+
+#[derive(Serialize)]
+struct LanguageBlocks {
+    language: String,
+    count: usize,
+    blocks: Vec<CodeLocation>,
+}
+
+#[derive(Serialize)]
+pub struct CodeOutput {
+    languages: Vec<LanguageBlocks>,
+}
+
+#[derive(Serialize)]
+pub struct ExtractedBlock {
+    file: String,
+    source: String,
+    line: usize,
+}
+
+#[derive(Serialize)]
+pub struct CodeExtractOutput {
+    pub extracted: Vec<ExtractedBlock>,
+}
+
+fn extract_code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // (fence char, fence length, language, start line, collected lines)
+    let mut open: Option<(char, usize, String, usize, Vec<&str>)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+        let is_fence = indent <= 3 && fence_len >= 3;
+
+        match &mut open {
+            None if is_fence => {
+                let info = trimmed[fence_len..].trim();
+                let language = info.split_whitespace().next().unwrap_or("").to_lowercase();
+                open = Some((fence_char.unwrap(), fence_len, language, index + 1, Vec::new()));
+            }
+            None => {}
+            Some((c, len, _, _, lines)) => {
+                let closes = is_fence && fence_char == Some(*c) && fence_len >= *len && trimmed[fence_len..].trim().is_empty();
+                if closes {
+                    let (_, _, language, line, lines) = open.take().unwrap();
+                    blocks.push(CodeBlock {
+                        language,
+                        line,
+                        code: lines.join("\n"),
+                    });
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+fn language_extension(language: &str) -> &str {
+    match language {
+        "python" | "py" => "py",
+        "rust" | "rs" => "rs",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "bash" | "sh" | "shell" | "zsh" => "sh",
+        "ruby" | "rb" => "rb",
+        "golang" | "go" => "go",
+        "yaml" | "yml" => "yml",
+        "markdown" | "md" => "md.txt",
+        "" => "txt",
+        other => other,
+    }
+}
+
+fn collect_code_blocks(vault_path: &Path, lang: Option<&str>, options: &ScanOptions) -> Vec<(String, CodeBlock)> {
+    let lang = lang.map(|l| l.to_lowercase());
+    let mut found = Vec::new();
+
+    for path in markdown_files(vault_path) {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let source = relative_path(vault_path, &path);
+        for block in extract_code_blocks(&content) {
+            if lang.as_ref().is_none_or(|l| *l == block.language) {
+                found.push((source.clone(), block));
+            }
+        }
+    }
+
+    found.sort_by(|a, b| (&a.0, a.1.line).cmp(&(&b.0, b.1.line)));
+    found
+}
+
+pub fn code_inventory(vault_path: &Path, lang: Option<&str>, options: &ScanOptions) -> CodeOutput {
+    let mut by_language: BTreeMap<String, Vec<CodeLocation>> = BTreeMap::new();
+
+    for (path, block) in collect_code_blocks(vault_path, lang, options) {
+        let language = if block.language.is_empty() { "(none)".to_string() } else { block.language };
+        by_language.entry(language).or_default().push(CodeLocation {
+            path,
+            line: block.line,
+            lines: block.code.lines().count(),
+        });
+    }
+
+    let mut languages: Vec<LanguageBlocks> = by_language
+        .into_iter()
+        .map(|(language, blocks)| LanguageBlocks {
+            language,
+            count: blocks.len(),
+            blocks,
+        })
+        .collect();
+    languages.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.language.cmp(&b.language)));
+
+    CodeOutput { languages }
+}
+
+pub fn extract_code(vault_path: &Path, lang: Option<&str>, out_dir: &Path, options: &ScanOptions) -> Result<Vec<ExtractedBlock>, String> {
+    fs::create_dir_all(out_dir).map_err(|e| format!("failed to create {}: {}", out_dir.display(), e))?;
+    let mut extracted = Vec::new();
+
+    for (source, block) in collect_code_blocks(vault_path, lang, options) {
+        // Flatten the note path so blocks from same-named notes in different folders don't collide
+        let stem = normalize_path(vault_path, &source).replace(['/', ' '], "_");
+        let file_name = format!("{}-L{}.{}", stem, block.line, language_extension(&block.language));
+        let out_path = out_dir.join(&file_name);
+        fs::write(&out_path, format!("{}\n", block.code))
+            .map_err(|e| format!("failed to write {}: {}", out_path.display(), e))?;
+        extracted.push(ExtractedBlock {
+            file: out_path.to_string_lossy().to_string(),
+            source,
+            line: block.line,
+        });
+    }
+
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_blocks_are_grouped_by_language_and_extracted() {
+        let blocks = extract_code_blocks("```Rust title\nfn main() {}\n```\n~~~~\n```not a close\n~~~~\n  ```sh\nls\n");
+        let found: Vec<(&str, usize, &str)> = blocks.iter().map(|b| (b.language.as_str(), b.line, b.code.as_str())).collect();
+        assert_eq!(found, [("rust", 1, "fn main() {}"), ("", 4, "```not a close")]);
+
+        let root = std::env::temp_dir().join(format!("obsidian-cli-code-{}", std::process::id()));
+        fs::create_dir_all(root.join("Dev")).unwrap();
+        fs::write(root.join("Dev/Setup Notes.md"), "```python\nprint(1)\n```\n\n```py\nprint(2)\n```\n```rust\n```\n").unwrap();
+        let options = ScanOptions::default();
+        let inventory = code_inventory(&root, None, &options);
+        let languages: Vec<(&str, usize)> = inventory.languages.iter().map(|l| (l.language.as_str(), l.count)).collect();
+        assert_eq!(languages, [("py", 1), ("python", 1), ("rust", 1)]);
+
+        let out = root.join("out");
+        let extracted = extract_code(&root, Some("PYTHON"), &out, &options).unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(fs::read_to_string(&extracted[0].file).unwrap(), "print(1)\n");
+        assert_eq!(Path::new(&extracted[0].file), out.join("Dev_Setup_Notes-L1.py"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
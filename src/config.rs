@@ -0,0 +1,408 @@
+//! Settings from a vault's `.obsidian-cli.toml`, and finding the vault in the first place.
+
+use crate::parse::{Frontmatter, TagSyntax, extract_tags_from_file};
+use crate::scan::{is_ignored_note, is_private_note};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Folder holding note templates, relative to the vault root
+    pub templates_folder: String,
+    /// Regex matched against a note's file stem to identify daily notes
+    pub daily_note_pattern: String,
+    /// Links whose target starts with this are placeholders for notes not written yet,
+    /// not broken links. Empty disables placeholders.
+    pub placeholder_prefix: String,
+    /// Folder of retired notes, left out of orphans, stats and link health unless
+    /// --include-archived is given. Empty disables archive handling.
+    pub archive_folder: String,
+    /// Frontmatter key that, set to true, leaves a note out of every command as if it
+    /// weren't in the vault. Empty disables it.
+    pub ignore_key: String,
+    /// The day weeks start on, `monday` or `sunday`, for the weeks of `tasks --agenda`
+    pub week_start: WeekStart,
+    pub graph: GraphConfig,
+    pub size_audit: SizeAuditConfig,
+    pub naming: NamingConfig,
+    pub link_text: LinkTextConfig,
+    pub tags: TagSyntax,
+    /// Alternative tag names and the tag they stand for, like `js = "javascript"`
+    pub tag_aliases: BTreeMap<String, String>,
+    /// Command shorthands and the arguments they stand for, like
+    /// `bl = "backlinks --context --format table"`
+    pub alias: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            templates_folder: "Templates".to_string(),
+            daily_note_pattern: r"^(\d{4}-\d{2}-\d{2}|\d{2}\.\d{2}\.\d{4})$".to_string(),
+            placeholder_prefix: "TODO:".to_string(),
+            archive_folder: "Archive".to_string(),
+            ignore_key: "obsidian-cli-ignore".to_string(),
+            week_start: WeekStart::default(),
+            graph: GraphConfig::default(),
+            size_audit: SizeAuditConfig::default(),
+            naming: NamingConfig::default(),
+            link_text: LinkTextConfig::default(),
+            tags: TagSyntax::default(),
+            tag_aliases: BTreeMap::new(),
+            alias: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SizeAuditConfig {
+    /// Files larger than this many bytes are flagged as too large to sync to mobile
+    pub max_file_bytes: u64,
+    /// How many of the largest files to list
+    pub largest_files: usize,
+}
+
+impl Default for SizeAuditConfig {
+    fn default() -> Self {
+        SizeAuditConfig {
+            max_file_bytes: 5 * 1024 * 1024,
+            largest_files: 20,
+        }
+    }
+}
+
+/// How `lint` treats a rule: errors make it exit non-zero, warnings are only reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Off,
+}
+
+/// Link text rules checked by `lint`, each with its severity.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LinkTextConfig {
+    /// `[[note-name]]` in a sentence where the name reads badly as text: a path, an
+    /// extension, a date prefix, or `-`/`_` instead of spaces
+    pub bare_links: Severity,
+    /// `[[Note|Note]]`, an alias that repeats the target
+    pub redundant_alias: Severity,
+    /// Link text like "click here" that says nothing about where the link goes
+    pub vague_text: Severity,
+    /// What counts as vague link text, compared ignoring case and punctuation
+    pub vague_phrases: Vec<String>,
+}
+
+impl Default for LinkTextConfig {
+    fn default() -> Self {
+        LinkTextConfig {
+            bare_links: Severity::Warning,
+            redundant_alias: Severity::Warning,
+            vague_text: Severity::Warning,
+            vague_phrases: ["click here", "here", "this", "this link", "link", "read more", "more", "this page"]
+                .map(str::to_string)
+                .to_vec(),
+        }
+    }
+}
+
+/// The day weeks start on, for grouping days into weeks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    /// The first day of the week `day` (in days since 1970-01-01, a Thursday) falls in.
+    pub(crate) fn week_of(self, day: i64) -> i64 {
+        let offset = match self {
+            WeekStart::Monday => 3,
+            WeekStart::Sunday => 4,
+        };
+        day - (day + offset).rem_euclid(7)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum NameCase {
+    #[default]
+    #[serde(rename = "any")]
+    Any,
+    #[serde(rename = "kebab-case")]
+    Kebab,
+    #[serde(rename = "snake_case")]
+    Snake,
+}
+
+/// Naming rules checked by `lint` for every folder and note name.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NamingConfig {
+    pub case: NameCase,
+    pub no_spaces: bool,
+    /// Notes in these folders must start with a YYYY-MM-DD date
+    pub date_prefix_folders: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GraphConfig {
+    /// Notes linked to or from more distinct notes than this are reported as hubs
+    pub max_fan_out: usize,
+    /// Treat links into daily notes as noise
+    pub prune_daily_links: bool,
+    /// Treat links from templates as noise
+    pub prune_template_links: bool,
+    /// Additional note names whose inbound links are always noise
+    pub noise_notes: Vec<String>,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        GraphConfig {
+            max_fan_out: 25,
+            prune_daily_links: true,
+            prune_template_links: true,
+            noise_notes: Vec::new(),
+        }
+    }
+}
+
+/// Decides which notes a scan sees and how it reads them.
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    /// Include notes marked `private: true` in frontmatter or tagged #private
+    pub include_private: bool,
+    pub tag_syntax: TagSyntax,
+    pub placeholder_prefix: String,
+    pub archive_folder: String,
+    pub include_archived: bool,
+    pub tag_aliases: BTreeMap<String, String>,
+    /// Frontmatter key marking notes to ignore; empty ignores none
+    pub ignore_key: String,
+}
+
+impl ScanOptions {
+    /// The options a vault's config asks for, leaving out private notes and the archive.
+    pub fn from_config(config: &Config) -> Self {
+        ScanOptions {
+            tag_syntax: config.tags.clone(),
+            placeholder_prefix: config.placeholder_prefix.clone(),
+            archive_folder: config.archive_folder.clone(),
+            tag_aliases: config.tag_aliases.clone(),
+            ignore_key: config.ignore_key.clone(),
+            ..ScanOptions::default()
+        }
+    }
+
+    pub(crate) fn in_archive(&self, note: &str) -> bool {
+        let folder = self.archive_folder.trim_matches('/');
+        !folder.is_empty() && note.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+    }
+
+    /// Whether a note with this content is left out of the scan: it is ignored, or it is
+    /// private and the scan doesn't include private notes.
+    pub(crate) fn hides(&self, content: &str) -> bool {
+        let frontmatter = Frontmatter::of(content);
+        is_ignored_note(&frontmatter, &self.ignore_key)
+            || (!self.include_private && is_private_note(content, &frontmatter, &self.tag_syntax))
+    }
+
+    /// Whether a report that leaves out archived notes should skip this one.
+    pub(crate) fn skips_archived(&self, note: &str) -> bool {
+        !self.include_archived && self.in_archive(note)
+    }
+
+    pub(crate) fn is_placeholder(&self, link: &str) -> bool {
+        !self.placeholder_prefix.is_empty() && link.trim_start().starts_with(&self.placeholder_prefix)
+    }
+
+    /// The tag an alias stands for, including nested tags under it (`js/react` becomes
+    /// `javascript/react` for `js = "javascript"`). Other tags are returned unchanged.
+    pub fn canonical_tag(&self, tag: &str) -> String {
+        let (head, rest) = match tag.split_once('/') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (tag, None),
+        };
+        match (self.tag_aliases.get(head), rest) {
+            (Some(canonical), Some(rest)) => format!("{}/{}", canonical, rest),
+            (Some(canonical), None) => canonical.clone(),
+            (None, _) => tag.to_string(),
+        }
+    }
+
+    /// A note's tags with aliases replaced by their canonical tag.
+    pub fn note_tags(&self, content: &str) -> Vec<String> {
+        extract_tags_from_file(content, &self.tag_syntax)
+            .iter()
+            .map(|tag| self.canonical_tag(tag))
+            .collect()
+    }
+}
+
+/// Finds the vault containing `path` by walking up to the nearest folder with an
+/// `.obsidian` directory. The walk never leaves the starting filesystem and stops at
+/// the home directory and any `stop_at` folder. Without a `.obsidian` folder `path`
+/// itself is the vault, unless it is a home or filesystem root, which is refused
+/// without `force` so a stray run doesn't scan everything below it.
+pub fn resolve_vault_root(path: &Path, stop_at: &[PathBuf], force: bool) -> Result<PathBuf, String> {
+    let start = path
+        .canonicalize()
+        .map_err(|e| format!("cannot open vault {}: {}", path.display(), e))?;
+    let home = std::env::var_os("HOME").and_then(|home| PathBuf::from(home).canonicalize().ok());
+    let stops: Vec<PathBuf> = stop_at
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .chain(home.clone())
+        .collect();
+
+    for dir in start.ancestors() {
+        if dir.join(".obsidian").is_dir() {
+            return Ok(dir.to_path_buf());
+        }
+        if stops.iter().any(|stop| stop == dir) || !same_filesystem(&start, dir) {
+            break;
+        }
+    }
+
+    let is_root = start.parent().is_none_or(|parent| !same_filesystem(&start, parent));
+    if !force && (is_root || home.as_ref() == Some(&start)) {
+        return Err(format!(
+            "{} is not inside a vault (no .obsidian folder found) and looks like a home or filesystem root; pass --force to scan it anyway",
+            start.display()
+        ));
+    }
+    Ok(start)
+}
+
+#[cfg(unix)]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_filesystem(_a: &Path, _b: &Path) -> bool {
+    true
+}
+
+pub fn load_config(vault_path: &Path, config_path: Option<&Path>) -> Result<Config, String> {
+    let default_path = vault_path.join(".obsidian-cli.toml");
+    let path = match config_path {
+        Some(path) => path,
+        None if default_path.is_file() => &default_path,
+        None => return Ok(Config::default()),
+    };
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read config {}: {}", path.display(), e))?;
+    toml::from_str(&content).map_err(|e| format!("invalid config {}: {}", path.display(), e))
+}
+
+/// Splits a command alias into arguments the way a shell would: on whitespace, except
+/// inside single or double quotes, with `\` escaping the next character outside single quotes.
+pub fn split_arguments(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(format!("unclosed ' in `{}`", line)),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => arg.extend(chars.next()),
+                        Some(c) => arg.push(c),
+                        None => return Err(format!("unclosed \" in `{}`", line)),
+                    }
+                }
+            }
+            '\\' => current.get_or_insert_with(String::new).extend(chars.next()),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::{format_date, parse_iso_date};
+
+    #[test]
+    fn weeks_start_on_the_configured_day() {
+        // 2024-02-29 is a Thursday
+        let thursday = parse_iso_date("2024-02-29").unwrap();
+        assert_eq!(format_date(WeekStart::Monday.week_of(thursday)), "2024-02-26");
+        assert_eq!(format_date(WeekStart::Sunday.week_of(thursday)), "2024-02-25");
+        assert_eq!(WeekStart::Monday.week_of(thursday - 3), thursday - 3);
+        assert_eq!(WeekStart::Sunday.week_of(thursday + 3), thursday + 3);
+        assert_eq!(format_date(WeekStart::Monday.week_of(-1)), "1969-12-29");
+    }
+
+    #[test]
+    fn aliases_split_like_shell_words() {
+        assert_eq!(
+            split_arguments(r#"tag "project/*"  --format 'table' a\ b"#).unwrap(),
+            ["tag", "project/*", "--format", "table", "a b"]
+        );
+        assert_eq!(split_arguments(r#"search "say \"hi\"" ''"#).unwrap(), ["search", r#"say "hi""#, ""]);
+        assert!(split_arguments("search 'open").is_err());
+    }
+
+    #[test]
+    fn tag_aliases_map_nested_tags() {
+        let options = ScanOptions {
+            tag_aliases: BTreeMap::from([("js".to_string(), "javascript".to_string())]),
+            ..ScanOptions::default()
+        };
+        assert_eq!(options.canonical_tag("js"), "javascript");
+        assert_eq!(options.canonical_tag("js/react"), "javascript/react");
+        assert_eq!(options.canonical_tag("jsx"), "jsx");
+        assert_eq!(options.note_tags("#js and #rust"), ["javascript", "rust"]);
+    }
+
+    #[test]
+    fn vault_root_is_the_nearest_folder_with_obsidian_settings() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-vault-root-{}", std::process::id()));
+        fs::create_dir_all(root.join("Vault/.obsidian")).unwrap();
+        fs::create_dir_all(root.join("Vault/Projects/Web")).unwrap();
+        fs::create_dir_all(root.join("Loose/Notes")).unwrap();
+        let root = root.canonicalize().unwrap();
+
+        assert_eq!(resolve_vault_root(&root.join("Vault/Projects/Web"), &[], false).unwrap(), root.join("Vault"));
+        assert_eq!(resolve_vault_root(&root.join("Vault/Projects/Web"), &[root.join("Vault/Projects")], false).unwrap(), root.join("Vault/Projects/Web"));
+        assert_eq!(resolve_vault_root(&root.join("Loose/Notes"), &[], false).unwrap(), root.join("Loose/Notes"));
+        assert!(resolve_vault_root(&root.join("Missing"), &[], false).unwrap_err().starts_with("cannot open vault"));
+        assert!(resolve_vault_root(Path::new("/"), &[], false).unwrap_err().contains("pass --force"));
+        assert_eq!(resolve_vault_root(Path::new("/"), &[], true).unwrap(), Path::new("/"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
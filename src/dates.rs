@@ -0,0 +1,134 @@
+//! Civil dates without a date crate: day counts, ISO and German-style dates, and file
+//! modification days.
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A file's modification time as `YYYY-MM-DDTHH:MM:SS` in UTC, which sorts and compares
+/// correctly as text.
+pub(crate) fn modified_timestamp(metadata: &fs::Metadata) -> Option<String> {
+    let seconds = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let time = seconds % 86_400;
+    Some(format!(
+        "{}T{:02}:{:02}:{:02}",
+        format_date((seconds / 86_400) as i64),
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    ))
+}
+
+pub(crate) fn modified_date(path: &Path) -> String {
+    let seconds = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    format_date((seconds / 86_400) as i64)
+}
+
+pub(crate) fn file_modified(path: &Path) -> String {
+    match fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(modified) => format!("{:?}", modified),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub(crate) fn parse_iso_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Parses a date written either as `YYYY-MM-DD` or German-style as `DD.MM.YYYY` (the day
+/// and month may drop their leading zero), the two ways daily notes are commonly named.
+pub(crate) fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '.');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(day), Some(month), Some(year)) if year.len() == 4 && day.len() <= 2 && month.len() <= 2 => {
+            parse_iso_date(&format!("{}-{}-{}", year, month, day))
+        }
+        _ => parse_iso_date(text),
+    }
+}
+
+/// The first date in `text`, in either form [`parse_date`] reads.
+pub(crate) fn find_date(text: &str) -> Option<i64> {
+    static DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d{4}-\d{2}-\d{2}|\b\d{1,2}\.\d{1,2}\.\d{4}").unwrap());
+    DATE.find_iter(text).find_map(|found| parse_date(found.as_str()))
+}
+
+pub(crate) fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Today's date in UTC, as days since the epoch.
+pub(crate) fn today() -> i64 {
+    (unix_now() / 86_400) as i64
+}
+
+pub(crate) fn days_since_epoch(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| (d.as_secs() / 86_400) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(parse_iso_date("2024-02-29"), Some(19782));
+        assert_eq!(format_date(19782), "2024-02-29");
+        assert_eq!(format_date(-1), "1969-12-31");
+        assert_eq!(parse_iso_date("2024-13-01"), None);
+    }
+
+    #[test]
+    fn dates_parse_in_iso_or_german_order() {
+        assert_eq!(parse_date("29.02.2024"), Some(19782));
+        assert_eq!(parse_date("1.3.2024"), parse_iso_date("2024-03-01"));
+        assert_eq!(parse_date("2024-02-29"), Some(19782));
+        assert_eq!(parse_date("29.13.2024"), None);
+        assert_eq!(find_date("Standup 05.01.2024"), parse_iso_date("2024-01-05"));
+        assert_eq!(find_date("created 2024-01-05T09:00"), parse_iso_date("2024-01-05"));
+        assert_eq!(find_date("version 1.2.3"), None);
+    }
+}
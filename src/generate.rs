@@ -0,0 +1,354 @@
+//! Generated notes and regions: maps of content, tag pages and "on this day".
+
+use crate::config::{Config, ScanOptions};
+use crate::dates::{civil_from_days, days_since_epoch, find_date, format_date, parse_date, today};
+use crate::links::link_target;
+use crate::parse::{Frontmatter, extract_frontmatter};
+use crate::scan::{markdown_files, note_stem, read_note, relative_path};
+use crate::write::VaultWriter;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MocGrouping {
+    Tag,
+    Folder,
+}
+
+#[derive(Serialize)]
+pub struct OnThisDayNote {
+    path: String,
+    /// The daily note's date, or the date the note was created
+    date: String,
+    years_ago: i64,
+    daily: bool,
+}
+
+#[derive(Serialize)]
+pub struct OnThisDayOutput {
+    dry_run: bool,
+    date: String,
+    /// Notes from the same day in earlier years, most recent first
+    notes: Vec<OnThisDayNote>,
+    /// The day's own daily note, if it exists
+    daily_note: Option<String>,
+    /// Whether the "On this day" section was written to the daily note
+    appended: bool,
+}
+
+#[derive(Serialize)]
+pub struct GeneratedNote {
+    path: String,
+    created: bool,
+    changed: bool,
+    entries: usize,
+}
+
+pub(crate) const GENERATED_START: &str = "<!-- obsidian-cli:generated:start -->";
+const GENERATED_END: &str = "<!-- obsidian-cli:generated:end -->";
+
+/// Replaces the marked auto-generated region of a note, leaving everything outside the
+/// markers untouched. Notes without markers get the region appended.
+fn replace_generated_region(existing: &str, generated: &str) -> String {
+    let region = format!("{}\n{}{}", GENERATED_START, generated, GENERATED_END);
+    if let Some(start) = existing.find(GENERATED_START)
+        && let Some(end) = existing[start..].find(GENERATED_END)
+    {
+        let end = start + end + GENERATED_END.len();
+        return format!("{}{}{}", &existing[..start], region, &existing[end..]);
+    }
+
+    let mut content = existing.to_string();
+    if !content.is_empty() && !content.ends_with("\n\n") {
+        content.push_str(if content.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    content.push_str(&region);
+    content.push('\n');
+    content
+}
+
+pub(crate) fn write_generated_note(vault_path: &Path, relative: &str, title: &str, generated: &str, entries: usize, writer: &mut VaultWriter) -> Result<GeneratedNote, String> {
+    let path = vault_path.join(relative);
+    let existing = fs::read_to_string(&path).ok();
+    let created = existing.is_none();
+    let base = existing.clone().unwrap_or_else(|| format!("# {}\n\n", title));
+    let content = replace_generated_region(&base, generated);
+    let changed = existing.as_deref() != Some(content.as_str());
+
+    if changed {
+        writer.write(vault_path, &path, &content)?;
+    }
+
+    Ok(GeneratedNote {
+        path: relative.to_string(),
+        created,
+        changed,
+        entries,
+    })
+}
+
+fn note_excerpt(content: &str) -> Option<String> {
+    let body = match extract_frontmatter(content) {
+        Some(frontmatter) => &content[frontmatter.len() + 8..],
+        None => content,
+    };
+    let line = body
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("```"))?;
+    // Keep excerpts from adding links of their own to the graph
+    let link_regex = Regex::new(r"\[\[(?:[^\]|]+\|)?([^\]]+)\]\]").unwrap();
+    let line = link_regex.replace_all(line, "$1");
+    let excerpt: String = line.chars().take(120).collect();
+    if excerpt.len() < line.len() {
+        Some(format!("{}…", excerpt))
+    } else {
+        Some(excerpt)
+    }
+}
+
+pub fn generate_moc(vault_path: &Path, folder: &str, grouping: Option<MocGrouping>, excerpts: bool, writer: &mut VaultWriter, options: &ScanOptions) -> Result<GeneratedNote, String> {
+    let folder = folder.trim_matches('/');
+    let folder_path = vault_path.join(folder);
+    if !folder_path.is_dir() {
+        return Err(format!("{} is not a folder in the vault", folder));
+    }
+    let title = folder.rsplit('/').next().unwrap_or(folder).to_string();
+    let index = format!("{}/{}.md", folder, title);
+
+    let all_notes: HashSet<String> = markdown_files(vault_path).iter().map(|p| relative_path(vault_path, p)).collect();
+    let mut notes: Vec<PathBuf> = markdown_files(&folder_path);
+    notes.sort();
+
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut entries = 0;
+    for path in notes {
+        let relative = relative_path(vault_path, &path);
+        if relative == index {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+
+        let mut line = format!("- [[{}]]", link_target(&relative, &all_notes));
+        if excerpts && let Some(excerpt) = note_excerpt(&content) {
+            line.push_str(&format!(" — {}", excerpt));
+        }
+        entries += 1;
+
+        let keys = match grouping {
+            None => vec![String::new()],
+            Some(MocGrouping::Folder) => {
+                let inner = &relative[folder.len() + 1..];
+                vec![inner.rsplit_once('/').map(|(dir, _)| dir.to_string()).unwrap_or_default()]
+            }
+            Some(MocGrouping::Tag) => {
+                let tags: BTreeSet<String> = options.note_tags(&content).into_iter().collect();
+                if tags.is_empty() {
+                    vec!["Untagged".to_string()]
+                } else {
+                    tags.into_iter().map(|t| format!("#{}", t)).collect()
+                }
+            }
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(line.clone());
+        }
+    }
+
+    let mut generated = String::new();
+    for (key, lines) in groups {
+        if !key.is_empty() {
+            generated.push_str(&format!("\n## {}\n\n", key));
+        }
+        for line in lines {
+            generated.push_str(&line);
+            generated.push('\n');
+        }
+    }
+
+    write_generated_note(vault_path, &index, &title, &generated, entries, writer)
+}
+
+/// Heading of the section `on-this-day --append` writes to a daily note
+const ON_THIS_DAY_HEADING: &str = "## On this day";
+
+/// Daily notes and other notes from the same calendar date as `date` (default today) in
+/// earlier years. A daily note's date is the one in its name; other notes' is their
+/// frontmatter `created` (or `date`), falling back to the file's creation time. With
+/// `append`, the list is written under an "On this day" heading in the day's daily note,
+/// replacing the section if it is already there.
+pub fn on_this_day(
+    vault_path: &Path,
+    config: &Config,
+    date: Option<&str>,
+    append: bool,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<OnThisDayOutput, String> {
+    let daily_regex = Regex::new(&config.daily_note_pattern).map_err(|e| format!("invalid daily_note_pattern: {}", e))?;
+    let day = match date {
+        Some(date) => parse_date(date).ok_or_else(|| format!("invalid date {:?}, expected YYYY-MM-DD or DD.MM.YYYY", date))?,
+        None => today(),
+    };
+    let (year, month, day_of_month) = civil_from_days(day);
+
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut notes = Vec::new();
+    let mut daily_note = None;
+    for path in files {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let stem = note_stem(&relative);
+        let daily = daily_regex.is_match(stem);
+        let note_day = match daily {
+            true => find_date(stem),
+            false => {
+                let frontmatter = Frontmatter::of(&content);
+                ["created", "date"]
+                    .iter()
+                    .filter_map(|key| frontmatter.get(key).and_then(serde_yaml::Value::as_str))
+                    .find_map(find_date)
+                    .or_else(|| fs::metadata(&path).and_then(|m| m.created()).ok().map(days_since_epoch))
+            }
+        };
+        let Some(note_day) = note_day else {
+            continue;
+        };
+        if daily && note_day == day {
+            daily_note = Some(path.clone());
+            continue;
+        }
+        let (note_year, note_month, note_day_of_month) = civil_from_days(note_day);
+        if (note_month, note_day_of_month) == (month, day_of_month) && note_year < year {
+            notes.push(OnThisDayNote {
+                path: relative,
+                date: format_date(note_day),
+                years_ago: year - note_year,
+                daily,
+            });
+        }
+    }
+    notes.sort_by(|a, b| a.years_ago.cmp(&b.years_ago).then_with(|| a.path.cmp(&b.path)));
+
+    let mut appended = false;
+    if append {
+        let path = daily_note.as_ref().ok_or_else(|| format!("there is no daily note for {}", format_date(day)))?;
+        if !notes.is_empty() {
+            let content = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            let mut section = format!("{}\n\n", ON_THIS_DAY_HEADING);
+            for note in &notes {
+                let plural = if note.years_ago == 1 { "" } else { "s" };
+                section.push_str(&format!("- [[{}]] ({} year{} ago)\n", note_stem(&note.path), note.years_ago, plural));
+            }
+            writer.write(vault_path, path, &replace_section(&content, ON_THIS_DAY_HEADING, &section))?;
+            appended = true;
+        }
+    }
+
+    Ok(OnThisDayOutput {
+        dry_run: writer.dry_run,
+        date: format_date(day),
+        notes,
+        daily_note: daily_note.map(|path| relative_path(vault_path, &path)),
+        appended,
+    })
+}
+
+/// The content with the section under `heading` (up to the next heading of the same or a
+/// higher level) replaced by `section`, or with `section` added at the end.
+fn replace_section(content: &str, heading: &str, section: &str) -> String {
+    let level = heading.chars().take_while(|c| *c == '#').count();
+    let mut lines = content.split_inclusive('\n');
+    let mut before = String::new();
+    for line in lines.by_ref() {
+        if line.trim_end().eq_ignore_ascii_case(heading) {
+            let rest: String = lines
+                .skip_while(|line| {
+                    let hashes = line.chars().take_while(|c| *c == '#').count();
+                    !(1..=level).contains(&hashes) || !line[hashes..].starts_with([' ', '\t'])
+                })
+                .collect();
+            let separator = if rest.is_empty() { "" } else { "\n" };
+            return format!("{}{}{}{}", before, section, separator, rest);
+        }
+        before.push_str(line);
+    }
+    let mut content = content.trim_end().to_string();
+    if !content.is_empty() {
+        content.push_str("\n\n");
+    }
+    content.push_str(section);
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_are_replaced_up_to_the_next_heading() {
+        let section = "## On this day\n\n- [[a]]\n";
+        assert_eq!(replace_section("# Day\ntext", "## On this day", section), "# Day\ntext\n\n## On this day\n\n- [[a]]\n");
+        let content = "# Day\n## on this day\nold\n### Sub\nold\n## Later\nkeep\n";
+        assert_eq!(replace_section(content, "## On this day", section), "# Day\n## On this day\n\n- [[a]]\n\n## Later\nkeep\n");
+        assert_eq!(replace_section("", "## On this day", section), section);
+    }
+
+    #[test]
+    fn on_this_day_dates_german_and_iso_daily_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-on-this-day-{}", std::process::id()));
+        fs::create_dir_all(root.join("Daily")).unwrap();
+        fs::write(root.join("Daily/05.03.2024.md"), "today").unwrap();
+        fs::write(root.join("Daily/05.03.2023.md"), "a year ago").unwrap();
+        fs::write(root.join("Daily/2022-03-05.md"), "two years ago").unwrap();
+        fs::write(root.join("Trip.md"), "---\ncreated: 5.3.2021\n---\n").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = on_this_day(&root, &Config::default(), Some("05.03.2024"), true, &mut writer, &ScanOptions::default()).unwrap();
+        let found: Vec<_> = output.notes.iter().map(|note| (note.path.as_str(), note.date.as_str(), note.daily)).collect();
+        assert_eq!(found, [
+            ("Daily/05.03.2023.md", "2023-03-05", true),
+            ("Daily/2022-03-05.md", "2022-03-05", true),
+            ("Trip.md", "2021-03-05", false),
+        ]);
+        assert_eq!(output.daily_note.as_deref(), Some("Daily/05.03.2024.md"));
+        assert!(fs::read_to_string(root.join("Daily/05.03.2024.md")).unwrap().contains("- [[05.03.2023]] (1 year ago)"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn folder_mocs_group_notes_and_keep_text_outside_the_markers() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-moc-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects/Web")).unwrap();
+        fs::write(root.join("Projects/Web/Site.md"), "#web\nLaunch the [[Home|home page]] redesign\n").unwrap();
+        fs::write(root.join("Projects/Garden.md"), "# Garden\n\nRaised beds\n").unwrap();
+        fs::write(root.join("Projects/Projects.md"), "# Projects\n\nMy own intro\n").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let options = ScanOptions::default();
+        let output = generate_moc(&root, "Projects/", Some(MocGrouping::Tag), true, &mut writer, &options).unwrap();
+        assert_eq!((output.path.as_str(), output.created, output.entries), ("Projects/Projects.md", false, 2));
+        let expected = format!(
+            "# Projects\n\nMy own intro\n\n{}\n\n## #web\n\n- [[Site]] — Launch the home page redesign\n\n## Untagged\n\n- [[Garden]] — Raised beds\n{}\n",
+            GENERATED_START, GENERATED_END
+        );
+        assert_eq!(fs::read_to_string(root.join("Projects/Projects.md")).unwrap(), expected);
+        assert!(!generate_moc(&root, "Projects", Some(MocGrouping::Tag), true, &mut writer, &options).unwrap().changed);
+        assert!(generate_moc(&root, "Missing", None, false, &mut writer, &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
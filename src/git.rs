@@ -0,0 +1,214 @@
+//! Reading a vault's notes as they were at earlier git revisions.
+
+use crate::config::ScanOptions;
+use crate::dates::format_date;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Serialize, Default)]
+struct DayActivity {
+    date: String,
+    commits: usize,
+    notes_changed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words_added: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words_removed: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ActivityOutput {
+    days: Vec<DayActivity>,
+}
+
+pub(crate) fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Path of the vault inside its git repository, e.g. "notes/" or "" for a vault at the repo root.
+pub(crate) fn git_vault_prefix(vault_path: &Path) -> Result<String, String> {
+    Ok(run_git(vault_path, &["rev-parse", "--show-prefix"])?.trim().to_string())
+}
+
+/// Reads every markdown note in the vault as of `revision`, keyed by vault-relative path.
+fn git_notes_at(vault_path: &Path, prefix: &str, revision: &str) -> Result<BTreeMap<String, String>, String> {
+    let listing = run_git(vault_path, &["ls-tree", "-r", "--full-name", "--name-only", revision, "--", "."])?;
+    let paths: Vec<&str> = listing.lines().filter(|p| p.ends_with(".md")).collect();
+    if paths.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    // Stream all blobs through a single cat-file process instead of one git call per note
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(vault_path)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run git: {}", e))?;
+    let mut stdin = child.stdin.take().ok_or("failed to open git stdin")?;
+    let request: String = paths.iter().map(|p| format!("{}:{}\n", revision, p)).collect();
+    let writer = std::thread::spawn(move || stdin.write_all(request.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| format!("failed to read git output: {}", e))?;
+    let _ = writer.join();
+
+    let mut notes = BTreeMap::new();
+    let mut rest: &[u8] = &output.stdout;
+    for path in paths {
+        let header_end = rest.iter().position(|b| *b == b'\n').ok_or("truncated git cat-file output")?;
+        let header = String::from_utf8_lossy(&rest[..header_end]).to_string();
+        rest = &rest[header_end + 1..];
+        if header.ends_with("missing") {
+            continue;
+        }
+        let size: usize = header
+            .rsplit(' ')
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| format!("unexpected git cat-file header: {}", header))?;
+        let content = String::from_utf8_lossy(&rest[..size]).into_owned();
+        rest = &rest[size + 1..];
+        notes.insert(path.strip_prefix(prefix).unwrap_or(path).to_string(), content);
+    }
+    Ok(notes)
+}
+
+/// Notes with ignored ones removed, and private ones unless the scan includes them.
+pub(crate) fn git_visible_notes(vault_path: &Path, prefix: &str, revision: &str, options: &ScanOptions) -> Result<BTreeMap<String, String>, String> {
+    let mut notes = git_notes_at(vault_path, prefix, revision)?;
+    notes.retain(|_, content| !options.hides(content));
+    Ok(notes)
+}
+
+/// Commits and changed notes per day of authoring, from git history. With `words`, also
+/// words added and removed, taken from word diffs with rename detection so moving or
+/// syncing a note doesn't count its words again.
+pub fn git_activity(vault_path: &Path, words: bool) -> Result<ActivityOutput, String> {
+    let mut args = vec!["log", "-M", "--format=%x00%at", "--"];
+    if words {
+        args.splice(1..1, ["-p", "--word-diff=porcelain"]);
+    } else {
+        args.insert(1, "--name-only");
+    }
+    args.push("*.md");
+    let log = run_git(vault_path, &args)?;
+
+    let mut days: BTreeMap<i64, (DayActivity, BTreeSet<String>)> = BTreeMap::new();
+    let mut day = None;
+    let mut in_hunk = false;
+    for line in log.lines() {
+        if let Some(timestamp) = line.strip_prefix('\0') {
+            let key = timestamp.trim().parse::<i64>().map_err(|_| format!("unexpected git log line: {}", line))? / 86_400;
+            days.entry(key).or_default().0.commits += 1;
+            day = Some(key);
+            in_hunk = false;
+            continue;
+        }
+        let Some((activity, notes)) = day.and_then(|key| days.get_mut(&key)) else {
+            continue;
+        };
+        if !words {
+            if !line.is_empty() {
+                notes.insert(line.to_string());
+            }
+            continue;
+        }
+
+        if line.starts_with("diff --git ") {
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if in_hunk {
+            let count = line.get(1..).unwrap_or("").split_whitespace().count();
+            match line.as_bytes().first() {
+                Some(b'+') => *activity.words_added.get_or_insert(0) += count,
+                Some(b'-') => *activity.words_removed.get_or_insert(0) += count,
+                _ => {}
+            }
+        } else if let Some(path) = line
+            .strip_prefix("+++ b/")
+            .or_else(|| line.strip_prefix("--- a/"))
+            .or_else(|| line.strip_prefix("rename to "))
+        {
+            notes.insert(path.to_string());
+        }
+    }
+
+    let days = days
+        .into_iter()
+        .map(|(key, (mut activity, notes))| {
+            activity.date = format_date(key);
+            activity.notes_changed = notes.len();
+            if words {
+                activity.words_added.get_or_insert(0);
+                activity.words_removed.get_or_insert(0);
+            }
+            activity
+        })
+        .collect();
+    Ok(ActivityOutput { days })
+}
+
+/// How many times each current note was renamed, following rename chains through history.
+pub(crate) fn git_rename_counts(vault_path: &Path) -> Result<BTreeMap<String, usize>, String> {
+    let prefix = git_vault_prefix(vault_path)?;
+    let log = run_git(vault_path, &["log", "--reverse", "-M", "--name-status", "--format=", "--", "."])?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in log.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() == 3 && fields[0].starts_with('R') {
+            let old = fields[1].strip_prefix(&prefix).unwrap_or(fields[1]);
+            let new = fields[2].strip_prefix(&prefix).unwrap_or(fields[2]);
+            let previous = counts.remove(old).unwrap_or(0);
+            counts.insert(new.to_string(), previous + 1);
+        }
+    }
+
+    Ok(counts.into_iter().filter(|(path, _)| vault_path.join(path).exists()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn notes_are_read_at_a_revision_relative_to_the_vault() {
+        let repo = std::env::temp_dir().join(format!("obsidian-cli-git-notes-{}", std::process::id()));
+        let vault = repo.join("notes");
+        fs::create_dir_all(vault.join("Projects")).unwrap();
+        let git = |args: &[&str]| run_git(&repo, args).unwrap();
+        git(&["init", "-q"]);
+        fs::write(vault.join("Home.md"), "[[Plan]]").unwrap();
+        fs::write(vault.join("Projects/Plan.md"), "plan").unwrap();
+        fs::write(vault.join("Secret.md"), "#private").unwrap();
+        fs::write(vault.join("image.png"), "png").unwrap();
+        git(&["add", "."]);
+        git(&["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "first"]);
+        fs::write(vault.join("Home.md"), "changed after the commit").unwrap();
+
+        let prefix = git_vault_prefix(&vault).unwrap();
+        assert_eq!(prefix, "notes/");
+        let notes = git_visible_notes(&vault, &prefix, "HEAD", &ScanOptions::default()).unwrap();
+        let notes: Vec<(&str, &str)> = notes.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        assert_eq!(notes, [("Home.md", "[[Plan]]"), ("Projects/Plan.md", "plan")]);
+        let options = ScanOptions { include_private: true, ..ScanOptions::default() };
+        assert!(git_visible_notes(&vault, &prefix, "HEAD", &options).unwrap().contains_key("Secret.md"));
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -0,0 +1,643 @@
+//! The link graph: exports, history through git, and prune suggestions.
+
+use crate::config::{Config, ScanOptions};
+use crate::git::{git_vault_prefix, git_visible_notes, run_git};
+use crate::parse::{
+    extract_link_definitions, extract_link_references, extract_links_from_file, extract_links_with_embeds,
+    extract_markdown_links, split_link_anchor,
+};
+use crate::query;
+use crate::scan::{
+    NoteResolver, hidden_notes, markdown_files, note_aliases, note_stem, read_note, relative_path,
+    resolve_markdown_link,
+};
+use crate::search::note_facts;
+use crate::tags::tag_matches;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Graphml,
+    Gexf,
+    Mermaid,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HistoryFormat {
+    Json,
+    /// One snapshot per line, oldest first
+    Ndjson,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphColoring {
+    Folder,
+    Tag,
+}
+
+#[derive(Serialize)]
+struct PruneEdge {
+    source: String,
+    target: String,
+    reason: &'static str,
+}
+
+#[derive(Serialize)]
+struct HubNote {
+    note: String,
+    incoming: usize,
+    outgoing: usize,
+}
+
+#[derive(Serialize)]
+pub struct PruneSuggestionsOutput {
+    total_edges: usize,
+    prunable_edges: Vec<PruneEdge>,
+    high_fan_out: Vec<HubNote>,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GraphEdge {
+    pub(crate) source: String,
+    pub(crate) target: String,
+}
+
+#[derive(Serialize)]
+pub struct GraphSnapshot {
+    commit: String,
+    timestamp: i64,
+    nodes: Vec<String>,
+    edges: Vec<GraphEdge>,
+}
+
+#[derive(Serialize)]
+pub struct GraphHistoryOutput {
+    pub snapshots: Vec<GraphSnapshot>,
+}
+
+#[derive(Serialize)]
+pub struct GraphDiffOutput {
+    from: String,
+    to: String,
+    nodes_added: Vec<String>,
+    nodes_removed: Vec<String>,
+    edges_added: Vec<GraphEdge>,
+    edges_removed: Vec<GraphEdge>,
+}
+
+pub fn suggest_prune(vault_path: &Path, config: &Config, options: &ScanOptions) -> Result<PruneSuggestionsOutput, String> {
+    let files = markdown_files(vault_path);
+    let hidden = hidden_notes(vault_path, options);
+    let resolvable: HashSet<String> = files.iter().map(|p| relative_path(vault_path, p)).collect();
+    let all_notes: HashSet<String> = resolvable.difference(&hidden).cloned().collect();
+    let resolver = NoteResolver::new(&resolvable).read_aliases(vault_path);
+    let daily_regex = Regex::new(&config.daily_note_pattern)
+        .map_err(|e| format!("invalid daily_note_pattern: {}", e))?;
+    let templates_prefix = format!("{}/", config.templates_folder.trim_end_matches('/'));
+
+    let mut total_edges = 0;
+    let mut prunable_edges = Vec::new();
+    let mut incoming: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let mut outgoing: HashMap<String, BTreeSet<String>> = HashMap::new();
+
+    for path in &files {
+        let Some(content) = read_note(path, options) else {
+            continue;
+        };
+        let source = relative_path(vault_path, path);
+
+        for link in extract_links_from_file(&content) {
+            let (note, _) = split_link_anchor(&link);
+            // [[#Heading]] links point back into the same note
+            let target = if note.is_empty() {
+                Some(source.clone())
+            } else {
+                resolver.resolve(note).cloned()
+            };
+            let Some(target) = target.filter(|t| !hidden.contains(t)) else {
+                continue;
+            };
+            total_edges += 1;
+
+            let reason = if target == source {
+                Some("self_link")
+            } else if config.graph.prune_template_links && source.starts_with(&templates_prefix) {
+                Some("template")
+            } else if config.graph.prune_daily_links && daily_regex.is_match(note_stem(&target)) {
+                Some("daily_note")
+            } else if config.graph.noise_notes.iter().any(|n| n.trim_end_matches(".md") == note_stem(&target)) {
+                Some("noise_note")
+            } else {
+                None
+            };
+
+            match reason {
+                Some(reason) => prunable_edges.push(PruneEdge {
+                    source: source.clone(),
+                    target,
+                    reason,
+                }),
+                None => {
+                    outgoing.entry(source.clone()).or_default().insert(target.clone());
+                    incoming.entry(target).or_default().insert(source.clone());
+                }
+            }
+        }
+    }
+
+    // Hubs are judged on the graph that remains after noisy edges are pruned
+    let mut high_fan_out: Vec<HubNote> = all_notes
+        .iter()
+        .map(|note| HubNote {
+            note: note.clone(),
+            incoming: incoming.get(note).map_or(0, |s| s.len()),
+            outgoing: outgoing.get(note).map_or(0, |s| s.len()),
+        })
+        .filter(|hub| hub.incoming.max(hub.outgoing) > config.graph.max_fan_out)
+        .collect();
+    high_fan_out.sort_by(|a, b| {
+        (b.incoming + b.outgoing)
+            .cmp(&(a.incoming + a.outgoing))
+            .then_with(|| a.note.cmp(&b.note))
+    });
+    prunable_edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+    Ok(PruneSuggestionsOutput {
+        total_edges,
+        prunable_edges,
+        high_fan_out,
+    })
+}
+
+/// Resolves the wikilinks of in-memory notes into unique source -> target edges.
+fn resolve_graph_edges(notes: &BTreeMap<String, String>) -> Vec<GraphEdge> {
+    let mut resolver = NoteResolver::new(notes.keys());
+    for (note, content) in notes {
+        resolver.add_aliases(note, note_aliases(content));
+    }
+    let mut edges = BTreeSet::new();
+    for (source, content) in notes {
+        for link in extract_links_from_file(content) {
+            let (note, _) = split_link_anchor(&link);
+            if note.is_empty() {
+                continue;
+            }
+            if let Some(target) = resolver.resolve(note) {
+                edges.insert((source.clone(), target.clone()));
+            }
+        }
+    }
+    edges
+        .into_iter()
+        .map(|(source, target)| GraphEdge { source, target })
+        .collect()
+}
+
+pub(crate) struct GraphNode {
+    pub(crate) words: usize,
+    tags: Vec<String>,
+}
+
+/// The vault's notes and the resolved links between them, for exporting to graph tools.
+pub struct LinkGraph {
+    pub(crate) nodes: BTreeMap<String, GraphNode>,
+    /// Number of links of a type ("link", "embed" or "reference") from the first note to the second
+    pub(crate) edges: BTreeMap<(String, String, &'static str), usize>,
+}
+
+impl GraphNode {
+    fn folder(path: &str) -> &str {
+        path.rsplit_once('/').map_or("", |(folder, _)| folder)
+    }
+}
+
+pub fn link_graph(vault_path: &Path, exclude_orphans: bool, options: &ScanOptions) -> LinkGraph {
+    let mut contents = BTreeMap::new();
+    for path in markdown_files(vault_path) {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        if let Some(content) = read_note(&path, options) {
+            contents.insert(relative, content);
+        }
+    }
+    let mut resolver = NoteResolver::new(contents.keys());
+    for (note, content) in &contents {
+        resolver.add_aliases(note, note_aliases(content));
+    }
+
+    let mut nodes = BTreeMap::new();
+    let mut edges = BTreeMap::new();
+    for (source, content) in contents {
+        for (link, embed) in extract_links_with_embeds(&content) {
+            let (note, _) = split_link_anchor(&link);
+            if note.is_empty() {
+                continue;
+            }
+            if let Some(target) = resolver.resolve(note).cloned() {
+                let kind = if embed { "embed" } else { "link" };
+                *edges.entry((source.clone(), target, kind)).or_insert(0) += 1;
+            }
+        }
+        for (destination, embed) in extract_markdown_links(&content) {
+            if let Some(target) = resolve_markdown_link(&source, &destination, &resolver) {
+                let kind = if embed { "embed" } else { "link" };
+                *edges.entry((source.clone(), target, kind)).or_insert(0) += 1;
+            }
+        }
+        let definitions: HashMap<String, String> = extract_link_definitions(&content)
+            .into_iter()
+            .filter(|definition| !definition.footnote)
+            .map(|definition| (definition.label, definition.destination))
+            .collect();
+        for reference in extract_link_references(&content) {
+            if let Some(target) = definitions
+                .get(&reference.label)
+                .filter(|_| !reference.footnote)
+                .and_then(|destination| resolve_markdown_link(&source, destination, &resolver))
+            {
+                *edges.entry((source.clone(), target, "reference")).or_insert(0) += 1;
+            }
+        }
+        let mut tags = options.note_tags(&content);
+        tags.sort();
+        tags.dedup();
+        nodes.insert(source, GraphNode { words: content.split_whitespace().count(), tags });
+    }
+
+    if exclude_orphans {
+        let linked: HashSet<&String> = edges.keys().flat_map(|(source, target, _)| [source, target]).collect();
+        nodes.retain(|note, _| linked.contains(note));
+    }
+    LinkGraph { nodes, edges }
+}
+
+const GRAPH_PALETTE: &[&str] = &[
+    "lightblue", "palegreen", "lightsalmon", "plum", "khaki", "lightpink", "aquamarine", "wheat", "lightsteelblue", "thistle",
+];
+
+/// Graphviz DOT for the link graph, labelling notes by name. With `coloring`, notes are
+/// filled with one color per folder or per first tag (untagged notes stay white).
+pub fn render_dot(graph: &LinkGraph, coloring: Option<GraphColoring>) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let group = |path: &str, node: &GraphNode| -> Option<String> {
+        match coloring? {
+            GraphColoring::Folder => Some(GraphNode::folder(path).to_string()),
+            GraphColoring::Tag => node.tags.first().cloned(),
+        }
+    };
+    let groups: BTreeSet<String> = graph.nodes.iter().filter_map(|(path, node)| group(path, node)).collect();
+    let color_of = |name: &str| groups.iter().position(|g| g == name).map(|i| GRAPH_PALETTE[i % GRAPH_PALETTE.len()]);
+
+    let mut out = String::from("digraph vault {\n");
+    if coloring.is_some() {
+        out.push_str("  node [style=filled, fillcolor=white];\n");
+    }
+    for (path, node) in &graph.nodes {
+        let mut attributes = vec![format!("label={}", quote(note_stem(path)))];
+        if let Some(color) = group(path, node).and_then(|name| color_of(&name)) {
+            attributes.push(format!("fillcolor={}", color));
+        }
+        out.push_str(&format!("  {} [{}];\n", quote(path), attributes.join(", ")));
+    }
+    let pairs: BTreeSet<(&String, &String)> = graph.edges().map(|(source, target, _, _)| (source, target)).collect();
+    for (source, target) in pairs {
+        out.push_str(&format!("  {} -> {};\n", quote(source), quote(target)));
+    }
+    out.push('}');
+    out
+}
+
+impl LinkGraph {
+    /// Keeps only the notes within `depth` links of `note`, following links in either direction.
+    pub fn restrict_to_neighborhood(&mut self, note: &str, depth: usize) -> Result<(), String> {
+        let center = NoteResolver::new(self.nodes.keys())
+            .resolve(note)
+            .cloned()
+            .ok_or_else(|| format!("no note named {} in the vault", note))?;
+        self.keep_within(vec![center], depth);
+        Ok(())
+    }
+
+    /// Keeps only the notes with a tag matching `tag` and for which `condition` (written as
+    /// in a query's WHERE) holds. With `neighbors`, notes linked to or from one of them stay too.
+    pub fn restrict_to_matching(
+        &mut self,
+        vault_path: &Path,
+        tag: Option<&str>,
+        condition: Option<&str>,
+        neighbors: bool,
+        options: &ScanOptions,
+    ) -> Result<(), String> {
+        let condition = condition.map(query::parse_condition).transpose()?;
+        let matching: Vec<String> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| tag.is_none_or(|pattern| node.tags.iter().any(|t| tag_matches(pattern, t, false))))
+            .filter(|(note, _)| {
+                condition.as_ref().is_none_or(|condition| {
+                    let path = vault_path.join(note);
+                    read_note(&path, options).is_some_and(|content| condition.holds(&note_facts(&path, note.to_string(), &content, options)))
+                })
+            })
+            .map(|(note, _)| note.clone())
+            .collect();
+        self.keep_within(matching, usize::from(neighbors));
+        Ok(())
+    }
+
+    /// Keeps only the notes within `depth` links of one of `notes`, in either direction.
+    fn keep_within(&mut self, notes: Vec<String>, depth: usize) {
+        let mut neighbors: HashMap<&String, Vec<&String>> = HashMap::new();
+        for (source, target, _) in self.edges.keys() {
+            neighbors.entry(source).or_default().push(target);
+            neighbors.entry(target).or_default().push(source);
+        }
+
+        let mut reached: HashSet<String> = notes.iter().cloned().collect();
+        let mut frontier = notes;
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for note in &frontier {
+                for neighbor in neighbors.get(note).into_iter().flatten() {
+                    if reached.insert((*neighbor).clone()) {
+                        next.push((*neighbor).clone());
+                    }
+                }
+            }
+            frontier = next;
+        }
+        self.nodes.retain(|note, _| reached.contains(note));
+    }
+
+    /// Edges between notes still in the graph, as (source, target, type, count).
+    fn edges(&self) -> impl Iterator<Item = (&String, &String, &'static str, usize)> {
+        self.edges
+            .iter()
+            .filter(|((source, target, _), _)| self.nodes.contains_key(source) && self.nodes.contains_key(target))
+            .map(|((source, target, kind), count)| (source, target, *kind, *count))
+    }
+}
+
+/// A Mermaid flowchart of the graph, for pasting into a note. Embeds are dotted arrows and
+/// `highlight`, if given, is drawn with a thick border.
+pub fn render_mermaid(graph: &LinkGraph, highlight: Option<&str>) -> String {
+    let ids: HashMap<&String, String> = graph.nodes.keys().enumerate().map(|(i, path)| (path, format!("n{}", i))).collect();
+    let mut out = String::from("flowchart LR\n");
+    for path in graph.nodes.keys() {
+        out.push_str(&format!("    {}[\"{}\"]\n", ids[path], note_stem(path).replace('"', "#quot;")));
+    }
+    for (source, target, kind, _) in graph.edges() {
+        let arrow = if kind == "embed" { "-.->" } else { "-->" };
+        out.push_str(&format!("    {} {} {}\n", ids[source], arrow, ids[target]));
+    }
+    let center = highlight.and_then(|note| NoteResolver::new(graph.nodes.keys()).resolve(note).cloned());
+    if let Some(id) = center.and_then(|path| ids.get(&path)) {
+        out.push_str(&format!("    style {} stroke-width:3px\n", id));
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// GraphML for Gephi, Cytoscape and yEd: notes carry their folder, word count and tags
+/// (comma separated), links their type and how many times they occur.
+pub fn render_graphml(graph: &LinkGraph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"folder\" for=\"node\" attr.name=\"folder\" attr.type=\"string\"/>\n",
+        "  <key id=\"word_count\" for=\"node\" attr.name=\"word_count\" attr.type=\"int\"/>\n",
+        "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+        "  <key id=\"type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n",
+        "  <key id=\"count\" for=\"edge\" attr.name=\"count\" attr.type=\"int\"/>\n",
+        "  <graph id=\"vault\" edgedefault=\"directed\">\n",
+    ));
+    for (path, node) in &graph.nodes {
+        out.push_str(&format!("    <node id=\"{}\">\n", xml_escape(path)));
+        out.push_str(&format!("      <data key=\"label\">{}</data>\n", xml_escape(note_stem(path))));
+        out.push_str(&format!("      <data key=\"folder\">{}</data>\n", xml_escape(GraphNode::folder(path))));
+        out.push_str(&format!("      <data key=\"word_count\">{}</data>\n", node.words));
+        out.push_str(&format!("      <data key=\"tags\">{}</data>\n", xml_escape(&node.tags.join(", "))));
+        out.push_str("    </node>\n");
+    }
+    for (source, target, kind, count) in graph.edges() {
+        out.push_str(&format!("    <edge source=\"{}\" target=\"{}\">\n", xml_escape(source), xml_escape(target)));
+        out.push_str(&format!("      <data key=\"type\">{}</data>\n", kind));
+        out.push_str(&format!("      <data key=\"count\">{}</data>\n", count));
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>");
+    out
+}
+
+/// GEXF 1.2 for Gephi, with the same attributes as [`render_graphml`]. Tags are a
+/// liststring and the link count is also the edge weight.
+pub fn render_gexf(graph: &LinkGraph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">\n",
+        "  <graph mode=\"static\" defaultedgetype=\"directed\">\n",
+        "    <attributes class=\"node\">\n",
+        "      <attribute id=\"folder\" title=\"folder\" type=\"string\"/>\n",
+        "      <attribute id=\"word_count\" title=\"word_count\" type=\"integer\"/>\n",
+        "      <attribute id=\"tags\" title=\"tags\" type=\"liststring\"/>\n",
+        "    </attributes>\n",
+        "    <attributes class=\"edge\">\n",
+        "      <attribute id=\"type\" title=\"type\" type=\"string\"/>\n",
+        "      <attribute id=\"count\" title=\"count\" type=\"integer\"/>\n",
+        "    </attributes>\n",
+        "    <nodes>\n",
+    ));
+    for (path, node) in &graph.nodes {
+        out.push_str(&format!("      <node id=\"{}\" label=\"{}\">\n", xml_escape(path), xml_escape(note_stem(path))));
+        out.push_str("        <attvalues>\n");
+        out.push_str(&format!("          <attvalue for=\"folder\" value=\"{}\"/>\n", xml_escape(GraphNode::folder(path))));
+        out.push_str(&format!("          <attvalue for=\"word_count\" value=\"{}\"/>\n", node.words));
+        out.push_str(&format!("          <attvalue for=\"tags\" value=\"{}\"/>\n", xml_escape(&node.tags.join("|"))));
+        out.push_str("        </attvalues>\n      </node>\n");
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (id, (source, target, kind, count)) in graph.edges().enumerate() {
+        out.push_str(&format!(
+            "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\">\n",
+            id,
+            xml_escape(source),
+            xml_escape(target),
+            count
+        ));
+        out.push_str("        <attvalues>\n");
+        out.push_str(&format!("          <attvalue for=\"type\" value=\"{}\"/>\n", kind));
+        out.push_str(&format!("          <attvalue for=\"count\" value=\"{}\"/>\n", count));
+        out.push_str("        </attvalues>\n      </edge>\n");
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>");
+    out
+}
+
+pub fn graph_history(vault_path: &Path, snapshots: usize, options: &ScanOptions) -> Result<Vec<GraphSnapshot>, String> {
+    let prefix = git_vault_prefix(vault_path)?;
+    let log = run_git(vault_path, &["log", "--reverse", "--format=%H %ct", "--", "."])?;
+    let commits: Vec<(&str, i64)> = log
+        .lines()
+        .filter_map(|line| {
+            let (hash, time) = line.split_once(' ')?;
+            Some((hash, time.parse().ok()?))
+        })
+        .collect();
+    if commits.is_empty() || snapshots == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Evenly spaced picks that always include the first and latest commit
+    let count = snapshots.min(commits.len());
+    let picks: BTreeSet<usize> = (0..count)
+        .map(|i| if count == 1 { commits.len() - 1 } else { i * (commits.len() - 1) / (count - 1) })
+        .collect();
+
+    let mut result = Vec::new();
+    for index in picks {
+        let (commit, timestamp) = commits[index];
+        let notes = git_visible_notes(vault_path, &prefix, commit, options)?;
+        result.push(GraphSnapshot {
+            commit: commit.to_string(),
+            timestamp,
+            edges: resolve_graph_edges(&notes),
+            nodes: notes.into_keys().collect(),
+        });
+    }
+    Ok(result)
+}
+
+/// Compares the link graph at two revisions: notes and edges present in only one of them.
+pub fn graph_diff(vault_path: &Path, from: &str, to: &str, options: &ScanOptions) -> Result<GraphDiffOutput, String> {
+    let prefix = git_vault_prefix(vault_path)?;
+    let before = git_visible_notes(vault_path, &prefix, from, options)?;
+    let after = git_visible_notes(vault_path, &prefix, to, options)?;
+
+    let before_edges: BTreeSet<GraphEdge> = resolve_graph_edges(&before).into_iter().collect();
+    let mut after_edges: BTreeSet<GraphEdge> = resolve_graph_edges(&after).into_iter().collect();
+    let mut edges_removed = Vec::new();
+    for edge in before_edges {
+        if !after_edges.remove(&edge) {
+            edges_removed.push(edge);
+        }
+    }
+
+    Ok(GraphDiffOutput {
+        from: from.to_string(),
+        to: to.to_string(),
+        nodes_added: after.keys().filter(|n| !before.contains_key(*n)).cloned().collect(),
+        nodes_removed: before.keys().filter(|n| !after.contains_key(*n)).cloned().collect(),
+        edges_added: after_edges.into_iter().collect(),
+        edges_removed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::load_config;
+    use std::fs;
+
+    #[test]
+    fn graph_can_be_scoped_to_matching_notes_and_their_neighbors() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-graph-scope-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Alpha.md"), "---\nstatus: active\n---\n#project/web [[Notes]]\n").unwrap();
+        fs::write(root.join("Beta.md"), "#project/app\n").unwrap();
+        fs::write(root.join("Notes.md"), "[[Other]]\n").unwrap();
+        fs::write(root.join("Other.md"), "#area\n").unwrap();
+
+        let options = ScanOptions::default();
+        let scoped = |tag, condition, neighbors| {
+            let mut graph = link_graph(&root, false, &options);
+            graph.restrict_to_matching(&root, tag, condition, neighbors, &options).unwrap();
+            graph.nodes.into_keys().collect::<Vec<_>>()
+        };
+        assert_eq!(scoped(Some("project/*"), None, false), ["Alpha.md", "Beta.md"]);
+        assert_eq!(scoped(Some("project/*"), None, true), ["Alpha.md", "Beta.md", "Notes.md"]);
+        assert_eq!(scoped(Some("project/*"), Some("status = 'active'"), false), ["Alpha.md"]);
+        assert!(link_graph(&root, false, &options).restrict_to_matching(&root, None, Some("status ="), false, &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn prune_suggestions_follow_the_graph_config() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-graph-prune-{}", std::process::id()));
+        fs::create_dir_all(root.join("Templates")).unwrap();
+        fs::write(root.join(".obsidian-cli.toml"), "[graph]\nmax_fan_out = 1\nnoise_notes = [\"Index\"]\n").unwrap();
+        fs::write(root.join("Templates/Meeting.md"), "[[Hub]]\n").unwrap();
+        fs::write(root.join("A.md"), "[[Hub]] [[2024-01-01]] [[Index]] [[#Top]]\n").unwrap();
+        fs::write(root.join("B.md"), "[[Hub]]\n").unwrap();
+        fs::write(root.join("Hub.md"), "").unwrap();
+        fs::write(root.join("Index.md"), "").unwrap();
+        fs::write(root.join("2024-01-01.md"), "").unwrap();
+
+        let config = load_config(&root, None).unwrap();
+        let output = suggest_prune(&root, &config, &ScanOptions::from_config(&config)).unwrap();
+        assert_eq!(output.total_edges, 6);
+        let prunable: Vec<(&str, &str, &str)> =
+            output.prunable_edges.iter().map(|e| (e.source.as_str(), e.target.as_str(), e.reason)).collect();
+        assert_eq!(
+            prunable,
+            [("A.md", "2024-01-01.md", "daily_note"), ("A.md", "A.md", "self_link"), ("A.md", "Index.md", "noise_note"), ("Templates/Meeting.md", "Hub.md", "template")]
+        );
+        let hubs: Vec<(&str, usize)> = output.high_fan_out.iter().map(|h| (h.note.as_str(), h.incoming)).collect();
+        assert_eq!(hubs, [("Hub.md", 2)]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn graph_history_snapshots_and_diffs_commits() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-graph-history-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let git = |args: &[&str]| run_git(&root, args).unwrap();
+        let commit = |message: &str| {
+            git(&["add", "-A"]);
+            git(&["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-q", "-m", message]);
+        };
+        git(&["init", "-q"]);
+        fs::write(root.join("A.md"), "[[B]]").unwrap();
+        fs::write(root.join("B.md"), "").unwrap();
+        commit("first");
+        fs::write(root.join("C.md"), "[[A]]").unwrap();
+        commit("second");
+        fs::write(root.join("A.md"), "[[C]]").unwrap();
+        fs::remove_file(root.join("B.md")).unwrap();
+        commit("third");
+
+        let options = ScanOptions::default();
+        let edges = |snapshot: &GraphSnapshot| snapshot.edges.iter().map(|e| format!("{}>{}", e.source, e.target)).collect::<Vec<_>>();
+        let history = graph_history(&root, 2, &options).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].nodes, ["A.md", "B.md"]);
+        assert_eq!(edges(&history[0]), ["A.md>B.md"]);
+        assert_eq!(edges(&history[1]), ["A.md>C.md", "C.md>A.md"]);
+        assert_eq!(graph_history(&root, 10, &options).unwrap().len(), 3);
+
+        let diff = graph_diff(&root, "HEAD~2", "HEAD", &options).unwrap();
+        assert_eq!(diff.nodes_added, ["C.md"]);
+        assert_eq!(diff.nodes_removed, ["B.md"]);
+        assert_eq!(diff.edges_added.len(), 2);
+        assert_eq!((diff.edges_removed[0].source.as_str(), diff.edges_removed[0].target.as_str()), ("A.md", "B.md"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,373 @@
+//! Checks meant to run unattended: the git pre-commit hook, watch guards and `doctor`.
+
+use crate::config::{Config, ScanOptions};
+use crate::git::run_git;
+use crate::parse::{Frontmatter, extract_frontmatter, extract_links_from_file, split_link_anchor};
+use crate::scan::{NoteResolver, markdown_files, note_aliases, relative_path, walk_vault};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+#[derive(Serialize)]
+struct Diagnostic {
+    path: String,
+    kind: &'static str,
+    detail: String,
+    impact: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct DoctorOutput {
+    scanned: usize,
+    problems: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+pub struct HookProblem {
+    pub path: String,
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct HookOutput {
+    pub files_checked: usize,
+    pub problems: Vec<HookProblem>,
+}
+
+/// Lines longer than this are almost always pasted data that slows every regex scan
+const LONG_LINE_CHARS: usize = 10_000;
+
+fn diagnose_note(content: &str) -> Vec<(&'static str, String, &'static str)> {
+    let mut problems = Vec::new();
+
+    if content.starts_with("---\r\n") {
+        problems.push((
+            "crlf_frontmatter",
+            "frontmatter uses Windows line endings".to_string(),
+            "frontmatter is ignored, so its tags are not counted",
+        ));
+    } else if content.starts_with("---\n") && extract_frontmatter(content).is_none() {
+        let detail = if content[4..].lines().any(|l| l == "---") {
+            "closing --- is the last line without a trailing newline"
+        } else {
+            "no closing --- line"
+        };
+        problems.push((
+            "unterminated_frontmatter",
+            detail.to_string(),
+            "frontmatter is ignored, so its tags are not counted",
+        ));
+    }
+
+    for (index, line) in content.lines().enumerate() {
+        let length = line.chars().count();
+        if length > LONG_LINE_CHARS {
+            problems.push((
+                "long_line",
+                format!("line {} is {} characters long", index + 1, length),
+                "note is still analyzed, but scanning it is slow",
+            ));
+        }
+    }
+
+    problems
+}
+
+pub fn run_doctor(vault_path: &Path) -> Result<DoctorOutput, String> {
+    let mut scanned = 0;
+    let mut problems = Vec::new();
+
+    for entry in walk_vault(vault_path) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().map(|p| relative_path(vault_path, p)).unwrap_or_default();
+                problems.push(Diagnostic {
+                    path,
+                    kind: "walk_error",
+                    detail: e.to_string(),
+                    impact: "path and everything below it is skipped by all commands",
+                });
+                continue;
+            }
+        };
+        let path = entry.path();
+        if !(path.is_file() && path.extension().is_some_and(|ext| ext == "md")) {
+            continue;
+        }
+        scanned += 1;
+        let relative = relative_path(vault_path, path);
+
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                problems.push(Diagnostic {
+                    path: relative,
+                    kind: "read_error",
+                    detail: e.to_string(),
+                    impact: "note is skipped by all commands",
+                });
+                continue;
+            }
+        };
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => {
+                problems.push(Diagnostic {
+                    path: relative,
+                    kind: "invalid_utf8",
+                    detail: format!("invalid UTF-8 at byte {}", e.utf8_error().valid_up_to()),
+                    impact: "note is skipped by all commands",
+                });
+                continue;
+            }
+        };
+
+        for (kind, detail, impact) in diagnose_note(&content) {
+            problems.push(Diagnostic {
+                path: relative.clone(),
+                kind,
+                detail,
+                impact,
+            });
+        }
+    }
+
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(DoctorOutput { scanned, problems })
+}
+
+/// Broken link targets of a note, resolved against `all_notes`.
+pub(crate) fn broken_link_targets(content: &str, resolver: &NoteResolver, options: &ScanOptions) -> BTreeSet<String> {
+    extract_links_from_file(content)
+        .into_iter()
+        .filter(|link| {
+            let (note, _) = split_link_anchor(link);
+            !note.is_empty() && !options.is_placeholder(note) && resolver.resolve(note).is_none()
+        })
+        .collect()
+}
+
+/// Checks only what is staged, reading note content from the git index rather than the
+/// working tree so partially staged files are judged on what will actually be committed.
+/// Names are checked against the `[naming]` rules for notes added or renamed by the commit.
+pub fn run_pre_commit(vault_path: &Path, config: &Config, options: &ScanOptions) -> Result<HookOutput, String> {
+    let staged = run_git(vault_path, &["diff", "--cached", "--name-only", "--diff-filter=ACMR", "--relative", "--", "."])?;
+    let staged: Vec<&str> = staged.lines().filter(|p| p.ends_with(".md")).collect();
+
+    let indexed = run_git(vault_path, &["ls-files", "--cached", "--", "."])?;
+    let resolver = NoteResolver::new(indexed.lines().filter(|p| p.ends_with(".md")));
+
+    let mut problems = Vec::new();
+    for path in &staged {
+        let content = run_git(vault_path, &["show", &format!(":./{}", path)])?;
+
+        // Only links that were not already broken in HEAD count as introduced
+        let previous = run_git(vault_path, &["show", &format!("HEAD:./{}", path)]).ok();
+        let previously_broken = previous
+            .as_deref()
+            .map(|old| broken_link_targets(old, &resolver, options))
+            .unwrap_or_default();
+        for target in broken_link_targets(&content, &resolver, options) {
+            if !previously_broken.contains(&target) {
+                problems.push(HookProblem {
+                    path: path.to_string(),
+                    kind: "broken_link",
+                    detail: format!("[[{}]] does not resolve to a note", target),
+                });
+            }
+        }
+
+        for (kind, detail, _) in diagnose_note(&content) {
+            if kind != "long_line" {
+                problems.push(HookProblem {
+                    path: path.to_string(),
+                    kind,
+                    detail,
+                });
+            }
+        }
+
+        if previous.is_none() && !options.hides(&content) {
+            let (rules, fixed) = config.naming.check_note(vault_path, path);
+            if !rules.is_empty() {
+                problems.push(HookProblem {
+                    path: path.to_string(),
+                    kind: "naming",
+                    detail: format!("name breaks the [naming] rules ({}); rename to {}.md", rules.join(", "), fixed),
+                });
+            }
+        }
+    }
+
+    Ok(HookOutput {
+        files_checked: staged.len(),
+        problems,
+    })
+}
+
+/// A note's broken links and frontmatter problems, as `guard` reports them.
+fn guarded_problems(content: &str, resolver: &NoteResolver, options: &ScanOptions) -> BTreeSet<(&'static str, String)> {
+    let mut problems: BTreeSet<(&'static str, String)> = broken_link_targets(content, resolver, options)
+        .into_iter()
+        .map(|target| ("broken_link", format!("[[{}]] does not resolve to a note", target)))
+        .collect();
+    for (kind, detail, _) in diagnose_note(content) {
+        if kind != "long_line" {
+            problems.insert((kind, detail));
+        }
+    }
+    if let Some(frontmatter) = extract_frontmatter(content)
+        && let Err(e) = Frontmatter::parse(&frontmatter)
+    {
+        problems.insert(("invalid_frontmatter", format!("frontmatter is not valid YAML: {}", e)));
+    }
+    problems
+}
+
+/// Watches for saves that introduce problems into notes. Each check re-reads only notes
+/// whose size or modification time changed and reports the problems they didn't have
+/// before, so notes that were already broken don't repeat on every save.
+pub struct Guard {
+    seen: HashMap<PathBuf, (SystemTime, u64)>,
+    problems: HashMap<String, BTreeSet<(&'static str, String)>>,
+    /// Each note's aliases, so links to them aren't reported as broken
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Guard {
+    /// Starts from the vault as it is now, taking its existing problems as known.
+    pub fn new(vault_path: &Path, options: &ScanOptions) -> Guard {
+        let mut guard = Guard {
+            seen: HashMap::new(),
+            problems: HashMap::new(),
+            aliases: BTreeMap::new(),
+        };
+        guard.check(vault_path, options);
+        guard
+    }
+
+    /// Problems introduced by the notes saved since the last check.
+    pub fn check(&mut self, vault_path: &Path, options: &ScanOptions) -> Vec<HookProblem> {
+        let paths = markdown_files(vault_path);
+        let mut seen = HashMap::new();
+        let mut changed = Vec::new();
+        for path in &paths {
+            let Some(stamp) = fs::metadata(path).ok().and_then(|m| Some((m.modified().ok()?, m.len()))) else {
+                continue;
+            };
+            if self.seen.get(path) != Some(&stamp) {
+                let content = fs::read_to_string(path).unwrap_or_default();
+                let relative = relative_path(vault_path, path);
+                self.aliases.insert(relative.clone(), note_aliases(&content));
+                changed.push((relative, content));
+            }
+            seen.insert(path.clone(), stamp);
+        }
+        self.aliases.retain(|note, _| seen.contains_key(&vault_path.join(note)));
+
+        let mut resolver = NoteResolver::new(paths.iter().map(|p| relative_path(vault_path, p)));
+        for (note, aliases) in &self.aliases {
+            resolver.add_aliases(note, aliases.clone());
+        }
+        let mut introduced = Vec::new();
+        for (relative, content) in changed {
+            let problems = if options.hides(&content) {
+                BTreeSet::new()
+            } else {
+                guarded_problems(&content, &resolver, options)
+            };
+            let known = self.problems.remove(&relative).unwrap_or_default();
+            introduced.extend(problems.difference(&known).map(|(kind, detail)| HookProblem {
+                path: relative.clone(),
+                kind,
+                detail: detail.clone(),
+            }));
+            self.problems.insert(relative, problems);
+        }
+        self.problems.retain(|note, _| seen.contains_key(&vault_path.join(note)));
+        self.seen = seen;
+        introduced
+    }
+}
+
+/// Shows a desktop notification with `notify-send`, or `osascript` on macOS.
+pub fn notify_desktop(title: &str, message: &str) -> Result<(), String> {
+    let status = if cfg!(target_os = "macos") {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!("display notification {} with title {}", quote(message), quote(title)))
+            .status()
+    } else {
+        Command::new("notify-send").args(["--app-name=obsidian-cli", title, message]).status()
+    };
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("notification command failed with {}", status)),
+        Err(e) => Err(format!("failed to send a desktop notification: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doctor_reports_unreadable_and_malformed_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-doctor-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Binary.md"), b"caf\xe9").unwrap();
+        fs::write(root.join("Crlf.md"), "---\r\ntags: [a]\r\n---\r\n").unwrap();
+        fs::write(root.join("Fine.md"), "---\ntags: [a]\n---\nbody\n").unwrap();
+        fs::write(root.join("Open.md"), "---\ntags: [a]\nbody\n").unwrap();
+        fs::write(root.join("Wide.md"), format!("ok\n{}\n", "x".repeat(LONG_LINE_CHARS + 1))).unwrap();
+
+        let output = run_doctor(&root).unwrap();
+        assert_eq!(output.scanned, 5);
+        let problems: Vec<(&str, &str, &str)> = output.problems.iter().map(|p| (p.path.as_str(), p.kind, p.detail.as_str())).collect();
+        assert_eq!(
+            problems,
+            [
+                ("Binary.md", "invalid_utf8", "invalid UTF-8 at byte 3"),
+                ("Crlf.md", "crlf_frontmatter", "frontmatter uses Windows line endings"),
+                ("Open.md", "unterminated_frontmatter", "no closing --- line"),
+                ("Wide.md", "long_line", "line 2 is 10001 characters long"),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn pre_commit_checks_only_what_the_commit_introduces() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-pre-commit-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let git = |args: &[&str]| run_git(&root, args).unwrap();
+        git(&["init", "-q"]);
+        fs::write(root.join("Home.md"), "[[Old]]\n").unwrap();
+        fs::write(root.join("Plan.md"), "").unwrap();
+        git(&["add", "-A"]);
+        git(&["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "first"]);
+
+        fs::write(root.join("Home.md"), "[[Old]] [[Plan]] [[Missing]]\n").unwrap();
+        fs::write(root.join("new idea.md"), "---\ntags: [a]\n").unwrap();
+        git(&["add", "-A"]);
+        // Only the staged content counts, not later edits in the working tree
+        fs::write(root.join("Home.md"), "[[Old]] [[Plan]] [[Missing]] [[Unstaged]]\n").unwrap();
+
+        let mut config = Config::default();
+        config.naming.no_spaces = true;
+        let output = run_pre_commit(&root, &config, &ScanOptions::default()).unwrap();
+        assert_eq!(output.files_checked, 2);
+        let problems: Vec<(&str, &str)> = output.problems.iter().map(|p| (p.path.as_str(), p.kind)).collect();
+        assert_eq!(problems, [("Home.md", "broken_link"), ("new idea.md", "unterminated_frontmatter"), ("new idea.md", "naming")]);
+        assert_eq!(output.problems[0].detail, "[[Missing]] does not resolve to a note");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
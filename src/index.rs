@@ -0,0 +1,358 @@
+//! The on-disk note index behind `cache` and `whats-new`.
+
+use crate::config::ScanOptions;
+use crate::dates::unix_now;
+use crate::graph::GraphEdge;
+use crate::parse::{Frontmatter, extract_links_from_file, extract_tags_from_file, split_link_anchor};
+use crate::scan::{NoteResolver, is_ignored_note, is_private_note, markdown_files, relative_path};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CacheAction {
+    Status,
+    Warm,
+    Clear,
+}
+
+/// Bump whenever the shape or meaning of cached entries changes.
+const INDEX_VERSION: u32 = 4;
+
+/// Per-note facts cached between runs, keyed by vault-relative path.
+#[derive(Serialize, Deserialize, Default)]
+pub(crate) struct NoteIndex {
+    version: u32,
+    /// Tag syntax and ignore key the cached entries were read with
+    tag_syntax: String,
+    pub(crate) notes: BTreeMap<String, IndexedNote>,
+    hits: u64,
+    misses: u64,
+    last_warmed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct IndexedNote {
+    pub(crate) modified: SystemTime,
+    size: u64,
+    pub(crate) word_count: usize,
+    pub(crate) links: Vec<String>,
+    pub(crate) tags: Vec<String>,
+    aliases: Vec<String>,
+    private: bool,
+    ignored: bool,
+}
+
+impl IndexedNote {
+    pub(crate) fn hidden(&self, options: &ScanOptions) -> bool {
+        self.ignored || (self.private && !options.include_private)
+    }
+}
+
+#[derive(Serialize)]
+pub struct CacheStatusOutput {
+    path: Option<String>,
+    exists: bool,
+    size_bytes: u64,
+    entries: usize,
+    stale_entries: usize,
+    uncached_notes: usize,
+    deleted_entries: usize,
+    hits: u64,
+    misses: u64,
+    hit_rate: Option<f64>,
+    last_warmed: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct TagChange {
+    note: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct WhatsNewOutput {
+    /// False when there was no earlier index to compare with, so nothing is reported yet
+    has_baseline: bool,
+    new_notes: Vec<String>,
+    deleted_notes: Vec<String>,
+    tag_changes: Vec<TagChange>,
+    links_added: Vec<GraphEdge>,
+    links_removed: Vec<GraphEdge>,
+    /// Links that don't resolve now but did, or didn't exist, before
+    newly_broken: Vec<GraphEdge>,
+}
+
+/// Per-vault cache directory under $XDG_CACHE_HOME (or ~/.cache), named after the vault's path.
+pub(crate) fn cache_dir(vault_path: &Path) -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    let vault = vault_path.canonicalize().ok()?;
+    let name = vault.to_string_lossy().trim_start_matches('/').replace(['/', '\\', ':'], "%");
+    Some(base.join("obsidian-cli").join(name))
+}
+
+fn load_index(vault_path: &Path) -> NoteIndex {
+    let Some(path) = cache_dir(vault_path).map(|dir| dir.join("index.json")) else {
+        return NoteIndex::default();
+    };
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<NoteIndex>(&content).ok())
+        .filter(|index| index.version == INDEX_VERSION)
+        .unwrap_or_default()
+}
+
+fn save_index(vault_path: &Path, index: &NoteIndex) -> Result<(), String> {
+    let dir = cache_dir(vault_path).ok_or("no cache directory available (HOME is not set)")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    let path = dir.join("index.json");
+    fs::write(&path, json).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+/// The scan settings cached entries depend on; entries read with other settings are dropped.
+fn index_settings(options: &ScanOptions) -> String {
+    format!("{:?} {}", options.tag_syntax, options.ignore_key)
+}
+
+/// Brings the index up to date with the vault, re-reading only notes whose size or
+/// modification time changed. Returns the number of notes that had to be re-read.
+pub(crate) fn update_index(vault_path: &Path, index: &mut NoteIndex, options: &ScanOptions) -> usize {
+    let tag_syntax = index_settings(options);
+    if index.version != INDEX_VERSION || index.tag_syntax != tag_syntax {
+        index.notes.clear();
+        index.version = INDEX_VERSION;
+        index.tag_syntax = tag_syntax;
+    }
+
+    let mut notes = BTreeMap::new();
+    let mut misses = 0;
+    for path in markdown_files(vault_path) {
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        let relative = relative_path(vault_path, &path);
+
+        if let Some(cached) = index.notes.remove(&relative)
+            && cached.modified == modified
+            && cached.size == metadata.len()
+        {
+            notes.insert(relative, cached);
+            continue;
+        }
+
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        misses += 1;
+        let frontmatter = Frontmatter::of(&content);
+        notes.insert(
+            relative,
+            IndexedNote {
+                modified,
+                size: metadata.len(),
+                word_count: content.split_whitespace().count(),
+                links: extract_links_from_file(&content),
+                tags: extract_tags_from_file(&content, &options.tag_syntax),
+                aliases: frontmatter.list("aliases", "alias", &[',']),
+                private: is_private_note(&content, &frontmatter, &options.tag_syntax),
+                ignored: is_ignored_note(&frontmatter, &options.ignore_key),
+            },
+        );
+    }
+
+    index.hits += (notes.len() - misses) as u64;
+    index.misses += misses as u64;
+    index.notes = notes;
+    misses
+}
+
+/// The up-to-date note index. Saving it back is best effort, so a read-only cache
+/// directory only costs speed.
+pub(crate) fn indexed_notes(vault_path: &Path, options: &ScanOptions) -> NoteIndex {
+    let mut index = load_index(vault_path);
+    if update_index(vault_path, &mut index, options) > 0 || !index.notes.is_empty() {
+        let _ = save_index(vault_path, &index);
+    }
+    index
+}
+
+fn cache_status(vault_path: &Path) -> CacheStatusOutput {
+    let path = cache_dir(vault_path).map(|dir| dir.join("index.json"));
+    let size_bytes = path.as_ref().and_then(|p| fs::metadata(p).ok()).map_or(0, |m| m.len());
+    let index = load_index(vault_path);
+
+    let mut stale_entries = 0;
+    let mut deleted_entries = 0;
+    for (note, cached) in &index.notes {
+        match fs::metadata(vault_path.join(note)) {
+            Ok(metadata) if metadata.modified().ok() == Some(cached.modified) && metadata.len() == cached.size => {}
+            Ok(_) => stale_entries += 1,
+            Err(_) => deleted_entries += 1,
+        }
+    }
+    let uncached_notes = markdown_files(vault_path)
+        .iter()
+        .filter(|p| !index.notes.contains_key(&relative_path(vault_path, p)))
+        .count();
+    let lookups = index.hits + index.misses;
+
+    CacheStatusOutput {
+        exists: path.as_ref().is_some_and(|p| p.exists()),
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        size_bytes,
+        entries: index.notes.len(),
+        stale_entries,
+        uncached_notes,
+        deleted_entries,
+        hits: index.hits,
+        misses: index.misses,
+        hit_rate: (lookups > 0).then(|| index.hits as f64 / lookups as f64),
+        last_warmed: index.last_warmed,
+    }
+}
+
+/// The visible notes in an index, and each one's links resolved against every note in it.
+/// Links that resolve to a hidden note are left out; broken ones keep the link as written.
+fn index_links(index: &NoteIndex, options: &ScanOptions) -> BTreeMap<String, BTreeSet<(String, bool)>> {
+    let mut resolver = NoteResolver::new(index.notes.keys());
+    for (path, note) in &index.notes {
+        resolver.add_aliases(path, note.aliases.clone());
+    }
+    index
+        .notes
+        .iter()
+        .filter(|(_, note)| !note.hidden(options))
+        .map(|(path, note)| {
+            let links = note
+                .links
+                .iter()
+                .filter_map(|link| match split_link_anchor(link).0 {
+                    "" => Some((path.clone(), true)),
+                    note => match resolver.resolve(note) {
+                        Some(target) if index.notes[target].hidden(options) => None,
+                        Some(target) => Some((target.clone(), true)),
+                        None => Some((note.to_string(), options.is_placeholder(note))),
+                    },
+                })
+                .collect();
+            (path.clone(), links)
+        })
+        .collect()
+}
+
+/// What changed in the vault since the note index was last brought up to date, by this
+/// command or any other that uses the index, and brings it up to date.
+pub fn whats_new(vault_path: &Path, options: &ScanOptions) -> WhatsNewOutput {
+    let previous = load_index(vault_path);
+    let has_baseline = !previous.notes.is_empty() && previous.tag_syntax == index_settings(options);
+    let current = indexed_notes(vault_path, options);
+    if !has_baseline {
+        return WhatsNewOutput {
+            has_baseline,
+            ..WhatsNewOutput::default()
+        };
+    }
+
+    let before = index_links(&previous, options);
+    let after = index_links(&current, options);
+    let edges = |links: &BTreeMap<String, BTreeSet<(String, bool)>>, keep: &dyn Fn(bool) -> bool| -> BTreeSet<GraphEdge> {
+        links
+            .iter()
+            .flat_map(|(source, targets)| {
+                targets.iter().filter(|(_, ok)| keep(*ok)).map(|(target, _)| GraphEdge {
+                    source: source.clone(),
+                    target: target.clone(),
+                })
+            })
+            .collect()
+    };
+    let (edges_before, edges_after) = (edges(&before, &|_| true), edges(&after, &|_| true));
+    let broken_before = edges(&before, &|ok| !ok);
+
+    let tags = |note: &IndexedNote| -> BTreeSet<String> { note.tags.iter().map(|t| options.canonical_tag(t)).collect() };
+    let mut tag_changes = Vec::new();
+    for (path, note) in current.notes.iter().filter(|(path, _)| before.contains_key(*path) && after.contains_key(*path)) {
+        let (old, new) = (tags(&previous.notes[path]), tags(note));
+        if old != new {
+            tag_changes.push(TagChange {
+                note: path.clone(),
+                added: new.difference(&old).cloned().collect(),
+                removed: old.difference(&new).cloned().collect(),
+            });
+        }
+    }
+
+    WhatsNewOutput {
+        has_baseline,
+        new_notes: after.keys().filter(|path| !before.contains_key(*path)).cloned().collect(),
+        deleted_notes: before.keys().filter(|path| !after.contains_key(*path)).cloned().collect(),
+        tag_changes,
+        links_added: edges_after.difference(&edges_before).map(GraphEdge::clone).collect(),
+        links_removed: edges_before.difference(&edges_after).map(GraphEdge::clone).collect(),
+        newly_broken: edges(&after, &|ok| !ok).difference(&broken_before).map(GraphEdge::clone).collect(),
+    }
+}
+
+pub fn run_cache_action(vault_path: &Path, action: CacheAction, options: &ScanOptions) -> Result<CacheStatusOutput, String> {
+    match action {
+        CacheAction::Status => {}
+        CacheAction::Warm => {
+            let mut index = load_index(vault_path);
+            update_index(vault_path, &mut index, options);
+            index.last_warmed = Some(unix_now());
+            save_index(vault_path, &index)?;
+        }
+        CacheAction::Clear => {
+            if let Some(dir) = cache_dir(vault_path).filter(|dir| dir.exists()) {
+                fs::remove_dir_all(&dir).map_err(|e| format!("failed to remove {}: {}", dir.display(), e))?;
+            }
+        }
+    }
+    Ok(cache_status(vault_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::TagSyntax;
+
+    #[test]
+    fn index_rereads_only_changed_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-index-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Home.md"), "#idea [[Plan]]").unwrap();
+        fs::write(root.join("Plan.md"), "---\naliases: [Roadmap]\n---\n#private").unwrap();
+
+        let options = ScanOptions::default();
+        let mut index = NoteIndex::default();
+        assert_eq!(update_index(&root, &mut index, &options), 2);
+        assert_eq!(update_index(&root, &mut index, &options), 0);
+        assert_eq!((index.hits, index.misses), (2, 2));
+        let plan = &index.notes["Plan.md"];
+        assert_eq!(plan.aliases, ["Roadmap"]);
+        assert!(plan.hidden(&options) && !plan.hidden(&ScanOptions { include_private: true, ..ScanOptions::default() }));
+
+        fs::write(root.join("Home.md"), "#idea [[Plan]] and [[Other]]").unwrap();
+        fs::remove_file(root.join("Plan.md")).unwrap();
+        assert_eq!(update_index(&root, &mut index, &options), 1);
+        assert_eq!(index.notes.keys().collect::<Vec<_>>(), ["Home.md"]);
+        assert_eq!(index.notes["Home.md"].links, ["Plan", "Other"]);
+
+        // Entries read with another tag syntax are read again
+        let strict = ScanOptions { tag_syntax: TagSyntax { unicode: true, ..TagSyntax::default() }, ..ScanOptions::default() };
+        assert_eq!(update_index(&root, &mut index, &strict), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
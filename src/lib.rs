@@ -0,0 +1,93 @@
+//! Vault analysis behind the `obsidian-cli` binary. [`Vault`] is the entry point for
+//! embedding it: open a vault, then walk its notes, links and tags.
+
+mod code;
+mod config;
+mod dates;
+mod generate;
+mod git;
+mod graph;
+mod hooks;
+mod index;
+mod links;
+mod lint;
+mod output;
+pub mod parse;
+mod properties;
+mod query;
+mod report;
+mod scan;
+mod scrub;
+mod search;
+mod session;
+mod stats;
+mod symbols;
+mod tag_model;
+mod tags;
+mod tasks;
+mod templates;
+mod urls;
+pub mod vault;
+mod workspace;
+mod write;
+#[cfg(feature = "fuse")]
+mod mount;
+
+pub use config::{Config, GraphConfig, LinkTextConfig, NameCase, NamingConfig, ScanOptions, Severity, SizeAuditConfig, WeekStart};
+pub use parse::{Frontmatter, TagSyntax};
+pub use vault::{Link, Note, Tag, Vault};
+
+/// The commands behind the `obsidian-cli` binary, exported for it alone: not a stable API.
+#[doc(hidden)]
+pub mod cli {
+    #[cfg(feature = "fuse")]
+    pub use crate::mount::mount_views;
+    pub use crate::code::{CodeExtractOutput, code_inventory, extract_code};
+    pub use crate::config::{ScanOptions, WeekStart, load_config, resolve_vault_root, split_arguments};
+    pub use crate::generate::{MocGrouping, generate_moc, on_this_day};
+    pub use crate::git::git_activity;
+    pub use crate::graph::{
+        GraphColoring, GraphFormat, GraphHistoryOutput, HistoryFormat, graph_diff, graph_history, link_graph,
+        render_dot, render_gexf, render_graphml, render_mermaid, suggest_prune,
+    };
+    pub use crate::hooks::{Guard, notify_desktop, run_doctor, run_pre_commit};
+    pub use crate::index::{CacheAction, run_cache_action, whats_new};
+    pub use crate::links::{
+        BacklinksByHeadingOutput, BacklinksOutput, LinksOutput, OrphansOutput, RankedBacklinksOutput,
+        ScoredOrphansOutput, apply_link_fixes, collect_all_links, find_backlinks, find_backlinks_by_heading,
+        find_orphans, find_placeholders, find_slug_collisions, link_fragility, plan_link_fixes, rank_backlinks,
+        score_orphans, visit_links,
+    };
+    pub use crate::lint::{lint, load_gate_rules, load_schema, run_gate};
+    pub use crate::output::{
+        ListFormat, OutputFormat, Table, capabilities, capabilities_table, external_links_table, files_table,
+        found_notes_table, links_table, note_tables_table, orphans_table, ranked_search_table, render_search_matches,
+        render_stats_table, render_template, scored_orphans_table, search_table, tag_pairs_table, tags_table,
+    };
+    pub use crate::parse::TagSyntax;
+    pub use crate::properties::{edit_frontmatter, frontmatter_audit, inspect_note, list_properties};
+    pub use crate::report::{ReportGrouping, render_html_report, render_openmetrics, tag_rollup, tag_rollup_table};
+    pub use crate::scrub::scrub_vault;
+    pub use crate::search::{
+        FindOutput, RankedSearchOutput, SearchOutput, TablesOutput, find_notes, note_tables, rank_notes, run_query,
+        search_notes,
+    };
+    pub use crate::session::{SessionAction, session_changes, start_session};
+    pub use crate::stats::{FilesOutput, calculate_stats, collect_all_files, folder_tree, run_bench, size_audit};
+    pub use crate::symbols::collect_symbols;
+    pub use crate::tag_model::{suggest_tags, train_tag_model};
+    pub use crate::tags::{
+        TagCooccurrenceOutput, TagCount, TagSearchOutput, TagsOutput, collect_all_tags, find_notes_with_tag,
+        materialize_tags, rename_tag, tag_aliases, tag_cooccurrence, tag_tree,
+    };
+    pub use crate::tasks::{TaskSort, TasksOutput, build_agenda, collect_tasks, sort_tasks};
+    pub use crate::templates::{
+        TemplateCheckOutput, TemplatesAction, apply_template, check_templates, find_template_leftovers, list_templates,
+        new_note, related_links,
+    };
+    pub use crate::urls::{enrich_urls, external_links};
+    pub use crate::workspace::{BookmarksOutput, RecentOutput, collect_bookmarks, collect_recent};
+    pub use crate::write::{
+        ChangeSet, MergeConflict, VaultWriter, dedupe_attachments, delete_note, merge_vaults, move_note, rename_note,
+    };
+}
@@ -0,0 +1,1178 @@
+//! Links between notes: listings, orphans, backlinks, broken-link fixes and link rewriting.
+
+use crate::config::ScanOptions;
+use crate::git::git_rename_counts;
+use crate::graph::GraphEdge;
+use crate::index::indexed_notes;
+use crate::parse::{
+    self, anchor_matches_heading, extract_block_ids, extract_headings, extract_links_from_file,
+    extract_links_with_embeds, extract_markdown_links, mask_code, mask_code_blocks, split_link_anchor,
+};
+use crate::scan::{
+    NoteResolver, attachment_files, find_note_path, is_visible_entry, markdown_files, normalize_path, note_aliases,
+    note_stem, read_note, relative_path, resolve_markdown_link, walk_vault,
+};
+use crate::write::VaultWriter;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+#[derive(Serialize)]
+pub struct LinkInfo {
+    pub source: String,
+    pub target: String,
+    /// The heading in `[[Note#Heading]]`, or block in `[[Note#^block]]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    pub exists: bool,
+    /// The note exists but has no heading matching the link's `#heading`
+    pub broken_heading: bool,
+    /// The note exists but has no block marked with the link's `^id`
+    pub broken_block: bool,
+    /// Deliberately points at a note that hasn't been written yet, so it isn't broken
+    pub placeholder: bool,
+    /// An `![[embed]]` transcluding the note or attachment rather than linking to it
+    pub embed: bool,
+}
+
+#[derive(Serialize)]
+pub struct LinksOutput {
+    pub links: Vec<LinkInfo>,
+    pub broken_count: usize,
+    pub broken_heading_count: usize,
+    pub broken_block_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct OrphansOutput {
+    pub orphans: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct IsolationScore {
+    pub(crate) note: String,
+    /// 0 for a well connected note, 1 for one with no connections of any kind
+    pub(crate) score: f64,
+    pub(crate) inbound: usize,
+    pub(crate) outbound: usize,
+    /// Other notes sharing at least one tag
+    pub(crate) tag_neighbors: usize,
+    /// Other notes in the same folder
+    pub(crate) folder_siblings: usize,
+}
+
+#[derive(Serialize)]
+pub struct ScoredOrphansOutput {
+    pub threshold: f64,
+    pub notes: Vec<IsolationScore>,
+}
+
+#[derive(Serialize)]
+pub struct BacklinksOutput {
+    pub file: String,
+    pub backlinks: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RankedBacklink {
+    note: String,
+    /// 0 to 1, higher for notes that reference the target more prominently
+    score: f64,
+    mentions: usize,
+    /// Line of the first link to the target, starting at 1
+    first_line: usize,
+    /// Whether a heading links to the target
+    in_heading: bool,
+}
+
+#[derive(Serialize)]
+pub struct RankedBacklinksOutput {
+    pub file: String,
+    pub backlinks: Vec<RankedBacklink>,
+}
+
+#[derive(Serialize)]
+pub struct HeadingBacklinks {
+    heading: Option<String>,
+    /// The block id, for links to a `^block` rather than a heading
+    block: Option<String>,
+    backlinks: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BacklinksByHeadingOutput {
+    pub file: String,
+    pub sections: Vec<HeadingBacklinks>,
+}
+
+/// A broken wikilink and the existing note it most likely meant.
+#[derive(Serialize, Clone)]
+pub struct LinkFix {
+    pub source: String,
+    /// The broken target, as written
+    pub target: String,
+    /// What the target is rewritten to
+    pub replacement: String,
+    /// Path of the note the replacement links to
+    pub note: String,
+    /// "renamed" when only case, spacing, punctuation or a date prefix differ, otherwise
+    /// "similar" for the closest name by edit distance
+    pub reason: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct LinkFixOutput {
+    dry_run: bool,
+    fixed: Vec<LinkFix>,
+    /// Broken links with no single close enough note
+    unfixed: Vec<GraphEdge>,
+    /// Notes rewritten
+    notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Placeholder {
+    /// Title of the note to write, with the placeholder prefix removed
+    title: String,
+    target: String,
+    sources: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlaceholdersOutput {
+    prefix: String,
+    placeholders: Vec<Placeholder>,
+}
+
+#[derive(Serialize)]
+struct SlugCollision {
+    slug: String,
+    notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct HeadingRef {
+    line: usize,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct AnchorCollision {
+    path: String,
+    anchor: String,
+    headings: Vec<HeadingRef>,
+}
+
+#[derive(Serialize)]
+pub struct SlugCollisionsOutput {
+    note_collisions: Vec<SlugCollision>,
+    anchor_collisions: Vec<AnchorCollision>,
+}
+
+#[derive(Serialize)]
+struct NoteFragility {
+    path: String,
+    fragile_links: usize,
+    anchored: usize,
+    ambiguous: usize,
+    renamed_targets: usize,
+}
+
+#[derive(Serialize)]
+pub struct FragilityOutput {
+    total_links: usize,
+    fragile_links: usize,
+    fragility_score: f64,
+    anchored_links: usize,
+    ambiguous_links: usize,
+    /// None when the vault isn't tracked by git
+    links_to_renamed_notes: Option<usize>,
+    renamed_notes: BTreeMap<String, usize>,
+    notes: Vec<NoteFragility>,
+}
+
+pub fn collect_all_links(vault_path: &Path, options: &ScanOptions) -> Result<(Vec<LinkInfo>, HashSet<String>), String> {
+    let mut all_links = Vec::new();
+    let all_notes = visit_links(vault_path, options, |link| all_links.push(link))?;
+    Ok((all_links, all_notes))
+}
+
+/// Passes each link to `visit` as soon as its note has been read, for output that streams
+/// instead of waiting for the whole vault. Resolving a link needs every note's aliases, so
+/// output only starts after a first pass has read each note once; headings and block ids
+/// are read later, for the notes anchored links point at. Returns the paths of all visible
+/// notes.
+pub fn visit_links(vault_path: &Path, options: &ScanOptions, mut visit: impl FnMut(LinkInfo)) -> Result<HashSet<String>, String> {
+    let mut all_notes = HashSet::new();
+    // Links into private notes still resolve, they are just left out of the results
+    let mut private_notes = HashSet::new();
+    let mut aliases = Vec::new();
+    // Headings and block ids of link targets, read the first time an anchor points there
+    let mut anchors: HashMap<String, (Vec<String>, Vec<String>)> = HashMap::new();
+
+    // First pass: collect all note paths with their aliases
+    for entry in walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            let relative_path = path.strip_prefix(vault_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string();
+            let content = fs::read_to_string(path).unwrap_or_default();
+            aliases.push((relative_path.clone(), note_aliases(&content)));
+            if options.hides(&content) {
+                private_notes.insert(relative_path);
+            } else {
+                all_notes.insert(relative_path);
+            }
+        }
+    }
+    let mut resolver = NoteResolver::new(all_notes.union(&private_notes));
+    for (note, aliases) in aliases {
+        resolver.add_aliases(&note, aliases);
+    }
+    // Links and embeds can also point at images, PDFs and other attachments by name or path
+    let attachments = NoteResolver::new(attachment_files(vault_path).iter().map(|path| relative_path(vault_path, path)));
+
+    // Second pass: collect all links
+    for entry in walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            match read_note(path, options) {
+                Some(content) => {
+                    let source = path.strip_prefix(vault_path)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string();
+
+                    let wikilinks = extract_links_with_embeds(&content).into_iter().map(|(link, embed)| {
+                        let (note, heading) = split_link_anchor(&link);
+                        let target = match note {
+                            "" => Some(source.clone()),
+                            note => resolver.resolve(note).or_else(|| attachments.resolve(note)).cloned(),
+                        };
+                        (note.to_string(), heading.map(str::to_string), target, embed)
+                    });
+                    let markdown_links = extract_markdown_links(&content).into_iter().map(|(destination, embed)| {
+                        let target = resolve_markdown_link(&source, &destination, &resolver);
+                        let destination = parse::percent_decode(&destination);
+                        let (note, heading) = split_link_anchor(&destination);
+                        (note.to_string(), heading.map(str::to_string), target, embed)
+                    });
+                    for (link, heading, target_path, embed) in wikilinks.chain(markdown_links) {
+                        if target_path.as_ref().is_some_and(|t| private_notes.contains(t)) {
+                            continue;
+                        }
+                        let exists = target_path.is_some();
+                        let placeholder = !exists && options.is_placeholder(&link);
+                        let (broken_heading, broken_block) = match (&target_path, &heading) {
+                            (Some(target), Some(heading)) if target.ends_with(".md") => {
+                                let (headings, block_ids) = anchors.entry(target.clone()).or_insert_with(|| {
+                                    let content = fs::read_to_string(vault_path.join(target)).unwrap_or_default();
+                                    (extract_headings(&content), extract_block_ids(&content))
+                                });
+                                match heading.strip_prefix('^') {
+                                    Some(id) => (false, !block_ids.iter().any(|i| i == id)),
+                                    None => (!anchor_matches_heading(heading, headings), false),
+                                }
+                            }
+                            _ => (false, false),
+                        };
+                        let target = target_path.unwrap_or(link);
+
+                        visit(LinkInfo {
+                            source: source.clone(),
+                            target,
+                            heading,
+                            exists,
+                            broken_heading,
+                            broken_block,
+                            placeholder,
+                            embed,
+                        });
+                    }
+                }
+                None => {
+                    continue;
+                }
+            }
+        }
+    }
+
+    Ok(all_notes)
+}
+
+pub fn find_orphans(vault_path: &Path, options: &ScanOptions) -> Result<Vec<String>, String> {
+    let (links, all_notes) = collect_all_links(vault_path, options)?;
+
+    let mut has_outgoing = HashSet::new();
+    let mut has_incoming = HashSet::new();
+
+    for link in &links {
+        has_outgoing.insert(link.source.clone());
+        if link.exists {
+            has_incoming.insert(link.target.clone());
+        }
+    }
+
+    let mut orphans: Vec<String> = all_notes
+        .iter()
+        .filter(|note| !has_outgoing.contains(*note) && !has_incoming.contains(*note))
+        .filter(|note| !options.skips_archived(note))
+        .cloned()
+        .collect();
+    orphans.sort();
+
+    Ok(orphans)
+}
+
+/// How much inbound links, outbound links, tag neighbors and folder siblings each count
+/// towards a note not being isolated.
+const ISOLATION_WEIGHTS: [f64; 4] = [0.4, 0.3, 0.2, 0.1];
+
+/// Scores every note by how isolated it is, from 0 (well connected) to 1 (no inbound or
+/// outbound links, no tags shared with other notes and alone in its folder). Each kind of
+/// connection saturates, so the first few count most. Returns notes scoring at least
+/// `threshold`, most isolated first.
+pub fn score_orphans(vault_path: &Path, threshold: f64, options: &ScanOptions) -> Result<Vec<IsolationScore>, String> {
+    let (links, all_notes) = collect_all_links(vault_path, options)?;
+    let mut inbound: HashMap<&str, HashSet<&str>> = HashMap::new();
+    let mut outbound: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for link in links.iter().filter(|l| l.exists && l.source != l.target) {
+        inbound.entry(&link.target).or_default().insert(&link.source);
+        outbound.entry(&link.source).or_default().insert(&link.target);
+    }
+
+    let index = indexed_notes(vault_path, options);
+    let tags_of = |note: &str| -> BTreeSet<String> {
+        index.notes.get(note).map(|n| n.tags.iter().map(|t| options.canonical_tag(t)).collect()).unwrap_or_default()
+    };
+    let mut notes_by_tag: HashMap<String, HashSet<&str>> = HashMap::new();
+    let mut notes_by_folder: HashMap<&str, usize> = HashMap::new();
+    for note in &all_notes {
+        for tag in tags_of(note) {
+            notes_by_tag.entry(tag).or_default().insert(note);
+        }
+        *notes_by_folder.entry(note.rsplit_once('/').map_or("", |(folder, _)| folder)).or_default() += 1;
+    }
+
+    let saturate = |count: usize| 1.0 - 1.0 / (1.0 + count as f64);
+    let mut scores = Vec::new();
+    for note in all_notes.iter().filter(|note| !options.skips_archived(note)) {
+        let tag_neighbors: HashSet<&str> = tags_of(note)
+            .iter()
+            .flat_map(|tag| notes_by_tag[tag].iter().copied())
+            .filter(|other| other != note)
+            .collect();
+        let counts = [
+            inbound.get(note.as_str()).map_or(0, HashSet::len),
+            outbound.get(note.as_str()).map_or(0, HashSet::len),
+            tag_neighbors.len(),
+            notes_by_folder[note.rsplit_once('/').map_or("", |(folder, _)| folder)] - 1,
+        ];
+        let connected: f64 = ISOLATION_WEIGHTS.iter().zip(counts).map(|(weight, count)| weight * saturate(count)).sum();
+        let score = ((1.0 - connected) * 1000.0).round() / 1000.0;
+        if score >= threshold {
+            scores.push(IsolationScore {
+                note: note.clone(),
+                score,
+                inbound: counts[0],
+                outbound: counts[1],
+                tag_neighbors: counts[2],
+                folder_siblings: counts[3],
+            });
+        }
+    }
+
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.note.cmp(&b.note)));
+    Ok(scores)
+}
+
+pub fn find_backlinks(vault_path: &Path, target_file: &str, options: &ScanOptions) -> Result<Vec<String>, String> {
+    let (links, all_notes) = collect_all_links(vault_path, options)?;
+
+    // Normalize the target file path, which can also be one of the note's aliases
+    let target_file = find_note_path(vault_path, target_file, &all_notes).unwrap_or_else(|| target_file.to_string());
+    let target_normalized = normalize_path(vault_path, &target_file);
+
+    let mut backlinks = Vec::new();
+
+    for link in links {
+        let link_target_normalized = normalize_path(vault_path, &link.target);
+
+        // Check if this link points to our target file
+        if is_same_note(&link_target_normalized, &target_normalized) {
+            backlinks.push(link.source);
+        }
+    }
+
+    backlinks.sort();
+    backlinks.dedup();
+
+    Ok(backlinks)
+}
+
+pub(crate) fn is_same_note(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!("/{}", b)) || b.ends_with(&format!("/{}", a))
+}
+
+/// How much the number of mentions, how early the first one comes and linking from a
+/// heading each count towards a backlink's prominence.
+const PROMINENCE_WEIGHTS: [f64; 3] = [0.5, 0.3, 0.2];
+
+/// Backlinks to `target_file`, most prominent reference first. Mentions saturate, so the
+/// first few count most; a link on the first line counts fully for position and one on the
+/// last line not at all. Links in code or frontmatter don't count.
+pub fn rank_backlinks(vault_path: &Path, target_file: &str, options: &ScanOptions) -> Result<Vec<RankedBacklink>, String> {
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let notes: Vec<(String, String)> = paths
+        .iter()
+        .filter_map(|path| Some((relative_path(vault_path, path), mask_code(&read_note(path, options)?))))
+        .collect();
+    let resolver = NoteResolver::new(notes.iter().map(|(note, _)| note)).read_aliases(vault_path);
+    let target = resolver
+        .resolve(target_file)
+        .cloned()
+        .ok_or_else(|| format!("note not found: {}", target_file))?;
+
+    let mut ranked = Vec::new();
+    for (source, content) in notes {
+        if source == target {
+            continue;
+        }
+        let lines: Vec<&str> = content.lines().collect();
+        let mut mentions = 0;
+        let mut first_line = None;
+        let mut in_heading = false;
+        for (i, line) in lines.iter().enumerate() {
+            let wikilinks = extract_links_from_file(line)
+                .iter()
+                .filter(|link| resolver.resolve(split_link_anchor(link).0) == Some(&target))
+                .count();
+            let markdown_links = extract_markdown_links(line)
+                .iter()
+                .filter(|(destination, _)| resolve_markdown_link(&source, destination, &resolver).as_ref() == Some(&target))
+                .count();
+            let here = wikilinks + markdown_links;
+            if here > 0 {
+                mentions += here;
+                first_line.get_or_insert(i);
+                in_heading |= line.trim_start().starts_with('#') && line.trim_start().trim_start_matches('#').starts_with(' ');
+            }
+        }
+        let Some(first_line) = first_line else {
+            continue;
+        };
+
+        let position = 1.0 - first_line as f64 / lines.len().saturating_sub(1).max(1) as f64;
+        let components = [1.0 - 1.0 / (1.0 + mentions as f64), position, if in_heading { 1.0 } else { 0.0 }];
+        let score: f64 = PROMINENCE_WEIGHTS.iter().zip(components).map(|(weight, value)| weight * value).sum();
+        ranked.push(RankedBacklink {
+            note: source,
+            score: (score * 1000.0).round() / 1000.0,
+            mentions,
+            first_line: first_line + 1,
+            in_heading,
+        });
+    }
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.note.cmp(&b.note)));
+    Ok(ranked)
+}
+
+pub fn find_backlinks_by_heading(
+    vault_path: &PathBuf,
+    target_file: &str,
+    options: &ScanOptions,
+) -> Result<Vec<HeadingBacklinks>, String> {
+    let (_links, all_notes) = collect_all_links(vault_path, options)?;
+    let resolver = NoteResolver::new(&all_notes).read_aliases(vault_path);
+    let target_normalized = normalize_path(vault_path, resolver.resolve(target_file).map_or(target_file, String::as_str));
+
+    // Keyed by whether the anchor is a block id, then the anchor; None groups links to the
+    // note as a whole
+    let mut sections: BTreeMap<(bool, Option<String>), Vec<String>> = BTreeMap::new();
+
+    for entry in walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            match read_note(path, options) {
+                Some(content) => {
+                    let source = path.strip_prefix(vault_path)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string();
+
+                    for link in extract_links_from_file(&content) {
+                        let (note, anchor) = split_link_anchor(&link);
+                        let Some(target) = resolver.resolve(note).cloned() else {
+                            continue;
+                        };
+
+                        if is_same_note(&normalize_path(vault_path, &target), &target_normalized) {
+                            let block = anchor.is_some_and(|a| a.starts_with('^'));
+                            sections
+                                .entry((block, anchor.map(|a| a.trim_start_matches('^').to_string())))
+                                .or_default()
+                                .push(source.clone());
+                        }
+                    }
+                }
+                None => {
+                    continue;
+                }
+            }
+        }
+    }
+
+    Ok(sections
+        .into_iter()
+        .map(|((is_block, anchor), mut backlinks)| {
+            backlinks.sort();
+            backlinks.dedup();
+            let (heading, block) = if is_block { (None, anchor) } else { (anchor, None) };
+            HeadingBacklinks { heading, block, backlinks }
+        })
+        .collect())
+}
+
+/// Rewrites the target of every `[[wikilink]]` and `![[embed]]` for which `rewrite`
+/// returns a replacement, keeping any `#anchor`, `^block` and `|alias` intact. Code is skipped.
+pub(crate) fn rewrite_wikilinks(content: &str, rewrite: impl Fn(&str) -> Option<String>) -> (String, usize) {
+    static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]|#^]+)([^\]]*)\]\]").unwrap());
+    let mut count = 0;
+    let mut rewritten = String::with_capacity(content.len());
+    let mut end = 0;
+    for cap in LINK_REGEX.captures_iter(&mask_code_blocks(content)) {
+        let target = cap.get(1).unwrap();
+        let Some(replacement) = rewrite(&content[target.range()]) else {
+            continue;
+        };
+        count += 1;
+        rewritten.push_str(&content[end..target.start()]);
+        rewritten.push_str(&replacement);
+        end = target.end();
+    }
+    rewritten.push_str(&content[end..]);
+    (rewritten, count)
+}
+
+/// Rewrites the destination of every Markdown link, `[text](Note.md)`, for which `rewrite`
+/// returns a replacement, keeping the text, title and any `<...>` around it. Code is skipped.
+pub(crate) fn rewrite_markdown_links(content: &str, rewrite: impl Fn(&str) -> Option<String>) -> (String, usize) {
+    static MARKDOWN_LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"!?\[[^\[\]]*\]\(\s*(<[^>\n]*>|[^)\s]+)(?:\s+"[^"\n]*")?\s*\)"#).unwrap());
+    let mut count = 0;
+    let mut rewritten = String::with_capacity(content.len());
+    let mut end = 0;
+    for cap in MARKDOWN_LINK_REGEX.captures_iter(&mask_code(content)) {
+        let destination = cap.get(1).unwrap();
+        let written = &content[destination.range()];
+        let bracketed = written.starts_with('<');
+        let Some(replacement) = rewrite(written.trim_start_matches('<').trim_end_matches('>')) else {
+            continue;
+        };
+        count += 1;
+        rewritten.push_str(&content[end..destination.start()]);
+        if bracketed {
+            rewritten.push_str(&format!("<{}>", replacement));
+        } else {
+            rewritten.push_str(&replacement.replace('%', "%25").replace(' ', "%20").replace('(', "%28").replace(')', "%29"));
+        }
+        end = destination.end();
+    }
+    rewritten.push_str(&content[end..]);
+    (rewritten, count)
+}
+
+/// The vault path a relative link from inside `folder` points to, if it stays in the vault.
+pub(crate) fn relative_to_vault(folder: &str, link: &str) -> Option<String> {
+    let mut parts: Vec<&str> = folder.split('/').filter(|part| !part.is_empty()).collect();
+    for part in link.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// The path of `to` from inside `folder`, like `../Projects/Plan.md`.
+pub(crate) fn relative_link(folder: &str, to: &str) -> String {
+    let from: Vec<&str> = folder.split('/').filter(|part| !part.is_empty()).collect();
+    let to: Vec<&str> = to.split('/').collect();
+    let shared = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - shared];
+    parts.extend(&to[shared..]);
+    parts.join("/")
+}
+
+/// The note a broken link target most likely meant: one whose name differs only in case,
+/// spacing, punctuation or a leading date (as renames by `lint --fix-names` leave them), or
+/// failing that the only one within a few edits of it. Ties find nothing.
+fn closest_note<'a>(target: &str, notes: &'a [String]) -> Option<(&'a String, &'static str)> {
+    let squash = |text: &str| text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>();
+    let name = target.rsplit('/').next().unwrap_or(target);
+    let wanted = squash(name);
+    if wanted.is_empty() {
+        return None;
+    }
+    let renamed: Vec<&String> = notes
+        .iter()
+        .filter(|note| {
+            let stem = squash(note_stem(note));
+            stem.strip_suffix(&wanted).is_some_and(|prefix| prefix.chars().all(|c| c.is_ascii_digit()))
+        })
+        .collect();
+    match renamed.as_slice() {
+        [note] => return Some((note, "renamed")),
+        [] => {}
+        _ => return None,
+    }
+
+    let lowered = name.to_lowercase();
+    let limit = (lowered.chars().count() / 4).max(1);
+    let mut best: Option<(usize, Vec<&String>)> = None;
+    for note in notes {
+        let distance = edit_distance(&lowered, &note_stem(note).to_lowercase());
+        if distance > limit {
+            continue;
+        }
+        match &mut best {
+            Some((closest, found)) if distance == *closest => found.push(note),
+            Some((closest, _)) if distance > *closest => {}
+            _ => best = Some((distance, vec![note])),
+        }
+    }
+    match best {
+        Some((_, found)) if found.len() == 1 => Some((found[0], "similar")),
+        _ => None,
+    }
+}
+
+/// Finds the note each broken wikilink most likely meant. Links to attachments, Markdown
+/// links and placeholders are left alone; links with no single close note are listed as unfixed.
+pub fn plan_link_fixes(vault_path: &Path, options: &ScanOptions) -> Result<(Vec<LinkFix>, Vec<GraphEdge>), String> {
+    let (links, all_notes) = collect_all_links(vault_path, options)?;
+    let mut notes: Vec<String> = all_notes.into_iter().collect();
+    notes.sort();
+    let mut stems: HashMap<&str, usize> = HashMap::new();
+    for note in &notes {
+        *stems.entry(note_stem(note)).or_insert(0) += 1;
+    }
+
+    let broken: BTreeSet<(String, String)> = links
+        .into_iter()
+        .filter(|link| !link.exists && !link.placeholder)
+        .map(|link| (link.source, link.target))
+        .collect();
+    let mut fixes = Vec::new();
+    let mut unfixed = Vec::new();
+    let mut wikilinks: HashMap<String, HashSet<String>> = HashMap::new();
+    for (source, target) in broken {
+        let written = wikilinks.entry(source.clone()).or_insert_with(|| {
+            let content = fs::read_to_string(vault_path.join(&source)).unwrap_or_default();
+            extract_links_from_file(&content).iter().map(|link| split_link_anchor(link).0.to_string()).collect()
+        });
+        let is_attachment = target.rsplit('/').next().is_some_and(|name| name.contains('.') && !name.ends_with(".md"));
+        if !written.contains(&target) || is_attachment {
+            continue;
+        }
+        match closest_note(&target, &notes) {
+            Some((note, reason)) => {
+                let stem = note_stem(note);
+                let replacement = match stems.get(stem) {
+                    Some(1) => stem.to_string(),
+                    _ => note.strip_suffix(".md").unwrap_or(note).to_string(),
+                };
+                fixes.push(LinkFix {
+                    source,
+                    target,
+                    replacement,
+                    note: note.clone(),
+                    reason,
+                });
+            }
+            None => unfixed.push(GraphEdge { source, target }),
+        }
+    }
+    Ok((fixes, unfixed))
+}
+
+/// Rewrites the broken links of each fix to the note it found.
+pub fn apply_link_fixes(vault_path: &Path, fixes: Vec<LinkFix>, unfixed: Vec<GraphEdge>, writer: &mut VaultWriter) -> Result<LinkFixOutput, String> {
+    let mut by_source: BTreeMap<&str, HashMap<&str, &str>> = BTreeMap::new();
+    for fix in &fixes {
+        by_source.entry(&fix.source).or_default().insert(&fix.target, &fix.replacement);
+    }
+    let mut notes = Vec::new();
+    for (source, replacements) in by_source {
+        let path = vault_path.join(source);
+        let content = fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", source, e))?;
+        let (updated, count) = rewrite_wikilinks(&content, |target| replacements.get(target).map(|r| r.to_string()));
+        if count > 0 {
+            writer.write(vault_path, &path, &updated)?;
+            notes.push(source.to_string());
+        }
+    }
+    Ok(LinkFixOutput {
+        dry_run: writer.dry_run,
+        fixed: fixes,
+        unfixed,
+        notes,
+    })
+}
+
+/// Edits needed to turn `a` into `b`, counting characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            current.push((previous[j] + usize::from(ca != *cb)).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Shortest wikilink target that still resolves to `note` unambiguously.
+pub(crate) fn link_target(note: &str, all_notes: &HashSet<String>) -> String {
+    let stem = note_stem(note);
+    let ambiguous = all_notes.iter().filter(|n| note_stem(n) == stem).count() > 1;
+    if ambiguous {
+        note.strip_suffix(".md").unwrap_or(note).to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Placeholder links grouped by target, most referenced first so the notes most worth
+/// writing come at the top of the queue.
+pub fn find_placeholders(vault_path: &Path, options: &ScanOptions) -> Result<PlaceholdersOutput, String> {
+    let (links, _) = collect_all_links(vault_path, options)?;
+
+    let mut targets: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for link in links.into_iter().filter(|l| l.placeholder) {
+        let (target, _) = split_link_anchor(&link.target);
+        targets.entry(target.trim().to_string()).or_default().insert(link.source);
+    }
+
+    let mut placeholders: Vec<Placeholder> = targets
+        .into_iter()
+        .map(|(target, sources)| Placeholder {
+            title: target[options.placeholder_prefix.len()..].trim().to_string(),
+            target,
+            sources: sources.into_iter().collect(),
+        })
+        .collect();
+    placeholders.sort_by_key(|p| std::cmp::Reverse(p.sources.len()));
+
+    Ok(PlaceholdersOutput {
+        prefix: options.placeholder_prefix.clone(),
+        placeholders,
+    })
+}
+
+/// URL slug as most static site generators make it: lowercase letters and digits, with
+/// runs of whitespace, dashes and underscores turned into a single dash.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+pub fn find_slug_collisions(vault_path: &Path, options: &ScanOptions) -> Result<SlugCollisionsOutput, String> {
+    let heading_regex = Regex::new(r"^#{1,6}\s+(.+?)(?:\s+#+)?\s*$").unwrap();
+    let mut files = markdown_files(vault_path);
+    files.sort();
+
+    let mut by_slug: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut anchor_collisions = Vec::new();
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let relative = relative_path(vault_path, &path);
+        let url = relative
+            .strip_suffix(".md")
+            .unwrap_or(&relative)
+            .split('/')
+            .map(slugify)
+            .collect::<Vec<_>>()
+            .join("/");
+        by_slug.entry(url).or_default().push(relative.clone());
+
+        let mut anchors: BTreeMap<String, Vec<HeadingRef>> = BTreeMap::new();
+        for (index, line) in mask_code(&content).lines().enumerate() {
+            if let Some(cap) = heading_regex.captures(line) {
+                anchors.entry(slugify(&cap[1])).or_default().push(HeadingRef {
+                    line: index + 1,
+                    text: cap[1].to_string(),
+                });
+            }
+        }
+        for (anchor, headings) in anchors {
+            if headings.len() > 1 {
+                anchor_collisions.push(AnchorCollision {
+                    path: relative.clone(),
+                    anchor,
+                    headings,
+                });
+            }
+        }
+    }
+
+    let note_collisions = by_slug
+        .into_iter()
+        .filter(|(_, notes)| notes.len() > 1)
+        .map(|(slug, notes)| SlugCollision { slug, notes })
+        .collect();
+
+    Ok(SlugCollisionsOutput {
+        note_collisions,
+        anchor_collisions,
+    })
+}
+
+/// Targets renamed at least this many times in git history count as unstable
+const FREQUENT_RENAMES: usize = 2;
+
+pub fn link_fragility(vault_path: &Path, options: &ScanOptions) -> Result<FragilityOutput, String> {
+    let files = markdown_files(vault_path);
+    let all_notes: HashSet<String> = files.iter().map(|p| relative_path(vault_path, p)).collect();
+    let resolver = NoteResolver::new(&all_notes).read_aliases(vault_path);
+    let mut stems: HashMap<&str, usize> = HashMap::new();
+    for note in &all_notes {
+        *stems.entry(note_stem(note)).or_insert(0) += 1;
+    }
+    let renames = git_rename_counts(vault_path).ok();
+
+    let mut notes = Vec::new();
+    let (mut total_links, mut anchored_links, mut ambiguous_links, mut renamed_links) = (0, 0, 0, 0);
+    for path in &files {
+        if options.skips_archived(&relative_path(vault_path, path)) {
+            continue;
+        }
+        let Some(content) = read_note(path, options) else {
+            continue;
+        };
+        let mut note = NoteFragility {
+            path: relative_path(vault_path, path),
+            fragile_links: 0,
+            anchored: 0,
+            ambiguous: 0,
+            renamed_targets: 0,
+        };
+
+        for link in extract_links_from_file(&content) {
+            total_links += 1;
+            let (target, anchor) = split_link_anchor(&link);
+            let anchored = anchor.is_some();
+            let ambiguous = !target.contains('/') && stems.get(target.trim_end_matches(".md")).is_some_and(|n| *n > 1);
+            let renamed = renames.as_ref().is_some_and(|renames| {
+                resolver
+                    .resolve(target)
+                    .and_then(|t| renames.get(t))
+                    .is_some_and(|count| *count >= FREQUENT_RENAMES)
+            });
+
+            note.anchored += anchored as usize;
+            note.ambiguous += ambiguous as usize;
+            note.renamed_targets += renamed as usize;
+            if anchored || ambiguous || renamed {
+                note.fragile_links += 1;
+            }
+        }
+
+        anchored_links += note.anchored;
+        ambiguous_links += note.ambiguous;
+        renamed_links += note.renamed_targets;
+        if note.fragile_links > 0 {
+            notes.push(note);
+        }
+    }
+
+    notes.sort_by(|a, b| b.fragile_links.cmp(&a.fragile_links).then_with(|| a.path.cmp(&b.path)));
+    let fragile_links: usize = notes.iter().map(|n| n.fragile_links).sum();
+
+    Ok(FragilityOutput {
+        total_links,
+        fragile_links,
+        fragility_score: if total_links == 0 { 0.0 } else { fragile_links as f64 / total_links as f64 },
+        anchored_links,
+        ambiguous_links,
+        links_to_renamed_notes: renames.as_ref().map(|_| renamed_links),
+        renamed_notes: renames.unwrap_or_default(),
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_collapse_punctuation_and_spacing() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("C++ vs. C#"), "c-vs-c");
+        assert_eq!(slugify("  a -- b__c "), "a-b-c");
+        assert_eq!(slugify("Über Café"), "über-café");
+    }
+
+    #[test]
+    fn orphans_are_listed_in_path_order() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-orphans-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects")).unwrap();
+        for name in ["Zeta", "alpha", "Mid", "Projects/Beta", "Projects/Aardvark", "10 Ideas"] {
+            fs::write(root.join(format!("{}.md", name)), "No links here\n").unwrap();
+        }
+        fs::write(root.join("Linked.md"), "See [[Mid]]\n").unwrap();
+
+        let first = find_orphans(&root, &ScanOptions::default()).unwrap();
+        let second = find_orphans(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(first, ["10 Ideas.md", "Projects/Aardvark.md", "Projects/Beta.md", "Zeta.md", "alpha.md"]);
+        assert_eq!(first, second);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn embeds_are_flagged_and_find_attachments() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-embeds-{}", std::process::id()));
+        fs::create_dir_all(root.join("img")).unwrap();
+        fs::write(root.join("Note.md"), "[[Other]] ![[Other]] ![[pic.png]] ![[gone.png]]").unwrap();
+        fs::write(root.join("Other.md"), "").unwrap();
+        fs::write(root.join("img/pic.png"), "").unwrap();
+
+        let (links, _) = collect_all_links(&root, &ScanOptions::default()).unwrap();
+        let found: Vec<(&str, bool, bool)> = links.iter().map(|l| (l.target.as_str(), l.embed, l.exists)).collect();
+        assert_eq!(found, [("Other.md", false, true), ("Other.md", true, true), ("img/pic.png", true, true), ("gone.png", true, false)]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn anchored_links_resolve_the_note_and_check_the_anchor() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-headings-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Note.md"), "# Top\n[[Other#Goals]] [[Other#Gone]] [[#top]] [[Other#^block]] [[Other^gone]] [[Missing#Goals]]").unwrap();
+        fs::write(root.join("Other.md"), "## Goals\nA paragraph ^block").unwrap();
+
+        let (links, _) = collect_all_links(&root, &ScanOptions::default()).unwrap();
+        let found: Vec<(&str, bool, bool, bool)> =
+            links.iter().map(|l| (l.target.as_str(), l.exists, l.broken_heading, l.broken_block)).collect();
+        assert_eq!(
+            found,
+            [
+                ("Other.md", true, false, false),
+                ("Other.md", true, true, false),
+                ("Note.md", true, false, false),
+                ("Other.md", true, false, false),
+                ("Other.md", true, false, true),
+                ("Missing", false, false, false),
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn broken_links_find_renamed_or_similar_notes() {
+        let notes: Vec<String> = ["Project Plan.md", "Daily/2024-01-05 Standup.md", "Recipes.md", "Note1.md", "Note2.md"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let found = |target: &str| closest_note(target, &notes).map(|(note, reason)| (note.as_str(), reason));
+        assert_eq!(found("project-plan"), Some(("Project Plan.md", "renamed")));
+        assert_eq!(found("Standup"), Some(("Daily/2024-01-05 Standup.md", "renamed")));
+        assert_eq!(found("Recipies"), Some(("Recipes.md", "similar")));
+        assert_eq!(found("Note3"), None);
+        assert_eq!(found("Zebra"), None);
+    }
+
+    #[test]
+    fn backlinks_by_heading_list_block_references_separately() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-heading-backlinks-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Alpha.md"), "## Goals\nA quote ^blk1\n").unwrap();
+        fs::write(root.join("One.md"), "[[Alpha^blk1]] [[Alpha#Goals]]\n").unwrap();
+        fs::write(root.join("Two.md"), "![[Alpha#^blk1]] [[Alpha]]\n").unwrap();
+
+        let sections = find_backlinks_by_heading(&root, "Alpha", &ScanOptions::default()).unwrap();
+        let found: Vec<(Option<&str>, Option<&str>, &[String])> = sections
+            .iter()
+            .map(|s| (s.heading.as_deref(), s.block.as_deref(), s.backlinks.as_slice()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (None, None, &["Two.md".to_string()][..]),
+                (Some("Goals"), None, &["One.md".to_string()][..]),
+                (None, Some("blk1"), &["One.md".to_string(), "Two.md".to_string()][..]),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ranked_backlinks_skip_code_and_hidden_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-ranked-backlinks-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Alpha.md"), "Target\n").unwrap();
+        fs::write(root.join("Secret.md"), "---\nprivate: true\n---\nHidden\n").unwrap();
+        fs::write(root.join("Hub.md"), "# About [[Alpha]]\nMore on [[Alpha]]\n").unwrap();
+        fs::write(root.join("Code.md"), "Intro\n```\n[[Alpha]] [[Alpha]]\n```\nSee `[[Alpha]]` and [[Alpha]]\n").unwrap();
+        fs::write(root.join("Snippet.md"), "`[[Alpha]]`\n[[Secret]]\n").unwrap();
+        fs::write(root.join("Md.md"), "Intro\nSee [a](Alpha.md#Top) and `[b](Alpha.md)`\n").unwrap();
+
+        let options = ScanOptions::default();
+        let ranked = rank_backlinks(&root, "Alpha", &options).unwrap();
+        let found: Vec<(&str, usize, usize)> = ranked.iter().map(|b| (b.note.as_str(), b.mentions, b.first_line)).collect();
+        assert_eq!(found, [("Hub.md", 2, 1), ("Code.md", 1, 5), ("Md.md", 1, 2)]);
+        assert_eq!(rank_backlinks(&root, "Secret", &options).err().as_deref(), Some("note not found: Secret"));
+        assert!(rank_backlinks(&root, "Missing", &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn wikilinks_in_code_are_neither_found_nor_rewritten() {
+        let content = "---\nup: \"[[Old]]\"\n---\n[[Old|see]] `[[Old]]`\n```\n[[Old]] [[NotALink]]\n```\n![[Old#^block]]\n";
+        assert_eq!(extract_links_with_embeds(content), [
+            ("Old".to_string(), false),
+            ("Old".to_string(), false),
+            ("Old#^block".to_string(), true),
+        ]);
+        let (rewritten, count) = rewrite_wikilinks(content, |target| (target == "Old").then(|| "New".to_string()));
+        assert_eq!(count, 3);
+        assert_eq!(rewritten, "---\nup: \"[[New]]\"\n---\n[[New|see]] `[[Old]]`\n```\n[[Old]] [[NotALink]]\n```\n![[New#^block]]\n");
+    }
+
+    #[test]
+    fn private_notes_and_links_into_them_are_left_out_unless_included() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-private-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Home.md"), "[[Journal]] [[Diary]] [[Public]]").unwrap();
+        fs::write(root.join("Journal.md"), "---\nprivate: true\n---\n[[Public]]").unwrap();
+        fs::write(root.join("Diary.md"), "---\ntags: [private]\n---\n").unwrap();
+        fs::write(root.join("Health.md"), "Notes #private").unwrap();
+        fs::write(root.join("Public.md"), "").unwrap();
+
+        let links = |options: &ScanOptions| {
+            let (links, notes) = collect_all_links(&root, options).unwrap();
+            let mut notes: Vec<String> = notes.into_iter().collect();
+            notes.sort();
+            (links.into_iter().map(|l| format!("{}>{}", l.source, l.target)).collect::<Vec<_>>(), notes)
+        };
+        let (found, notes) = links(&ScanOptions::default());
+        assert_eq!(found, ["Home.md>Public.md"]);
+        assert_eq!(notes, ["Home.md", "Public.md"]);
+        let options = ScanOptions { include_private: true, ..ScanOptions::default() };
+        let (found, notes) = links(&options);
+        assert_eq!(found.len(), 4);
+        assert_eq!(notes.len(), 5);
+        assert_eq!(find_orphans(&root, &options).unwrap(), ["Health.md"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn fragile_links_are_anchored_ambiguous_or_to_often_renamed_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-fragility-{}", std::process::id()));
+        fs::create_dir_all(root.join("Work")).unwrap();
+        let git = |args: &[&str]| crate::git::run_git(&root, args).unwrap();
+        let commit = |message: &str| {
+            git(&["add", "-A"]);
+            git(&["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-q", "-m", message]);
+        };
+        git(&["init", "-q"]);
+        fs::write(root.join("Draft.md"), "").unwrap();
+        fs::write(root.join("Ideas.md"), "").unwrap();
+        fs::write(root.join("Work/Ideas.md"), "").unwrap();
+        fs::write(root.join("Home.md"), "[[Plan#Goals]] [[Ideas]] [[Work/Ideas]] [[Plan]] [[Draft]]").unwrap();
+        commit("first");
+        git(&["mv", "Draft.md", "Plan v1.md"]);
+        commit("rename");
+        git(&["mv", "Plan v1.md", "Plan.md"]);
+        commit("rename again");
+
+        let output = link_fragility(&root, &ScanOptions::default()).unwrap();
+        assert_eq!((output.total_links, output.anchored_links, output.ambiguous_links), (5, 1, 1));
+        assert_eq!(output.links_to_renamed_notes, Some(2));
+        assert_eq!(output.renamed_notes, BTreeMap::from([("Plan.md".to_string(), 2)]));
+        assert_eq!(output.fragile_links, 3);
+        assert_eq!(output.notes[0].path, "Home.md");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn placeholder_links_are_queued_instead_of_broken() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-placeholders-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("A.md"), "[[TODO: Write intro]] [[Missing]]").unwrap();
+        fs::write(root.join("B.md"), "[[TODO: Write intro#Draft]] [[TODO:Outline]]").unwrap();
+
+        let options = ScanOptions { placeholder_prefix: "TODO:".to_string(), ..ScanOptions::default() };
+        let (links, _) = collect_all_links(&root, &options).unwrap();
+        let flags: Vec<(&str, bool, bool)> = links.iter().map(|l| (l.target.as_str(), l.exists, l.placeholder)).collect();
+        assert_eq!(
+            flags,
+            [("TODO: Write intro", false, true), ("Missing", false, false), ("TODO: Write intro", false, true), ("TODO:Outline", false, true)]
+        );
+        let queue = find_placeholders(&root, &options).unwrap();
+        let queued: Vec<(&str, &str, usize)> = queue.placeholders.iter().map(|p| (p.target.as_str(), p.title.as_str(), p.sources.len())).collect();
+        assert_eq!(queued, [("TODO: Write intro", "Write intro", 2), ("TODO:Outline", "Outline", 1)]);
+        assert!(find_placeholders(&root, &ScanOptions::default()).unwrap().placeholders.is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn slug_collisions_find_clashing_note_urls_and_heading_anchors() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-slug-collisions-{}", std::process::id()));
+        fs::create_dir_all(root.join("Blog Posts")).unwrap();
+        fs::create_dir_all(root.join("blog_posts")).unwrap();
+        fs::write(root.join("Blog Posts/Hello World.md"), "# Set-up\n## Set up!\n```\n# Set up\n```\n# Other\n").unwrap();
+        fs::write(root.join("blog_posts/hello-world.md"), "").unwrap();
+        fs::write(root.join("Unique.md"), "").unwrap();
+
+        let output = find_slug_collisions(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(output.note_collisions.len(), 1);
+        assert_eq!(output.note_collisions[0].slug, "blog-posts/hello-world");
+        assert_eq!(output.note_collisions[0].notes, ["Blog Posts/Hello World.md", "blog_posts/hello-world.md"]);
+        assert_eq!(output.anchor_collisions.len(), 1);
+        let collision = &output.anchor_collisions[0];
+        assert_eq!((collision.path.as_str(), collision.anchor.as_str()), ("Blog Posts/Hello World.md", "set-up"));
+        assert_eq!(collision.headings.iter().map(|h| h.line).collect::<Vec<_>>(), [1, 2]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
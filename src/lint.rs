@@ -0,0 +1,1070 @@
+//! Vault checks: naming rules, link text, frontmatter schemas, similar tags and the CI gate.
+
+use crate::config::{Config, LinkTextConfig, NameCase, NamingConfig, ScanOptions, Severity};
+use crate::dates::modified_date;
+use crate::hooks::broken_link_targets;
+use crate::index::indexed_notes;
+use crate::links::{edit_distance, rewrite_wikilinks};
+use crate::parse::{
+    Frontmatter, extract_frontmatter, extract_link_definitions, extract_link_references, mask_code, split_link_anchor,
+};
+use crate::properties::property_type;
+use crate::scan::{
+    NoteResolver, hidden_notes, is_visible_entry, join_path, markdown_files, note_stem, read_note, relative_path, walk_vault,
+};
+use crate::tags::{TagCount, rename_tag_in_note};
+use crate::write::VaultWriter;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Serialize)]
+struct NamingViolation {
+    path: String,
+    /// "folder" or "note"
+    kind: &'static str,
+    /// "case", "spaces" or "date-prefix"
+    rules: Vec<&'static str>,
+    suggested: String,
+    /// Why `--fix-names` can't rename this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conflict: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RenamedPath {
+    from: String,
+    to: String,
+}
+
+/// Tags that look like one tag written several ways.
+#[derive(Serialize)]
+struct TagDuplicates {
+    /// Each spelling with the number of notes using it
+    tags: Vec<TagCount>,
+    suggested: String,
+    /// "case", "plural" and/or "spelling"
+    reasons: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct LintOutput {
+    naming: Vec<NamingViolation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renamed: Option<Vec<RenamedPath>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rewritten_notes: Option<Vec<String>>,
+    tags: Vec<TagDuplicates>,
+    /// Notes rewritten by `--fix-tags`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retagged_notes: Option<Vec<String>>,
+    /// Notes breaking the `--schema` rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<Vec<SchemaViolation>>,
+    /// With `--schema`, each folder's share of notes following its rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    drift: Option<Vec<FolderDrift>>,
+    references: Vec<ReferenceProblem>,
+    link_text: Vec<LinkTextProblem>,
+}
+
+impl LintOutput {
+    /// Whether anything is left to fix: every violation, or with `--fix-names` the ones
+    /// that couldn't be renamed, near-duplicate tags unless `--fix-tags` merged them, notes
+    /// breaking the schema, undefined references or unused definitions, and link text
+    /// problems whose rule is an error.
+    pub fn has_violations(&self) -> bool {
+        self.naming.iter().any(|v| self.renamed.is_none() || v.conflict.is_some())
+            || (!self.tags.is_empty() && self.retagged_notes.is_none())
+            || !self.references.is_empty()
+            || self.link_text.iter().any(|problem| problem.severity == Severity::Error)
+            || self.schema.as_ref().is_some_and(|violations| !violations.is_empty())
+    }
+}
+
+/// Rules a note has to meet for `gate`. Every rule is off unless the rules file sets it.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GateRules {
+    /// Frontmatter keys that must be present
+    required_frontmatter: Vec<String>,
+    no_broken_links: bool,
+    /// Tags the note must not carry, e.g. private or draft
+    forbidden_tags: Vec<String>,
+    min_words: usize,
+}
+
+/// Frontmatter rules for `lint --schema`, keyed by folder. A folder's rules apply to every
+/// note under it, and "" is the whole vault.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schema {
+    folders: BTreeMap<String, FolderSchema>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FolderSchema {
+    /// Keys every note must set
+    required: Vec<String>,
+    /// Tags every note must carry, itself or nested below it
+    tags: Vec<String>,
+    properties: BTreeMap<String, PropertySchema>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PropertySchema {
+    /// Allowed types, as listed by `properties`; one type or a list of them
+    #[serde(rename = "type", deserialize_with = "one_or_many")]
+    types: Vec<String>,
+    /// Allowed values; for a list, allowed items
+    values: Vec<serde_yaml::Value>,
+}
+
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
+/// A reference-style link or footnote without a definition, or a definition nothing uses.
+#[derive(Serialize)]
+struct ReferenceProblem {
+    path: String,
+    line: usize,
+    label: String,
+    footnote: bool,
+    /// "undefined" for a reference, "unused" for a definition
+    problem: &'static str,
+}
+
+/// A link whose text reads badly, per the `[link_text]` rules.
+#[derive(Serialize)]
+struct LinkTextProblem {
+    path: String,
+    line: usize,
+    /// 1-based, in characters
+    column: usize,
+    /// "bare_link", "redundant_alias" or "vague_text"
+    rule: &'static str,
+    severity: Severity,
+    /// The link as written
+    text: String,
+}
+
+#[derive(Serialize)]
+struct SchemaViolation {
+    path: String,
+    /// The frontmatter key, or the tag for a "tag" rule
+    key: String,
+    /// "required", "tag", "type", "value" or "invalid_frontmatter"
+    rule: &'static str,
+    detail: String,
+}
+
+/// How far the notes in a schema folder have drifted from its rules.
+#[derive(Serialize)]
+struct FolderDrift {
+    folder: String,
+    notes: usize,
+    /// Notes breaking none of the folder's rules
+    conforming: usize,
+    /// The rules notes break, with how many notes break each, most broken first
+    broken_rules: Vec<RuleDrift>,
+}
+
+#[derive(Serialize)]
+struct RuleDrift {
+    rule: &'static str,
+    key: String,
+    notes: usize,
+}
+
+impl FolderDrift {
+    /// Counts one more note breaking the rule.
+    fn broke(&mut self, rule: &'static str, key: &str) {
+        match self.broken_rules.iter_mut().find(|r| r.rule == rule && r.key == key) {
+            Some(existing) => existing.notes += 1,
+            None => self.broken_rules.push(RuleDrift { rule, key: key.to_string(), notes: 1 }),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GateFailure {
+    rule: &'static str,
+    detail: String,
+}
+
+#[derive(Serialize)]
+pub struct GateOutput {
+    pub note: String,
+    pub passed: bool,
+    pub failures: Vec<GateFailure>,
+}
+
+impl NamingConfig {
+    fn separator(&self) -> char {
+        if self.case == NameCase::Snake { '_' } else { '-' }
+    }
+
+    /// `name` in the configured case, keeping a leading date as it is.
+    fn cased(&self, name: &str) -> Option<String> {
+        if self.case == NameCase::Any {
+            return None;
+        }
+        let (date, rest) = DATE_PREFIX.find(name).map_or(("", name), |m| name.split_at(m.end()));
+        let words: Vec<String> = rest
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        let parts: Vec<&str> = std::iter::once(date)
+            .filter(|date| !date.is_empty())
+            .chain(words.iter().map(String::as_str))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(&self.separator().to_string()))
+    }
+
+    /// The rules `name` breaks and a name that keeps them. `date` is prefixed when the
+    /// name has to start with one.
+    fn check(&self, name: &str, date: Option<&str>) -> (Vec<&'static str>, String) {
+        let mut rules = Vec::new();
+        let mut fixed = name.to_string();
+        if self.cased(name).is_some_and(|cased| cased != name) {
+            rules.push("case");
+        }
+        if self.no_spaces && name.contains(' ') {
+            rules.push("spaces");
+            fixed = fixed.replace(' ', &self.separator().to_string());
+        }
+        if let Some(date) = date
+            && !DATE_PREFIX.is_match(name)
+        {
+            rules.push("date-prefix");
+            let joiner = if self.case == NameCase::Any && !self.no_spaces { ' ' } else { self.separator() };
+            fixed = format!("{}{}{}", date, joiner, fixed);
+        }
+        if let Some(cased) = self.cased(&fixed) {
+            fixed = cased;
+        }
+        (rules, fixed)
+    }
+
+    /// `check` for the note at vault-relative `file`, by its name without `.md`. Notes in a
+    /// date prefix folder have to start with a date, by default the day they were modified.
+    pub(crate) fn check_note(&self, vault_path: &Path, file: &str) -> (Vec<&'static str>, String) {
+        let stem = note_stem(file);
+        let dated = self
+            .date_prefix_folders
+            .iter()
+            .any(|folder| file.starts_with(&format!("{}/", folder.trim_end_matches('/'))));
+        let date = dated.then(|| modified_date(&vault_path.join(file)));
+        self.check(stem, date.as_deref())
+    }
+}
+
+static DATE_PREFIX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}").unwrap());
+
+/// Why `name` can't be used in `folder` (as it will be named after renaming), if it can't.
+fn naming_conflict(vault_path: &Path, targets: &HashSet<String>, folder: &str, name: &str) -> Option<String> {
+    let path = join_path(folder, name);
+    if targets.contains(&path) {
+        return Some(format!("another rename already targets {}", path));
+    }
+    // Compare exact names so that on a case-insensitive filesystem a case-only rename
+    // isn't blocked by the file itself
+    let taken = fs::read_dir(vault_path.join(folder))
+        .is_ok_and(|entries| entries.filter_map(|e| e.ok()).any(|e| e.file_name() == name));
+    taken.then(|| format!("{} already exists", path))
+}
+
+/// Checks folder and note names against the `[naming]` rules and looks for tags that are
+/// probably the same tag written differently. `fix_names` renames what it can and rewrites
+/// every link to a moved note or attachment so none of them break; `fix_tags` merges each
+/// group of tags into its suggested spelling.
+pub fn lint(
+    vault_path: &Path,
+    config: &Config,
+    fix_names: bool,
+    fix_tags: bool,
+    schema: Option<&Schema>,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<LintOutput, String> {
+    let mut output = lint_names(vault_path, config, fix_names, writer, options)?;
+    output.tags = near_duplicate_tags(vault_path, options);
+    if fix_tags {
+        output.retagged_notes = Some(merge_tags(vault_path, &output.tags, writer, options)?);
+    }
+    if let Some(schema) = schema {
+        let (violations, drift) = schema_violations(vault_path, schema, options);
+        output.schema = Some(violations);
+        output.drift = Some(drift);
+    }
+    output.references = reference_problems(vault_path, options);
+    output.link_text = link_text_problems(vault_path, &config.link_text, options);
+    Ok(output)
+}
+
+/// Links whose text reads badly, in path and position order: bare wikilinks in a sentence
+/// whose note name isn't written like text, aliases repeating their target, and vague text
+/// like "click here". Rules set to `off` aren't checked.
+fn link_text_problems(vault_path: &Path, rules: &LinkTextConfig, options: &ScanOptions) -> Vec<LinkTextProblem> {
+    static WIKILINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(!?)\[\[([^\]|]+)(?:\|([^\]]*))?\]\]").unwrap());
+    static MARKDOWN_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(!?)\[([^\[\]]*)\]\([^)\n]*\)").unwrap());
+    static DATE_PREFIX_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}.").unwrap());
+    let simplify = |text: &str| {
+        text.chars()
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let vague: HashSet<String> = rules.vague_phrases.iter().map(|phrase| simplify(phrase)).collect();
+    let awkward = |target: &str| {
+        let (note, _) = split_link_anchor(target);
+        let name = note.trim();
+        name.contains('/')
+            || name.ends_with(".md")
+            || DATE_PREFIX_REGEX.is_match(name)
+            || (!name.contains(' ') && name.contains(['-', '_']))
+    };
+
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut problems = Vec::new();
+    for path in files {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let masked = mask_code(&content);
+        let mut found: Vec<(std::ops::Range<usize>, &'static str, Severity)> = Vec::new();
+        for cap in WIKILINK_REGEX.captures_iter(&masked) {
+            if !cap[1].is_empty() {
+                continue;
+            }
+            let whole = cap.get(0).unwrap();
+            let target = &content[cap.get(2).unwrap().range()];
+            match cap.get(3).map(|alias| &content[alias.range()]) {
+                Some(alias) if alias.trim() == target.trim() => found.push((whole.range(), "redundant_alias", rules.redundant_alias)),
+                Some(alias) if vague.contains(&simplify(alias)) => found.push((whole.range(), "vague_text", rules.vague_text)),
+                Some(_) => {}
+                None => {
+                    // A link on a line of its own, as in a list of links, isn't in a sentence
+                    let line_start = content[..whole.start()].rfind('\n').map_or(0, |i| i + 1);
+                    let line_end = content[whole.end()..].find('\n').map_or(content.len(), |i| whole.end() + i);
+                    let rest = format!("{}{}", &masked[line_start..whole.start()], &masked[whole.end()..line_end]);
+                    let in_sentence = rest.trim_start_matches([' ', '\t', '-', '*', '>', '+']).chars().any(char::is_alphanumeric);
+                    if in_sentence && awkward(target) {
+                        found.push((whole.range(), "bare_link", rules.bare_links));
+                    }
+                }
+            }
+        }
+        for cap in MARKDOWN_LINK_REGEX.captures_iter(&masked) {
+            if cap[1].is_empty() && vague.contains(&simplify(&content[cap.get(2).unwrap().range()])) {
+                found.push((cap.get(0).unwrap().range(), "vague_text", rules.vague_text));
+            }
+        }
+        found.retain(|(_, _, severity)| *severity != Severity::Off);
+        found.sort_by_key(|(range, _, _)| range.start);
+        for (range, rule, severity) in found {
+            let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+            problems.push(LinkTextProblem {
+                path: relative.clone(),
+                line: content[..range.start].matches('\n').count() + 1,
+                column: content[line_start..range.start].chars().count() + 1,
+                rule,
+                severity,
+                text: content[range].to_string(),
+            });
+        }
+    }
+    problems
+}
+
+/// Reference-style links and footnotes with no definition in their note, and definitions
+/// no reference uses, in path and line order. A lone `[label]` only counts as a reference
+/// when it's defined, since square brackets are common in text.
+fn reference_problems(vault_path: &Path, options: &ScanOptions) -> Vec<ReferenceProblem> {
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut problems = Vec::new();
+    for path in files {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let definitions = extract_link_definitions(&content);
+        let references = extract_link_references(&content);
+        let defined: HashSet<(&str, bool)> = definitions.iter().map(|d| (d.label.as_str(), d.footnote)).collect();
+        let used: HashSet<(&str, bool)> = references.iter().map(|r| (r.label.as_str(), r.footnote)).collect();
+
+        let mut found: Vec<ReferenceProblem> = references
+            .iter()
+            .filter(|r| !r.shortcut && !defined.contains(&(r.label.as_str(), r.footnote)))
+            .map(|r| (r.line, &r.label, r.footnote, "undefined"))
+            .chain(
+                definitions
+                    .iter()
+                    .filter(|d| !used.contains(&(d.label.as_str(), d.footnote)))
+                    .map(|d| (d.line, &d.label, d.footnote, "unused")),
+            )
+            .map(|(line, label, footnote, problem)| ReferenceProblem {
+                path: relative.clone(),
+                line,
+                label: label.clone(),
+                footnote,
+                problem,
+            })
+            .collect();
+        found.sort_by_key(|problem| problem.line);
+        problems.extend(found);
+    }
+    problems
+}
+
+const PROPERTY_TYPES: [&str; 8] = ["text", "list", "number", "checkbox", "date", "datetime", "object", "empty"];
+
+pub fn load_schema(path: &Path) -> Result<Schema, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("failed to read schema {}: {}", path.display(), e))?;
+    let schema: Schema = serde_yaml::from_str(&content).map_err(|e| format!("invalid schema {}: {}", path.display(), e))?;
+    for (folder, rules) in &schema.folders {
+        for (key, property) in &rules.properties {
+            if let Some(kind) = property.types.iter().find(|kind| !PROPERTY_TYPES.contains(&kind.as_str())) {
+                return Err(format!(
+                    "invalid schema {}: {} in {:?} has unknown type {:?} (expected one of {})",
+                    path.display(),
+                    key,
+                    folder,
+                    kind,
+                    PROPERTY_TYPES.join(", ")
+                ));
+            }
+        }
+    }
+    Ok(schema)
+}
+
+/// Notes whose frontmatter or tags break the rules of the folders they're in, in path
+/// order, and how far each folder has drifted from its rules. Archived notes are only
+/// checked with `--include-archived`.
+fn schema_violations(vault_path: &Path, schema: &Schema, options: &ScanOptions) -> (Vec<SchemaViolation>, Vec<FolderDrift>) {
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut violations = Vec::new();
+    let mut drift: BTreeMap<&str, FolderDrift> = schema
+        .folders
+        .keys()
+        .map(|folder| {
+            let empty = FolderDrift {
+                folder: folder.clone(),
+                notes: 0,
+                conforming: 0,
+                broken_rules: Vec::new(),
+            };
+            (folder.as_str(), empty)
+        })
+        .collect();
+    for path in files {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let rules: Vec<(&str, &FolderSchema)> = schema
+            .folders
+            .iter()
+            .filter(|(folder, _)| {
+                let folder = folder.trim_matches('/');
+                folder.is_empty() || relative.starts_with(&format!("{}/", folder))
+            })
+            .map(|(folder, rules)| (folder.as_str(), rules))
+            .collect();
+        if rules.is_empty() {
+            continue;
+        }
+        let frontmatter = match extract_frontmatter(&content).map(|fm| Frontmatter::parse(&fm)) {
+            Some(Err(e)) => {
+                for (folder, _) in rules {
+                    let folder = drift.get_mut(folder).unwrap();
+                    folder.notes += 1;
+                    folder.broke("invalid_frontmatter", "");
+                }
+                violations.push(SchemaViolation {
+                    path: relative,
+                    key: String::new(),
+                    rule: "invalid_frontmatter",
+                    detail: format!("frontmatter is not valid YAML: {}", e),
+                });
+                continue;
+            }
+            Some(Ok(frontmatter)) => frontmatter,
+            None => Frontmatter::default(),
+        };
+        let tags = options.note_tags(&content);
+
+        for (folder, rules) in rules {
+            let mut found: Vec<(&str, &'static str, String)> = Vec::new();
+            for key in &rules.required {
+                if frontmatter.get(key).is_none() {
+                    found.push((key, "required", format!("{} is required", key)));
+                }
+            }
+            for tag in &rules.tags {
+                let tag = tag.trim_start_matches('#');
+                if !tags.iter().any(|t| t == tag || t.strip_prefix(tag).is_some_and(|rest| rest.starts_with('/'))) {
+                    found.push((tag, "tag", format!("#{} is required", tag)));
+                }
+            }
+            for (key, property) in &rules.properties {
+                let Some(value) = frontmatter.get(key) else {
+                    continue;
+                };
+                let kind = property_type(value);
+                if !property.types.is_empty() && !property.types.iter().any(|allowed| allowed == kind) {
+                    found.push((key, "type", format!("{} is {}, expected {}", key, kind, property.types.join(" or "))));
+                    continue;
+                }
+                if property.values.is_empty() {
+                    continue;
+                }
+                let items = match value {
+                    serde_yaml::Value::Sequence(items) => items.iter().collect(),
+                    value => vec![value],
+                };
+                for item in items {
+                    if !property.values.contains(item) {
+                        let shown = serde_yaml::to_string(item).unwrap_or_default();
+                        found.push((key, "value", format!("{} can't be {}", key, shown.trim_end())));
+                    }
+                }
+            }
+
+            let folder = drift.get_mut(folder).unwrap();
+            folder.notes += 1;
+            if found.is_empty() {
+                folder.conforming += 1;
+            }
+            let distinct: BTreeSet<(&'static str, &str)> = found.iter().map(|(key, rule, _)| (*rule, *key)).collect();
+            for (rule, key) in distinct {
+                folder.broke(rule, key);
+            }
+            violations.extend(found.into_iter().map(|(key, rule, detail)| SchemaViolation {
+                path: relative.clone(),
+                key: key.to_string(),
+                rule,
+                detail,
+            }));
+        }
+    }
+
+    let drift = drift
+        .into_values()
+        .map(|mut folder| {
+            folder.broken_rules.sort_by(|a, b| b.notes.cmp(&a.notes).then_with(|| (a.rule, &a.key).cmp(&(b.rule, &b.key))));
+            folder
+        })
+        .collect();
+    (violations, drift)
+}
+
+/// A tag's singular form, for telling `books` and `book` apart from unrelated tags.
+fn singular(tag: &str) -> String {
+    if let Some(stem) = tag.strip_suffix("ies").filter(|stem| stem.len() > 1) {
+        format!("{}y", stem)
+    } else if let Some(stem) = ["ches", "shes", "sses", "xes"].iter().find_map(|suffix| {
+        tag.strip_suffix(suffix).map(|stem| format!("{}{}", stem, &suffix[..suffix.len() - 2]))
+    }) {
+        stem
+    } else {
+        tag.strip_suffix('s').filter(|stem| !stem.ends_with('s') && stem.len() > 1).unwrap_or(tag).to_string()
+    }
+}
+
+/// Why two tags look like the same tag: "case", "plural" or "spelling" (one edit apart).
+/// Tags with digits are never spelling variants, so `q1` and `q2` stay apart.
+fn tag_similarity(a: &str, b: &str) -> Option<&'static str> {
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    if a == b {
+        Some("case")
+    } else if singular(&a) == singular(&b) {
+        Some("plural")
+    } else if a.chars().count().min(b.chars().count()) >= NEAR_DUPLICATE_MIN_CHARS
+        && !a.contains(|c: char| c.is_ascii_digit())
+        && !b.contains(|c: char| c.is_ascii_digit())
+        && edit_distance(&a, &b) == 1
+    {
+        Some("spelling")
+    } else {
+        None
+    }
+}
+
+/// Shorter tags are too easily one edit apart by chance to count as misspellings.
+const NEAR_DUPLICATE_MIN_CHARS: usize = 5;
+
+/// Groups of tags that differ only by case, plural or a one-letter typo, each with the
+/// spelling to merge them into: the most used one, preferring lower case.
+fn near_duplicate_tags(vault_path: &Path, options: &ScanOptions) -> Vec<TagDuplicates> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for note in indexed_notes(vault_path, options).notes.into_values() {
+        if note.hidden(options) {
+            continue;
+        }
+        for tag in note.tags.iter().map(|t| options.canonical_tag(t)).collect::<BTreeSet<_>>() {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    let tags: Vec<&String> = counts.keys().collect();
+
+    // Union-find over similar pairs, so book, books and Book end up in one group
+    let mut parent: Vec<usize> = (0..tags.len()).collect();
+    fn root(parent: &mut [usize], i: usize) -> usize {
+        if parent[i] != i {
+            parent[i] = root(parent, parent[i]);
+        }
+        parent[i]
+    }
+    let mut reasons: HashMap<usize, BTreeSet<&'static str>> = HashMap::new();
+    for i in 0..tags.len() {
+        for j in i + 1..tags.len() {
+            if let Some(reason) = tag_similarity(tags[i], tags[j]) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                let mut merged = reasons.remove(&a).unwrap_or_default();
+                merged.extend(reasons.remove(&b).unwrap_or_default());
+                merged.insert(reason);
+                parent[b] = a;
+                reasons.insert(a, merged);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<&String>> = BTreeMap::new();
+    for (i, tag) in tags.iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(tag);
+    }
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(group, variants)| {
+            let suggested = variants
+                .iter()
+                .max_by(|a, b| {
+                    counts[**a]
+                        .cmp(&counts[**b])
+                        .then_with(|| (a.to_lowercase() == ***a).cmp(&(b.to_lowercase() == ***b)))
+                        .then_with(|| b.cmp(a))
+                })
+                .map(|tag| tag.to_string())
+                .unwrap_or_default();
+            TagDuplicates {
+                tags: variants.iter().map(|tag| TagCount { tag: tag.to_string(), count: counts[*tag] }).collect(),
+                suggested,
+                reasons: reasons.remove(&group).unwrap_or_default().into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+/// Rewrites every variant in each group to its suggested tag, one write per note. Returns
+/// the visible notes that changed.
+fn merge_tags(vault_path: &Path, duplicates: &[TagDuplicates], writer: &mut VaultWriter, options: &ScanOptions) -> Result<Vec<String>, String> {
+    let renames: Vec<(&str, &str)> = duplicates
+        .iter()
+        .flat_map(|group| {
+            group
+                .tags
+                .iter()
+                .filter(|variant| variant.tag != group.suggested)
+                .map(|variant| (variant.tag.as_str(), group.suggested.as_str()))
+        })
+        .collect();
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut retagged = Vec::new();
+    for path in paths {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut renamed = content.clone();
+        for (old, new) in &renames {
+            renamed = rename_tag_in_note(&renamed, old, new, &options.tag_syntax).0;
+        }
+        if renamed != content {
+            writer.write(vault_path, &path, &renamed)?;
+            if !options.hides(&content) {
+                retagged.push(relative_path(vault_path, &path));
+            }
+        }
+    }
+    Ok(retagged)
+}
+
+/// Checks folder and note names against the `[naming]` rules. With `fix`, renames what it
+/// can and rewrites every link to a moved note or attachment so none of them break.
+fn lint_names(
+    vault_path: &Path,
+    config: &Config,
+    fix: bool,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<LintOutput, String> {
+    let naming = &config.naming;
+    let hidden = hidden_notes(vault_path, options);
+    let mut files: Vec<String> = walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| relative_path(vault_path, e.path()))
+        .collect();
+    files.sort();
+    let folders: BTreeSet<&str> = files
+        .iter()
+        .flat_map(|file| file.match_indices('/').map(|(i, _)| &file[..i]))
+        .collect();
+
+    let mut violations = Vec::new();
+    let mut targets = HashSet::new();
+    // Folders are visited parent first, so each one's new path builds on its parent's
+    let mut folder_paths: HashMap<&str, String> = HashMap::new();
+    for &folder in &folders {
+        let (parent, name) = folder.rsplit_once('/').unwrap_or(("", folder));
+        let parent = folder_paths.get(parent).cloned().unwrap_or_default();
+        let (rules, fixed) = naming.check(name, None);
+        let mut new_path = join_path(&parent, name);
+        if !rules.is_empty() {
+            let suggested = join_path(&parent, &fixed);
+            let conflict = naming_conflict(vault_path, &targets, &parent, &fixed);
+            if conflict.is_none() {
+                targets.insert(suggested.clone());
+                new_path = suggested.clone();
+            }
+            violations.push(NamingViolation {
+                path: folder.to_string(),
+                kind: "folder",
+                rules,
+                suggested,
+                conflict,
+            });
+        }
+        folder_paths.insert(folder, new_path);
+    }
+
+    let mut moves: Vec<(&str, String)> = Vec::new();
+    for file in &files {
+        let (parent, name) = file.rsplit_once('/').unwrap_or(("", file));
+        let parent = folder_paths.get(parent).cloned().unwrap_or_default();
+        let mut new_name = name.to_string();
+        if name.ends_with(".md") && !hidden.contains(file) {
+            let (rules, fixed) = naming.check_note(vault_path, file);
+            if !rules.is_empty() {
+                let fixed = format!("{}.md", fixed);
+                let conflict = naming_conflict(vault_path, &targets, &parent, &fixed);
+                if conflict.is_none() {
+                    targets.insert(join_path(&parent, &fixed));
+                    new_name = fixed.clone();
+                }
+                violations.push(NamingViolation {
+                    path: file.clone(),
+                    kind: "note",
+                    rules,
+                    suggested: join_path(&parent, &fixed),
+                    conflict,
+                });
+            }
+        }
+        let new_path = join_path(&parent, &new_name);
+        if new_path != *file {
+            moves.push((file, new_path));
+        }
+    }
+
+    if !fix {
+        return Ok(LintOutput {
+            naming: violations,
+            renamed: None,
+            rewritten_notes: None,
+            tags: Vec::new(),
+            retagged_notes: None,
+            schema: None,
+            drift: None,
+            references: Vec::new(),
+            link_text: Vec::new(),
+        });
+    }
+
+    let moved: HashMap<&str, &str> = moves.iter().map(|(from, to)| (*from, to.as_str())).collect();
+    let notes: Vec<&String> = files.iter().filter(|file| file.ends_with(".md")).collect();
+    let old_notes = NoteResolver::new(&notes);
+    let new_notes = NoteResolver::new(notes.iter().map(|note| moved.get(note.as_str()).copied().unwrap_or(note)));
+    let rewrite = |target: &str| {
+        let target = target.trim();
+        let replacement = match old_notes.resolve(target) {
+            Some(old) => {
+                let new = *moved.get(old.as_str())?;
+                let name = note_stem(new);
+                // Keep links by bare name when the new name still resolves to the note
+                if !target.contains('/') && new_notes.resolve(name).is_some_and(|note| note == new) {
+                    name.to_string()
+                } else {
+                    new.strip_suffix(".md").unwrap_or(new).to_string()
+                }
+            }
+            // Attachments linked by vault path
+            None => moved.get(target)?.to_string(),
+        };
+        (replacement != target).then_some(replacement)
+    };
+
+    let mut rewritten_notes = Vec::new();
+    for note in notes {
+        let path = vault_path.join(note);
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let (rewritten, count) = rewrite_wikilinks(&content, rewrite);
+        if count == 0 {
+            continue;
+        }
+        writer.write(vault_path, &path, &rewritten)?;
+        if !hidden.contains(note) {
+            rewritten_notes.push(moved.get(note.as_str()).copied().unwrap_or(note).to_string());
+        }
+    }
+    rewritten_notes.sort();
+
+    for (from, to) in &moves {
+        writer.rename(vault_path, &vault_path.join(from), &vault_path.join(to))?;
+    }
+
+    let renamed = violations
+        .iter()
+        .filter(|v| v.conflict.is_none())
+        .map(|v| RenamedPath {
+            from: v.path.clone(),
+            to: v.suggested.clone(),
+        })
+        .collect();
+    Ok(LintOutput {
+        naming: violations,
+        renamed: Some(renamed),
+        rewritten_notes: Some(rewritten_notes),
+        tags: Vec::new(),
+        retagged_notes: None,
+        schema: None,
+        drift: None,
+        references: Vec::new(),
+        link_text: Vec::new(),
+    })
+}
+
+pub fn load_gate_rules(path: &Path) -> Result<GateRules, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("failed to read rules {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content).map_err(|e| format!("invalid rules {}: {}", path.display(), e))
+}
+
+/// Checks a single note, private or not, against the publishing rules.
+pub fn run_gate(vault_path: &Path, note: &str, rules: &GateRules, options: &ScanOptions) -> Result<GateOutput, String> {
+    let all_notes: HashSet<String> = markdown_files(vault_path).iter().map(|p| relative_path(vault_path, p)).collect();
+    let resolver = NoteResolver::new(&all_notes).read_aliases(vault_path);
+    let relative = resolver.resolve(note).cloned().ok_or_else(|| format!("no note named {} in the vault", note))?;
+    let path = vault_path.join(&relative);
+    let content = fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+
+    let mut failures = Vec::new();
+    let frontmatter = Frontmatter::of(&content);
+    for key in &rules.required_frontmatter {
+        if frontmatter.get(key).is_none() {
+            failures.push(GateFailure {
+                rule: "required_frontmatter",
+                detail: format!("frontmatter has no {} key", key),
+            });
+        }
+    }
+
+    if rules.no_broken_links {
+        for target in broken_link_targets(&content, &resolver, options) {
+            failures.push(GateFailure {
+                rule: "no_broken_links",
+                detail: format!("[[{}]] does not resolve to a note", target),
+            });
+        }
+    }
+
+    let tags: BTreeSet<String> = options.note_tags(&content).into_iter().collect();
+    for tag in &rules.forbidden_tags {
+        let tag = tag.trim_start_matches('#');
+        if tags.contains(tag) {
+            failures.push(GateFailure {
+                rule: "forbidden_tags",
+                detail: format!("note is tagged #{}", tag),
+            });
+        }
+    }
+
+    let words = content.split_whitespace().count();
+    if words < rules.min_words {
+        failures.push(GateFailure {
+            rule: "min_words",
+            detail: format!("note has {} words, fewer than {}", words, rules.min_words),
+        });
+    }
+
+    Ok(GateOutput {
+        note: relative,
+        passed: failures.is_empty(),
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_rules_apply_per_folder() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-schema-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects/Old")).unwrap();
+        fs::write(root.join("Projects/a.md"), "---\nstatus: done\ntags: [x, y]\n---\n").unwrap();
+        fs::write(root.join("Projects/Old/b.md"), "---\nstatus: 3\n---\n").unwrap();
+        fs::write(root.join("Projects/d.md"), "---\nstatus: [unclosed\n---\n").unwrap();
+        fs::write(root.join("c.md"), "No frontmatter\n").unwrap();
+        let schema: Schema = serde_yaml::from_str(
+            "folders:\n  Projects:\n    required: [status]\n    properties:\n      status: {type: text, values: [done]}\n      tags: {values: [x]}\n",
+        )
+        .unwrap();
+        let (violations, drift) = schema_violations(&root, &schema, &ScanOptions::default());
+        let found: Vec<(String, &str, String)> = violations
+            .into_iter()
+            .map(|v| (v.path, v.rule, v.key))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Projects/Old/b.md".to_string(), "type", "status".to_string()),
+                ("Projects/a.md".to_string(), "value", "tags".to_string()),
+                ("Projects/d.md".to_string(), "invalid_frontmatter", String::new()),
+            ]
+        );
+        assert_eq!((drift[0].notes, drift[0].conforming, drift[0].broken_rules.len()), (3, 0, 3));
+
+        fs::write(root.join("Projects/a.md"), "---\nstatus: done\ntags: [x, source/book]\n---\n").unwrap();
+        fs::write(root.join("Projects/e.md"), "---\nstatus: done\n---\n#sources").unwrap();
+        let schema: Schema = serde_yaml::from_str("folders:\n  Projects/:\n    tags: ['#source']\n").unwrap();
+        let (violations, drift) = schema_violations(&root, &schema, &ScanOptions::default());
+        let found: Vec<(&str, &str)> = violations.iter().map(|v| (v.path.as_str(), v.rule)).collect();
+        assert_eq!(found, [("Projects/Old/b.md", "tag"), ("Projects/d.md", "invalid_frontmatter"), ("Projects/e.md", "tag")]);
+        assert_eq!((drift[0].notes, drift[0].conforming), (4, 1));
+        assert_eq!((drift[0].broken_rules[0].rule, drift[0].broken_rules[0].notes), ("tag", 2));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn similar_tags_are_found_by_case_plural_and_spelling() {
+        assert_eq!(tag_similarity("Book", "book"), Some("case"));
+        assert_eq!(tag_similarity("books", "Book"), Some("plural"));
+        assert_eq!(tag_similarity("stories", "story"), Some("plural"));
+        assert_eq!(tag_similarity("boxes", "box"), Some("plural"));
+        assert_eq!(tag_similarity("recipe", "recipie"), Some("spelling"));
+        assert_eq!(tag_similarity("class", "clas"), None);
+        assert_eq!(tag_similarity("cat", "car"), None);
+        assert_eq!(tag_similarity("sprint-1", "sprint-2"), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn naming_rules_suggest_fixed_names() {
+        let naming = NamingConfig {
+            case: NameCase::Kebab,
+            no_spaces: true,
+            date_prefix_folders: Vec::new(),
+        };
+        assert_eq!(naming.check("Big Plan", None), (vec!["case", "spaces"], "big-plan".to_string()));
+        assert_eq!(naming.check("big-plan", None), (vec![], "big-plan".to_string()));
+        assert_eq!(
+            naming.check("Standup", Some("2024-03-01")),
+            (vec!["case", "date-prefix"], "2024-03-01-standup".to_string())
+        );
+
+        let naming = NamingConfig {
+            case: NameCase::Snake,
+            ..naming
+        };
+        assert_eq!(naming.check("2024-03-01 Standup", None).1, "2024-03-01_standup");
+    }
+
+    #[test]
+    fn link_text_rules_flag_awkward_redundant_and_vague_links() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-link-text-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let content = "See [[meeting-notes]] and [[Plan]].\n- [[meeting-notes]]\n[[Plan|Plan]], [[Plan|here]], [Click here!](https://example.com)\n";
+        fs::write(root.join("Note.md"), content).unwrap();
+
+        let problems = link_text_problems(&root, &LinkTextConfig::default(), &ScanOptions::default());
+        let found: Vec<(usize, usize, &str)> = problems.iter().map(|p| (p.line, p.column, p.rule)).collect();
+        assert_eq!(found, [(1, 5, "bare_link"), (3, 1, "redundant_alias"), (3, 16, "vague_text"), (3, 31, "vague_text")]);
+
+        let rules = LinkTextConfig {
+            bare_links: Severity::Off,
+            ..LinkTextConfig::default()
+        };
+        assert_eq!(link_text_problems(&root, &rules, &ScanOptions::default()).len(), 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn gate_checks_a_note_against_the_publishing_rules() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-gate-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("rules.yml"), "required_frontmatter: [title, date]\nno_broken_links: true\nforbidden_tags: ['#draft']\nmin_words: 5\n").unwrap();
+        fs::write(root.join("bad-rules.yml"), "min_word: 5\n").unwrap();
+        fs::write(root.join("Post.md"), "---\ntitle: Post\n---\n#draft See [[Missing]]\n").unwrap();
+        fs::write(root.join("Ready.md"), "---\ntitle: Ready\ndate: 2024-01-02\n---\nA finished post linking [[Post]]\n").unwrap();
+
+        assert!(load_gate_rules(&root.join("bad-rules.yml")).is_err());
+        let rules = load_gate_rules(&root.join("rules.yml")).unwrap();
+        let options = ScanOptions::default();
+        let output = run_gate(&root, "Post", &rules, &options).unwrap();
+        assert!(!output.passed);
+        let failures: Vec<(&str, &str)> = output.failures.iter().map(|f| (f.rule, f.detail.as_str())).collect();
+        assert_eq!(
+            failures,
+            [
+                ("required_frontmatter", "frontmatter has no date key"),
+                ("no_broken_links", "[[Missing]] does not resolve to a note"),
+                ("forbidden_tags", "note is tagged #draft"),
+            ]
+        );
+        assert!(run_gate(&root, "Ready", &rules, &options).unwrap().passed);
+        assert!(run_gate(&root, "Nope", &rules, &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
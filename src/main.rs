@@ -1,10 +1,11 @@
-use clap::Parser;
-use regex::Regex;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use obsidian_cli::cli::*;
 use serde::Serialize;
-use std::collections::{BTreeMap, HashSet};
-use std::fs;
+use std::ffi::OsString;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
 #[command(name = "obsidian-cli")]
@@ -12,562 +13,2057 @@ use walkdir::WalkDir;
 #[command(about = "A CLI tool for reading Obsidian vaults")]
 #[command(after_help = "EXAMPLES:\n    \
     # List all tags with counts\n    \
-    obsidian-cli --tags\n\n    \
-    # Show vault statistics\n    \
-    obsidian-cli --stats\n\n    \
+    obsidian-cli tags\n\n    \
+    # Show vault statistics (also what runs without a command)\n    \
+    obsidian-cli stats\n    \
+    obsidian-cli stats --tree --depth 2\n\n    \
+    # Show tags, files, links or statistics as a table instead of JSON\n    \
+    obsidian-cli tags --format table\n\n    \
+    # Export links as CSV for a spreadsheet\n    \
+    obsidian-cli links --format csv > links.csv\n\n    \
+    # Stream links one JSON object per line as the vault is scanned\n    \
+    obsidian-cli links --format ndjson | head\n\n    \
+    # Export vault statistics for Prometheus\n    \
+    obsidian-cli stats --format openmetrics\n\n    \
+    # Write a Markdown report of broken links from a Handlebars template\n    \
+    obsidian-cli links --broken --template broken.hbs > Broken.md\n\n    \
+    # Use a vault other than the current directory\n    \
+    obsidian-cli --vault ~/Notes orphans\n\n    \
     # List all files with metadata\n    \
-    obsidian-cli --files\n\n    \
+    obsidian-cli files\n\n    \
     # Find broken links\n    \
-    obsidian-cli --links\n\n    \
-    # Find orphaned notes\n    \
-    obsidian-cli --orphans\n\n    \
+    obsidian-cli links --broken\n\n    \
+    # Find orphaned notes, or also lightly connected ones by isolation score\n    \
+    obsidian-cli orphans\n    \
+    obsidian-cli orphans --scored --threshold 0.8\n\n    \
+    # Query notes by tag, frontmatter and computed fields like Dataview\n    \
+    obsidian-cli query 'TABLE status, words FROM #project WHERE words > 500 SORT modified DESC'\n\n    \
+    # Path of the note best matching a partial title or alias, for opening from the shell\n    \
+    obsidian-cli find qrev --limit 1 --format ndjson | jq -r .path\n\n    \
+    # Find every line mentioning a phrase, highlighted in a table\n    \
+    obsidian-cli search \"quarterly review\" --format table\n\n    \
+    # The ten notes most relevant to some words\n    \
+    obsidian-cli search \"rust async runtime\" --ranked --limit 10\n\n    \
+    # Find TODO(name) markers and who they are assigned to\n    \
+    obsidian-cli search --regex 'TODO\\((\\w+)\\)'\n\n    \
     # Find notes with a specific tag\n    \
-    obsidian-cli --tag writing\n\n    \
+    obsidian-cli tag writing\n\n    \
     # Show backlinks to a note\n    \
-    obsidian-cli --backlinks \"My Note.md\"")]
+    obsidian-cli backlinks \"My Note.md\"\n\n    \
+    # Group backlinks by the heading they point to\n    \
+    obsidian-cli backlinks \"My Note.md\" --by-heading\n\n    \
+    # List the notes that reference a note most prominently first\n    \
+    obsidian-cli backlinks \"My Note.md\" --ranked\n\n    \
+    # Suggest noisy links and hub notes to prune from the graph\n    \
+    obsidian-cli graph suggest-prune\n\n    \
+    # Validate templates in the templates folder\n    \
+    obsidian-cli templates check\n\n    \
+    # Find notes where a template was inserted but its {{date}} or <% tp.* %> never filled in\n    \
+    obsidian-cli templates leftovers\n\n    \
+    # Report emoji and status symbols used across notes\n    \
+    obsidian-cli symbols\n\n    \
+    # Preview merging byte-identical attachments\n    \
+    obsidian-cli attachments dedupe --dry-run\n\n    \
+    # Break down vault size and flag files too large for mobile sync\n    \
+    obsidian-cli size-audit\n\n    \
+    # List bookmarked notes and the files recently open in Obsidian\n    \
+    obsidian-cli bookmarks\n    \
+    obsidian-cli recent --from-workspace\n\n    \
+    # Write an HTML dashboard to attach to a weekly email\n    \
+    obsidian-cli report --html report.html\n\n    \
+    # Report files that could not be read or parsed\n    \
+    obsidian-cli doctor\n\n    \
+    # List fenced code blocks by language, or dump the Python ones to files\n    \
+    obsidian-cli code\n    \
+    obsidian-cli code --lang python --out snippets/\n\n    \
+    # Track words written during a writing session\n    \
+    obsidian-cli session start\n    \
+    obsidian-cli session stop\n\n    \
+    # Generate or refresh an index note for a folder\n    \
+    obsidian-cli moc Projects --group-by folder --excerpts\n\n    \
+    # Find notes still using tag aliases from the config instead of the canonical tag\n    \
+    obsidian-cli tags aliases --report\n\n    \
+    # Generate a page per tag under Tags/ listing the notes with that tag\n    \
+    obsidian-cli tags materialize --folder Tags\n\n    \
+    # Count words written per day from git history\n    \
+    obsidian-cli activity --words\n\n    \
+    # Export link graph snapshots reconstructed from git history\n    \
+    obsidian-cli graph history --snapshots 20 --format json\n\n    \
+    # Render the link graph with Graphviz, colored by folder\n    \
+    obsidian-cli graph --color-by folder --exclude-orphans | dot -Tsvg > vault.svg\n\n    \
+    # Export the link graph with note and link attributes for Gephi\n    \
+    obsidian-cli graph --format gexf > vault.gexf\n\n    \
+    # Draw the notes within two links of one note as a Mermaid flowchart\n    \
+    obsidian-cli graph --around \"Note.md\" --depth 2 --format mermaid\n\n    \
+    # Draw only the project notes, with the notes they link to or from, or only the active ones\n    \
+    obsidian-cli graph --tag project --neighbors --format mermaid\n    \
+    obsidian-cli graph --query \"status = 'active'\" --format mermaid\n\n    \
+    # Show notes and links added or removed on a branch, for reviewing a pull request\n    \
+    obsidian-cli graph diff --from main --to HEAD\n\n    \
+    # List open tasks by due date, or as an agenda for the next week\n    \
+    obsidian-cli tasks --sort due\n    \
+    obsidian-cli tasks --agenda 7\n\n    \
+    # Score how fragile the vault's links are\n    \
+    obsidian-cli link-fragility\n\n    \
+    # Check staged notes from a git pre-commit hook\n    \
+    obsidian-cli hook pre-commit\n\n    \
+    # Delete a note, pointing links to it at another note first\n    \
+    obsidian-cli delete \"Old Plan.md\" --redirect-to \"Plan.md\"\n\n    \
+    # Report folder and note names breaking the naming rules, then rename them and fix links\n    \
+    obsidian-cli lint\n    \
+    obsidian-cli lint --fix-names --dry-run\n\n    \
+    # See which plugins left keys in frontmatter, and strip those of removed plugins\n    \
+    obsidian-cli frontmatter-audit --strip-unused --dry-run\n\n    \
+    # List [[TODO: ...]] placeholder links as a queue of notes to write\n    \
+    obsidian-cli placeholders\n\n    \
+    # Print the edits a command would make, for review by another tool\n    \
+    obsidian-cli attachments dedupe --plan-only\n\n    \
+    # Time indexing, parsing and queries on this vault with 1 to 4 threads\n    \
+    obsidian-cli bench --threads 4\n\n    \
+    # Check a note against publishing rules before it goes out\n    \
+    obsidian-cli gate Posts/Launch.md --rules publish.yaml\n\n    \
+    # Find notes and headings that would publish to the same URL or anchor\n    \
+    obsidian-cli slug-collisions\n\n    \
+    # Show a note's effective tags, aliases and CSS classes as Obsidian sees them\n    \
+    obsidian-cli inspect Projects/Alpha.md\n\n    \
+    # Turn pasted bare URLs into [Page title](url) links\n    \
+    obsidian-cli urls enrich --dry-run\n\n    \
+    # Rebuild the note index cache after a sync\n    \
+    obsidian-cli cache warm\n\n    \
+    # Run from anywhere inside a vault; the root is found by walking up to .obsidian\n    \
+    cd MyVault/Projects && obsidian-cli stats")]
 struct Cli {
-    /// Path to the Obsidian vault (defaults to current directory)
-    #[arg(value_name = "VAULT_PATH")]
-    #[arg(default_value = ".")]
-    vault_path: PathBuf,
+    /// Path to the Obsidian vault, or any folder inside it
+    #[arg(long, global = true, value_name = "PATH", default_value = ".")]
+    vault: PathBuf,
 
-    /// List all tags found in the vault with occurrence counts
+    /// Scan the vault path even if it is a home or filesystem root with no .obsidian folder
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Stop looking for the vault's .obsidian folder at DIR (repeatable; your home directory always stops the search)
+    #[arg(long, global = true, value_name = "DIR")]
+    stop_at: Vec<PathBuf>,
+
+    /// Path to a config file (defaults to .obsidian-cli.toml in the vault)
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Include notes marked `private: true` or tagged #private, which are left out by default
+    #[arg(long, global = true)]
+    include_private: bool,
+
+    /// Count notes in the archive folder in orphans, stats and link health like any other note
+    #[arg(long, global = true)]
+    include_archived: bool,
+
+    /// Extract tags using exactly the Obsidian app's tag grammar
+    #[arg(long, global = true)]
+    obsidian_strict: bool,
+
+    /// Render the command's JSON result through a Handlebars template instead of printing it
+    #[arg(long, global = true, value_name = "FILE")]
+    template: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+/// Options shared by every command that modifies the vault.
+#[derive(Args)]
+struct WriteArgs {
+    /// Report what would change without modifying any files
     #[arg(long)]
-    tags: bool,
+    dry_run: bool,
 
-    /// Show vault statistics
+    /// Print the planned file changes (byte ranges with before/after text) instead of making them
     #[arg(long)]
-    stats: bool,
+    plan_only: bool,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List all tags found in the vault with occurrence counts
+    Tags {
+        #[command(subcommand)]
+        action: Option<TagsCommand>,
+
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+
+        /// Show nested tags as a tree, with counts totalled up each level of the hierarchy
+        #[arg(long, conflicts_with = "format")]
+        tree: bool,
+
+        /// List pairs of tags used together in the same notes, with how many notes share them
+        #[arg(long, conflicts_with = "tree")]
+        cooccurrence: bool,
+
+        /// With --cooccurrence, leave out pairs shared by fewer notes than this
+        #[arg(long, value_name = "N", default_value_t = 1, requires = "cooccurrence")]
+        min_count: usize,
+    },
+
+    /// Find notes whose tags match a tag or a combination of tags
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Tag {
+        #[command(subcommand)]
+        action: Option<TagCommand>,
+
+        /// Tag to look for, or an expression like "(writing AND draft) NOT archive"
+        /// combining tags with AND, OR, NOT, -tag and parentheses. Tags may use * and ?
+        /// wildcards, like project/*
+        #[arg(required = true)]
+        tag: Option<String>,
+
+        /// Also match tags nested under the given ones, so project finds project/alpha
+        #[arg(long)]
+        include_subtags: bool,
+    },
+
+    /// Show vault statistics
+    Stats {
+        /// Output format for statistics
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+
+        /// Break statistics down by folder as a tree with per-subtree totals
+        #[arg(long, conflicts_with = "format")]
+        tree: bool,
+
+        /// Collapse folders nested deeper than this into their parent
+        #[arg(long, value_name = "N", requires = "tree")]
+        depth: Option<usize>,
+    },
 
     /// List all markdown files with metadata
-    #[arg(long)]
-    files: bool,
+    Files {
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
 
-    /// List all links and show broken links
-    #[arg(long)]
-    links: bool,
+    /// List all links with whether their target exists
+    Links {
+        /// Only list links whose target doesn't exist, or whose #heading or ^block doesn't
+        #[arg(long)]
+        broken: bool,
 
-    /// Find orphaned notes (notes with no incoming or outgoing links)
-    #[arg(long)]
-    orphans: bool,
+        /// Only list `![[embeds]]` of notes and attachments
+        #[arg(long, conflicts_with = "no_embeds")]
+        embeds_only: bool,
 
-    /// Find notes containing a specific tag
-    #[arg(long, value_name = "TAG")]
-    tag: Option<String>,
+        /// Leave `![[embeds]]` out, listing only links
+        #[arg(long)]
+        no_embeds: bool,
 
-    /// Show which notes link to a specific note
-    #[arg(long, value_name = "FILE")]
-    backlinks: Option<String>,
-}
+        /// List the http(s) URLs in notes instead of links between notes
+        #[arg(long, conflicts_with_all = ["embeds_only", "no_embeds"])]
+        external: bool,
 
-#[derive(Serialize)]
-struct TagCount {
-    tag: String,
-    count: usize,
-}
+        /// With --external, request every URL to find dead ones (--broken also checks, listing only those)
+        #[arg(long, requires = "external")]
+        check: bool,
 
-#[derive(Serialize)]
-struct TagsOutput {
-    tags: Vec<TagCount>,
-}
+        /// Number of URLs to check at once
+        #[arg(long, value_name = "N", default_value_t = 8, requires = "external")]
+        jobs: usize,
 
-#[derive(Serialize)]
-struct StatsOutput {
-    total_notes: usize,
-    total_tags: usize,
-    total_links: usize,
-    broken_links: usize,
-    orphaned_notes: usize,
-}
+        /// Count a URL as dead when it doesn't answer within this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 10, requires = "external")]
+        timeout: u64,
 
-#[derive(Serialize)]
-struct FileInfo {
-    path: String,
-    word_count: usize,
-    link_count: usize,
-    tag_count: usize,
-    modified: String,
-}
+        /// Rewrite broken links to the existing note they most likely meant, asking before each
+        #[arg(long, conflicts_with_all = ["external", "embeds_only"])]
+        fix: bool,
 
-#[derive(Serialize)]
-struct FilesOutput {
-    files: Vec<FileInfo>,
-}
+        /// With --fix, rewrite every link without asking
+        #[arg(long, requires = "fix")]
+        yes: bool,
+
+        #[command(flatten)]
+        write: WriteArgs,
+
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
+
+    /// Find orphaned notes (notes with no incoming or outgoing links)
+    Orphans {
+        /// Score every note's isolation from 0 to 1 instead of listing only notes without links
+        #[arg(long)]
+        scored: bool,
+
+        /// With --scored, list notes scoring at least this
+        #[arg(long, value_name = "SCORE", default_value_t = 0.8, requires = "scored")]
+        threshold: f64,
+
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
+
+    /// Find notes by a fuzzy match of part of their name or an alias, best match first
+    Find {
+        query: String,
+
+        /// Show at most this many notes
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        #[arg(long, value_enum, default_value = "json")]
+        format: ListFormat,
+    },
+
+    /// Run a Dataview-style query, like `LIST FROM #project WHERE words > 500 SORT modified DESC`
+    Query {
+        /// LIST or TABLE field, ..., then optionally FROM #tag/"folder", WHERE, SORT and LIMIT
+        query: String,
+    },
+
+    /// Extract the Markdown tables in a note, or in every note, as rows keyed by column
+    Tables {
+        /// Note to read the tables of
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        note: Option<String>,
+
+        /// Read the tables of every note
+        #[arg(long)]
+        all: bool,
+
+        /// Keep only these columns, in this order (comma-separated)
+        #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+        select: Vec<String>,
+
+        /// Keep only rows matching a condition, written as in query's WHERE, like `Status != "done" AND Price > 10`
+        #[arg(long = "where", value_name = "CONDITION")]
+        condition: Option<String>,
+
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
+
+    /// Find text in note bodies, case-insensitively
+    Search {
+        query: String,
+
+        /// Treat the query as a case-sensitive regular expression and report its capture groups
+        #[arg(long, conflicts_with = "ranked")]
+        regex: bool,
+
+        /// List notes containing any of the query's words, most relevant first (BM25)
+        #[arg(long)]
+        ranked: bool,
+
+        /// Show at most this many matches, or notes with --ranked
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Show each match in its surrounding lines, rendered as colored Markdown with
+        /// highlighted code blocks, instead of as --format output
+        #[arg(long, conflicts_with = "ranked")]
+        render_matches: bool,
+
+        /// Lines to show either side of each match with --render-matches
+        #[arg(long, value_name = "N", default_value_t = 3, requires = "render_matches")]
+        context: usize,
+
+        #[arg(long, value_enum, default_value = "json")]
+        format: ListFormat,
+    },
 
-#[derive(Serialize)]
-struct LinkInfo {
-    source: String,
-    target: String,
-    exists: bool,
+    /// Show which notes link to a specific note
+    Backlinks {
+        /// Note to find backlinks to
+        note: String,
+
+        /// Group backlinks by the heading or block of the target note they point to
+        #[arg(long)]
+        by_heading: bool,
+
+        /// Order backlinks by how prominently each note references this one: how often, how
+        /// early and whether from a heading
+        #[arg(long, conflicts_with = "by_heading")]
+        ranked: bool,
+    },
+
+    /// Inventory (list) or validate (check) note templates in the templates folder, or find
+    /// notes with unreplaced template placeholders (leftovers)
+    Templates {
+        #[arg(value_enum)]
+        action: TemplatesAction,
+    },
+
+    /// Report emoji and status symbols used across notes
+    Symbols,
+
+    /// Work with attachments: images, PDFs and other non-note files
+    Attachments {
+        #[command(subcommand)]
+        action: AttachmentsCommand,
+    },
+
+    /// Break down vault size by folder and file type and flag files too large for mobile sync
+    SizeAudit,
+
+    /// List notes, folders and searches bookmarked in Obsidian
+    Bookmarks,
+
+    /// List recently modified notes
+    Recent {
+        /// Take recent files from Obsidian's workspace instead of file modification times
+        #[arg(long)]
+        from_workspace: bool,
+    },
+
+    /// Write a single-file HTML dashboard of vault statistics, charts, top lists and broken links,
+    /// or print a Markdown table of totals per tag
+    Report {
+        #[arg(long, value_name = "FILE", required_unless_present = "group_by", conflicts_with = "group_by")]
+        html: Option<PathBuf>,
+
+        /// Print notes, words, open tasks and last activity per group as a Markdown table
+        #[arg(long, value_enum)]
+        group_by: Option<ReportGrouping>,
+
+        /// Tags to group by, with * wildcards; project/* gives one row per project
+        #[arg(long, value_name = "PATTERN", default_value = "*", requires = "group_by")]
+        tags: String,
+
+        /// With --html, the day the activity heatmap's weeks start on (defaults to week_start
+        /// in the config)
+        #[arg(long, value_enum, value_name = "DAY", requires = "html")]
+        week_starts: Option<WeekStart>,
+    },
+
+    /// Report every file that failed to read or parse, and how it affects results
+    Doctor,
+
+    /// Copy the vault with names hashed and text replaced by lorem ipsum, keeping links, tags
+    /// and frontmatter keys, to share when reporting a parsing bug
+    Scrub {
+        /// Folder to write the copy to; must not exist yet, or be empty
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+    },
+
+    /// Merge the notes and attachments of one vault into another, renaming files whose name is
+    /// already taken and rewriting links to them
+    VaultMerge {
+        /// Vault to copy from; it is left unchanged
+        src: PathBuf,
+
+        /// Vault to merge into
+        dst: PathBuf,
+
+        /// Where a file goes when the destination has a different file with its name
+        #[arg(long, value_enum, default_value_t = MergeConflict::Suffix)]
+        conflict: MergeConflict,
+
+        /// Folder for --conflict folder, instead of the source vault's name
+        #[arg(long, value_name = "FOLDER")]
+        prefix: Option<String>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// List the commands, output formats, cargo features and JSON schema versions of this binary
+    Capabilities {
+        #[arg(long, value_enum, default_value_t = ListFormat::Json)]
+        format: ListFormat,
+    },
+
+    /// List fenced code blocks across the vault grouped by language
+    Code {
+        /// Only include code blocks in this language
+        #[arg(long, value_name = "LANG")]
+        lang: Option<String>,
+
+        /// Write each matching code block to a file in this directory
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+
+    /// Start, check on, or stop a writing session that tracks words added and removed
+    Session {
+        #[arg(value_enum)]
+        action: SessionAction,
+    },
+
+    /// Create or update an index note (map of content) listing every note in a folder
+    Moc {
+        folder: String,
+
+        /// Group the notes listed in the map of content
+        #[arg(long, value_enum, value_name = "GROUP")]
+        group_by: Option<MocGrouping>,
+
+        /// Include a one-line excerpt of each note in the map of content
+        #[arg(long)]
+        excerpts: bool,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Report frontmatter keys added by plugins and which notes each plugin has touched
+    FrontmatterAudit {
+        /// Remove keys belonging to plugins that are no longer enabled in the vault
+        #[arg(long)]
+        strip_unused: bool,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// List every frontmatter key in the vault with how many notes use it and the types of its values
+    Properties,
+
+    /// List placeholder links (e.g. [[TODO: topic]]) as a queue of notes still to be written
+    Placeholders,
+
+    /// Time index building, parsing throughput and query latency on this vault
+    Bench {
+        /// Highest thread count to benchmark parsing with (defaults to the number of CPUs)
+        #[arg(long, value_name = "N")]
+        threads: Option<usize>,
+    },
+
+    /// Check one note against a YAML rule set, exiting non-zero when it fails any rule
+    Gate {
+        note: String,
+
+        /// YAML file with the rules to check
+        #[arg(long, value_name = "FILE")]
+        rules: PathBuf,
+    },
+
+    /// Report notes whose paths slugify to the same URL and headings that produce duplicate anchors
+    SlugCollisions,
+
+    /// Show a note's effective metadata, merging frontmatter and inline tags like Obsidian does
+    Inspect { note: String },
+
+    /// Report commits and changed notes per day from the vault's git history
+    Activity {
+        /// Also count words added and removed per day, from word diffs
+        #[arg(long)]
+        words: bool,
+    },
+
+    /// Export the vault's link graph, trace or compare it through git history, or suggest links to prune
+    #[command(group(clap::ArgGroup::new("filter").multiple(true)))]
+    Graph {
+        #[command(subcommand)]
+        action: Option<GraphCommand>,
+
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+
+        /// Fill notes with one color per folder or per (first) tag (DOT only)
+        #[arg(long, value_enum, value_name = "BY")]
+        color_by: Option<GraphColoring>,
+
+        /// Leave out notes with no links to or from other notes
+        #[arg(long)]
+        exclude_orphans: bool,
+
+        /// Only export the notes around this one
+        #[arg(long, value_name = "NOTE")]
+        around: Option<String>,
+
+        /// With --around, how many links away from the note to go
+        #[arg(long, value_name = "N", default_value_t = 1, requires = "around")]
+        depth: usize,
+
+        /// Only export notes with a tag matching this pattern, like `project` or `project/*`
+        #[arg(long, value_name = "PATTERN", conflicts_with = "around", group = "filter")]
+        tag: Option<String>,
+
+        /// Only export notes matching a condition, written as in a query's WHERE
+        #[arg(long, value_name = "CONDITION", conflicts_with = "around", group = "filter")]
+        query: Option<String>,
+
+        /// With --tag or --query, also export the notes linked to or from a matching note
+        #[arg(long, requires = "filter")]
+        neighbors: bool,
+    },
+
+    /// List tasks (checkbox items) across the vault, including Tasks plugin dates and priorities
+    Tasks {
+        /// Order tasks by a date field or by priority
+        #[arg(long, value_enum, value_name = "KEY")]
+        sort: Option<TaskSort>,
+
+        /// Group open tasks by due or scheduled day for the next N days
+        #[arg(long, value_name = "DAYS", conflicts_with = "sort")]
+        agenda: Option<u32>,
+
+        /// With --agenda, the day weeks start on (defaults to week_start in the config)
+        #[arg(long, value_enum, value_name = "DAY", requires = "agenda")]
+        week_starts: Option<WeekStart>,
+
+        /// Include completed and cancelled tasks
+        #[arg(long)]
+        include_done: bool,
+    },
+
+    /// Score how fragile links are: heading anchors, ambiguous names and renamed targets
+    LinkFragility,
+
+    /// Mount read-only derived views (by-tag, orphans, backlinks) of the vault at DIR
+    #[cfg(feature = "fuse")]
+    Mount {
+        #[arg(value_name = "DIR")]
+        mountpoint: PathBuf,
+    },
+
+    /// Git hooks that check notes before they are committed
+    Hook {
+        #[command(subcommand)]
+        action: HookCommand,
+    },
+
+    /// Watch the vault and report, with a desktop notification, each save that introduces a
+    /// broken link or invalid frontmatter
+    Guard {
+        /// Seconds between checks for saved notes
+        #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+        interval: u64,
+
+        /// Only print problems, one JSON object per line, without desktop notifications
+        #[arg(long)]
+        no_notify: bool,
+    },
+
+    /// Create a note, filled in from a template with --from-template, and print its path
+    New {
+        title: String,
+
+        /// The note template to fill in, by name in the templates folder or by vault path
+        #[arg(long = "from-template", value_name = "TEMPLATE")]
+        note_template: Option<String>,
+
+        /// Vault-relative folder for the note (defaults to Obsidian's folder for new notes)
+        #[arg(long, value_name = "FOLDER")]
+        folder: Option<String>,
+
+        /// Add a Related section linking the N notes most relevant to the title
+        #[arg(long, value_name = "N")]
+        related: Option<usize>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Rename or move a note, rewriting every link to it across the vault
+    Rename {
+        /// The note, by path or name
+        old: String,
+
+        /// Its new vault-relative path; `.md` is added when missing
+        new: String,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Move a note into another folder, rewriting path links to it and its own relative links
+    Move {
+        /// The note, by path or name
+        note: String,
+
+        /// Vault-relative folder to move it into, created if needed (`/` for the vault root)
+        folder: String,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Move a note to .trash, refusing while other notes still link to it
+    Delete {
+        note: String,
+
+        /// Point links to the note at this note instead before deleting it
+        #[arg(long, value_name = "NOTE")]
+        redirect_to: Option<String>,
+
+        /// Delete the note even though other notes link to it, leaving those links broken
+        #[arg(long, conflicts_with = "redirect_to")]
+        force: bool,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Check folder and note names against the [naming] rules in the config and look for tags
+    /// differing only by case, plural or a typo, exiting non-zero on violations
+    Lint {
+        /// Rename folders and notes to follow the rules, rewriting links to them
+        #[arg(long)]
+        fix_names: bool,
+
+        /// Merge each group of near-duplicate tags into its suggested tag
+        #[arg(long, conflicts_with = "fix_names")]
+        fix_tags: bool,
+
+        /// Check notes' frontmatter against the required keys, types and values per folder in
+        /// this YAML file
+        #[arg(long, value_name = "FILE")]
+        schema: Option<PathBuf>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Add a template's frontmatter keys and sections to existing notes that lack them
+    ApplyTemplate {
+        /// The template, by name in the templates folder or by vault path
+        #[arg(value_name = "TEMPLATE")]
+        template_note: String,
+
+        /// Only notes matching this condition, written as in a query's WHERE
+        #[arg(long = "where", visible_alias = "query", value_name = "CONDITION")]
+        condition: Option<String>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Set or remove a frontmatter property across notes
+    Meta {
+        #[command(subcommand)]
+        action: MetaCommand,
+    },
+
+    /// Work with external URLs in notes
+    Urls {
+        #[command(subcommand)]
+        action: UrlsCommand,
+    },
+
+    /// List daily notes and notes created on the same date in earlier years
+    OnThisDay {
+        /// The date to look back from, as YYYY-MM-DD or DD.MM.YYYY (default today)
+        #[arg(long, value_name = "DATE")]
+        date: Option<String>,
+
+        /// Add the list as an "On this day" section to the date's daily note
+        #[arg(long)]
+        append: bool,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+
+    /// Train models on the vault, kept in the cache directory
+    Ml {
+        #[command(subcommand)]
+        action: MlCommand,
+    },
+
+    /// Predict tags for untagged notes, or for one note, with a model trained by `ml train-tags`
+    SuggestTags {
+        /// Only this note, tagged or not
+        note: Option<String>,
+
+        /// Use this model file instead of the vault's trained one
+        #[arg(long, value_name = "FILE")]
+        model: Option<PathBuf>,
+
+        /// Only suggest tags with at least this confidence, from 0 to 1
+        #[arg(long, value_name = "P", default_value_t = 0.7)]
+        threshold: f64,
+
+        /// Suggest at most this many tags per note
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        limit: usize,
+    },
+
+    /// Inspect (status), rebuild (warm) or delete (clear) the note index cache
+    Cache {
+        #[arg(value_enum)]
+        action: CacheAction,
+    },
+
+    /// Summarize new and deleted notes, tag changes and added, removed and newly broken
+    /// links since the note index was last updated (by this, tags, files or cache warm)
+    Whatsnew,
 }
 
-#[derive(Serialize)]
-struct LinksOutput {
-    links: Vec<LinkInfo>,
-    broken_count: usize,
+#[derive(Subcommand)]
+enum MetaCommand {
+    /// Set a property in every matching note, replacing its old value in place
+    Set {
+        key: String,
+
+        /// The value, written as YAML (`done`, `5`, `true`, `[a, b]`); quoted if it needs to be
+        value: String,
+
+        /// Only notes matching this condition, written as in a query's WHERE
+        #[arg(long = "where", value_name = "CONDITION")]
+        condition: Option<String>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Remove a property, with any nested lines under it, from every matching note
+    Remove {
+        key: String,
+
+        /// Only notes matching this condition, written as in a query's WHERE
+        #[arg(long = "where", value_name = "CONDITION")]
+        condition: Option<String>,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
 }
 
-#[derive(Serialize)]
-struct OrphansOutput {
-    orphans: Vec<String>,
+#[derive(Subcommand)]
+enum AttachmentsCommand {
+    /// Find byte-identical attachments, point embeds at one copy and move the rest to .trash
+    Dedupe {
+        #[command(flatten)]
+        write: WriteArgs,
+    },
 }
 
-#[derive(Serialize)]
-struct TagSearchOutput {
-    tag: String,
-    files: Vec<String>,
+#[derive(Subcommand)]
+enum HookCommand {
+    /// Check staged notes for new broken links, invalid frontmatter and names breaking the
+    /// [naming] rules, exiting non-zero on problems
+    PreCommit,
 }
 
-#[derive(Serialize)]
-struct BacklinksOutput {
-    file: String,
-    backlinks: Vec<String>,
+#[derive(Subcommand)]
+enum UrlsCommand {
+    /// Fetch the page title of every bare URL and rewrite it as a [Title](url) link
+    Enrich {
+        /// Number of pages to fetch at once
+        #[arg(long, value_name = "N", default_value_t = 8)]
+        jobs: usize,
+
+        /// Give up on a page after this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timeout: u64,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
 }
 
-fn extract_tags_from_file(content: &str) -> Vec<String> {
-    let mut tags = Vec::new();
+#[derive(Subcommand)]
+enum MlCommand {
+    /// Train a classifier on the vault's tagged notes for `suggest-tags`, fully offline
+    TrainTags {
+        /// Only learn tags used by at least this many notes
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        min_notes: usize,
+
+        /// Save the model here instead of the cache directory
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+}
 
-    // Match inline tags like #tag or #tag/subtag
-    let inline_tag_regex = Regex::new(r"(?:^|\s)#([a-zA-Z0-9_/-]+)").unwrap();
-    for cap in inline_tag_regex.captures_iter(content) {
-        if let Some(tag) = cap.get(1) {
-            tags.push(tag.as_str().to_string());
-        }
-    }
+#[derive(Subcommand)]
+enum GraphCommand {
+    /// Suggest low-value links and high fan-out notes to prune from the graph
+    SuggestPrune,
+
+    /// Reconstruct the link graph at several points in the vault's git history
+    History {
+        /// Number of evenly spaced commits to take graph snapshots at
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        snapshots: usize,
+
+        #[arg(long, value_enum, default_value_t = HistoryFormat::Json)]
+        format: HistoryFormat,
+    },
+
+    /// Report notes and links added or removed between two revisions
+    Diff {
+        /// Revision to compare from
+        #[arg(long, value_name = "REV")]
+        from: String,
+
+        /// Revision to compare to
+        #[arg(long, value_name = "REV", default_value = "HEAD")]
+        to: String,
+    },
+}
 
-    // Match frontmatter tags
-    if let Some(frontmatter) = extract_frontmatter(content) {
-        if let Some(fm_tags) = parse_frontmatter_tags(&frontmatter) {
-            tags.extend(fm_tags);
-        }
-    }
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Rename a tag, and the tags nested under it, in note text and frontmatter across the vault
+    Rename {
+        old: String,
+        new: String,
 
-    tags
+        #[command(flatten)]
+        write: WriteArgs,
+    },
 }
 
-fn extract_frontmatter(content: &str) -> Option<String> {
-    if content.starts_with("---\n") {
-        if let Some(end_pos) = content[4..].find("\n---\n") {
-            return Some(content[4..4 + end_pos].to_string());
-        }
-    }
-    None
+#[derive(Subcommand)]
+enum TagsCommand {
+    /// Show the tag aliases from the config
+    Aliases {
+        /// Also list notes still using an alias instead of its canonical tag
+        #[arg(long)]
+        report: bool,
+    },
+
+    /// Create or update one note per tag in a folder, listing every note with that tag
+    Materialize {
+        /// Folder to write tag pages to
+        #[arg(long, value_name = "FOLDER", default_value = "Tags")]
+        folder: String,
+
+        #[command(flatten)]
+        write: WriteArgs,
+    },
 }
 
-fn parse_frontmatter_tags(frontmatter: &str) -> Option<Vec<String>> {
-    let mut tags = Vec::new();
-
-    for line in frontmatter.lines() {
-        let line = line.trim();
+/// Template given with --template, applied to every command's JSON result.
+static TEMPLATE: OnceLock<String> = OnceLock::new();
 
-        // Match "tags: tag1" or "tags: [tag1, tag2]"
-        if line.starts_with("tags:") {
-            let tags_part = line.strip_prefix("tags:").unwrap().trim();
-
-            // Handle array format [tag1, tag2]
-            if tags_part.starts_with('[') && tags_part.ends_with(']') {
-                let tags_str = &tags_part[1..tags_part.len() - 1];
-                for tag in tags_str.split(',') {
-                    let tag = tag.trim().trim_matches('"').trim_matches('\'');
-                    if !tag.is_empty() {
-                        tags.push(tag.to_string());
-                    }
-                }
-            } else if !tags_part.is_empty() {
-                // Handle single tag format
-                let tag = tags_part.trim_matches('"').trim_matches('\'');
-                tags.push(tag.to_string());
-            }
-        }
-        // Handle list format
-        else if line.starts_with("- ") && !tags.is_empty() {
-            let tag = line.strip_prefix("- ").unwrap().trim().trim_matches('"').trim_matches('\'');
-            if !tag.is_empty() {
-                tags.push(tag.to_string());
-            }
+/// Prints a command's JSON result, or renders it through the --template file when given.
+fn print_json(json: &str) {
+    let Some(template) = TEMPLATE.get() else {
+        println!("{}", json);
+        return;
+    };
+    let rendered = serde_json::from_str(json)
+        .map_err(|e| e.to_string())
+        .and_then(|data| render_template(template, &data));
+    match rendered {
+        Ok(text) => print!("{}", text),
+        Err(e) => {
+            eprintln!("Error rendering template: {}", e);
+            std::process::exit(1);
         }
     }
+}
 
-    if tags.is_empty() {
-        None
+/// Prints a write command's report, or just its planned changes with `--plan-only`.
+fn print_mutation<T: Serialize>(output: &T, writer: &VaultWriter) {
+    let json = if writer.plan_only {
+        serde_json::to_string_pretty(&ChangeSet { changes: &writer.changes })
     } else {
-        Some(tags)
+        serde_json::to_string_pretty(output)
+    };
+    match json {
+        Ok(json) => print_json(&json),
+        Err(e) => eprintln!("Error serializing to JSON: {}", e),
     }
 }
 
-fn extract_links_from_file(content: &str) -> Vec<String> {
-    let mut links = Vec::new();
-
-    // Match [[link]] and [[link|alias]]
-    let link_regex = Regex::new(r"\[\[([^\]|]+)(?:\|[^\]]*)?\]\]").unwrap();
-    for cap in link_regex.captures_iter(content) {
-        if let Some(link) = cap.get(1) {
-            links.push(link.as_str().to_string());
-        }
+/// Asks a yes/no question on stderr, taking anything but "y" or "yes" as no.
+fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
     }
-
-    links
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
-fn normalize_path(_vault_path: &Path, note_path: &str) -> String {
-    // Remove .md extension if present for comparison
-    let normalized = if note_path.ends_with(".md") {
-        &note_path[..note_path.len() - 3]
-    } else {
-        note_path
-    };
-    normalized.to_string()
+/// Tables are colored only on a terminal, and never when NO_COLOR is set.
+fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
 }
 
-fn find_note_path(vault_path: &Path, link: &str, all_notes: &HashSet<String>) -> Option<String> {
-    // Try exact match first
-    let link_normalized = normalize_path(vault_path, link);
-
-    for note in all_notes {
-        let note_normalized = normalize_path(vault_path, note);
-
-        // Check if the link matches the note name (with or without path)
-        if note_normalized == link_normalized || note_normalized.ends_with(&format!("/{}", link_normalized)) {
-            return Some(note.clone());
-        }
+/// Prints a listing as a table or CSV; JSON and NDJSON output are left to the caller.
+fn print_listing(table: &Table, format: ListFormat) {
+    match format {
+        ListFormat::Table => println!("{}", table.render(use_color())),
+        ListFormat::Csv => print!("{}", table.to_csv()),
+        ListFormat::Json | ListFormat::Ndjson => {}
     }
-
-    None
 }
 
-fn collect_all_tags(vault_path: &PathBuf) -> Result<BTreeMap<String, usize>, String> {
-    let mut tag_counts = BTreeMap::new();
+/// Writes one JSON object and a newline, flushing so a pipeline can start on it right away.
+fn write_json_line<T: Serialize>(out: &mut impl Write, item: &T) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *out, item)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
 
-    for entry in WalkDir::new(vault_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-
-        // Only process markdown files
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let tags = extract_tags_from_file(&content);
-                    for tag in tags {
-                        *tag_counts.entry(tag).or_insert(0) += 1;
-                    }
-                }
-                Err(_) => {
-                    continue;
-                }
-            }
+/// Prints items as NDJSON, stopping quietly once stdout is closed.
+fn print_ndjson<T: Serialize>(items: impl IntoIterator<Item = T>) {
+    let mut out = std::io::stdout().lock();
+    for item in items {
+        if write_json_line(&mut out, &item).is_err() {
+            return;
         }
     }
-
-    Ok(tag_counts)
 }
 
-fn collect_all_files(vault_path: &PathBuf) -> Result<Vec<FileInfo>, String> {
-    let mut files = Vec::new();
-
-    for entry in WalkDir::new(vault_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let word_count = content.split_whitespace().count();
-                    let links = extract_links_from_file(&content);
-                    let tags = extract_tags_from_file(&content);
-
-                    let relative_path = path.strip_prefix(vault_path)
-                        .unwrap_or(path)
-                        .to_string_lossy()
-                        .to_string();
-
-                    let modified = if let Ok(metadata) = fs::metadata(path) {
-                        if let Ok(modified) = metadata.modified() {
-                            format!("{:?}", modified)
-                        } else {
-                            "unknown".to_string()
-                        }
-                    } else {
-                        "unknown".to_string()
-                    };
-
-                    files.push(FileInfo {
-                        path: relative_path,
-                        word_count,
-                        link_count: links.len(),
-                        tag_count: tags.len(),
-                        modified,
-                    });
-                }
-                Err(_) => {
-                    continue;
-                }
+fn print_stats(vault_path: &Path, format: OutputFormat, options: &ScanOptions) {
+    let started = Instant::now();
+    match calculate_stats(vault_path, options) {
+        Ok(stats) => match format {
+            OutputFormat::Json => match serde_json::to_string_pretty(&stats) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            OutputFormat::Table => println!("{}", render_stats_table(&stats, use_color())),
+            OutputFormat::Openmetrics => {
+                println!("{}", render_openmetrics(&stats, started.elapsed()))
             }
-        }
+        },
+        Err(e) => eprintln!("Error calculating stats: {}", e),
     }
-
-    Ok(files)
 }
 
-fn collect_all_links(vault_path: &PathBuf) -> Result<(Vec<LinkInfo>, HashSet<String>), String> {
-    let mut all_links = Vec::new();
-    let mut all_notes = HashSet::new();
-
-    // First pass: collect all note paths
-    for entry in WalkDir::new(vault_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            let relative_path = path.strip_prefix(vault_path)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
-            all_notes.insert(relative_path);
+/// Global options whose next argument is their value rather than the command.
+const GLOBAL_VALUE_OPTIONS: [&str; 4] = ["--vault", "--stop-at", "--config", "--template"];
+
+/// Replaces a command alias from the config's `[alias]` table with the arguments it stands
+/// for, so `obsidian-cli bl Note` runs as `obsidian-cli backlinks --ranked Note`.
+/// The config is found from the `--vault`, `--config`, `--stop-at` and `--force` options the
+/// same way as for the command itself. Built-in commands can't be redefined.
+fn expand_alias(mut args: Vec<OsString>) -> Vec<OsString> {
+    let mut vault = PathBuf::from(".");
+    let mut config = None;
+    let mut stop_at = Vec::new();
+    let mut force = false;
+    let mut command = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if arg == "--" {
+            break;
         }
-    }
-
-    // Second pass: collect all links
-    for entry in WalkDir::new(vault_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let source = path.strip_prefix(vault_path)
-                        .unwrap_or(path)
-                        .to_string_lossy()
-                        .to_string();
-
-                    let links = extract_links_from_file(&content);
-                    for link in links {
-                        let target_path = find_note_path(vault_path, &link, &all_notes);
-                        let exists = target_path.is_some();
-                        let target = target_path.unwrap_or(link);
-
-                        all_links.push(LinkInfo {
-                            source: source.clone(),
-                            target,
-                            exists,
-                        });
-                    }
-                }
-                Err(_) => {
-                    continue;
-                }
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if GLOBAL_VALUE_OPTIONS.contains(&name) => (name, Some(PathBuf::from(value))),
+            _ if GLOBAL_VALUE_OPTIONS.contains(&&*arg) => {
+                i += 1;
+                (&*arg, args.get(i).map(PathBuf::from))
             }
+            _ => (&*arg, None),
+        };
+        match (name, value) {
+            ("--vault", Some(value)) => vault = value,
+            ("--config", Some(value)) => config = Some(value),
+            ("--stop-at", Some(value)) => stop_at.push(value),
+            ("--force", None) => force = true,
+            (name, None) if command.is_none() && !name.starts_with('-') => command = Some(i),
+            _ => {}
         }
+        i += 1;
     }
 
-    Ok((all_links, all_notes))
-}
-
-fn find_orphans(vault_path: &PathBuf) -> Result<Vec<String>, String> {
-    let (links, all_notes) = collect_all_links(vault_path)?;
-
-    let mut has_outgoing = HashSet::new();
-    let mut has_incoming = HashSet::new();
-
-    for link in &links {
-        has_outgoing.insert(link.source.clone());
-        if link.exists {
-            has_incoming.insert(link.target.clone());
+    let Some(position) = command else { return args };
+    let Some(name) = args[position].to_str() else { return args };
+    if name == "help" || Cli::command().find_subcommand(name).is_some() {
+        return args;
+    }
+    // Errors finding the vault or reading its config are reported once the command runs.
+    let Ok(root) = resolve_vault_root(&vault, &stop_at, force) else { return args };
+    let Ok(config) = load_config(&root, config.as_deref()) else { return args };
+    let Some(expansion) = config.alias.get(name) else { return args };
+    match split_arguments(expansion) {
+        Ok(expanded) if !expanded.is_empty() => {
+            args.splice(position..=position, expanded.into_iter().map(OsString::from));
+            args
+        }
+        Ok(_) => {
+            eprintln!("Error: alias {} is empty", name);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error in alias {}: {}", name, e);
+            std::process::exit(1);
         }
     }
-
-    let orphans: Vec<String> = all_notes
-        .iter()
-        .filter(|note| !has_outgoing.contains(*note) && !has_incoming.contains(*note))
-        .cloned()
-        .collect();
-
-    Ok(orphans)
 }
 
-fn find_notes_with_tag(vault_path: &PathBuf, target_tag: &str) -> Result<Vec<String>, String> {
-    let mut matching_files = Vec::new();
+fn main() {
+    let cli = Cli::parse_from(expand_alias(std::env::args_os().collect()));
+
+    if let Some(path) = &cli.template {
+        match std::fs::read_to_string(path) {
+            Ok(template) => TEMPLATE.get_or_init(|| template),
+            Err(e) => {
+                eprintln!("Error reading template {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        };
+    }
 
-    for entry in WalkDir::new(vault_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
+    // Commands that don't work on the vault found from --vault
+    if let Some(Commands::Capabilities { format }) = &cli.command {
+        let capabilities = capabilities(&Cli::command());
+        match format {
+            ListFormat::Json => match serde_json::to_string_pretty(&capabilities) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            ListFormat::Ndjson => print_ndjson(&capabilities.commands),
+            _ => print_listing(&capabilities_table(&capabilities), *format),
+        }
+        return;
+    }
+    if let Some(Commands::VaultMerge {
+        src,
+        dst,
+        conflict,
+        prefix,
+        write,
+    }) = &cli.command
     {
-        let path = entry.path();
-
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "md") {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let tags = extract_tags_from_file(&content);
-                    if tags.iter().any(|t| t == target_tag) {
-                        let relative_path = path.strip_prefix(vault_path)
-                            .unwrap_or(path)
-                            .to_string_lossy()
-                            .to_string();
-                        matching_files.push(relative_path);
-                    }
-                }
-                Err(_) => {
-                    continue;
-                }
+        let resolve = |path: &Path| match resolve_vault_root(path, &cli.stop_at, cli.force) {
+            Ok(root) => root,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let (src, dst) = (resolve(src), resolve(dst));
+        let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+        match merge_vaults(&src, &dst, *conflict, prefix.as_deref(), &mut writer) {
+            Ok(output) => print_mutation(&output, &writer),
+            Err(e) => {
+                eprintln!("Error merging vaults: {}", e);
+                std::process::exit(2);
             }
         }
+        return;
     }
 
-    Ok(matching_files)
-}
-
-fn find_backlinks(vault_path: &PathBuf, target_file: &str) -> Result<Vec<String>, String> {
-    let (links, _all_notes) = collect_all_links(vault_path)?;
-
-    // Normalize the target file path
-    let target_normalized = normalize_path(vault_path, target_file);
-
-    let mut backlinks = Vec::new();
-
-    for link in links {
-        let link_target_normalized = normalize_path(vault_path, &link.target);
+    let vault_path = match resolve_vault_root(&cli.vault, &cli.stop_at, cli.force) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-        // Check if this link points to our target file
-        if link_target_normalized == target_normalized ||
-           link_target_normalized.ends_with(&format!("/{}", target_normalized)) ||
-           target_normalized.ends_with(&format!("/{}", link_target_normalized)) {
-            backlinks.push(link.source);
+    let config = match load_config(&vault_path, cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            std::process::exit(1);
         }
+    };
+    let mut options = ScanOptions {
+        include_private: cli.include_private,
+        include_archived: cli.include_archived,
+        ..ScanOptions::from_config(&config)
+    };
+    if cli.obsidian_strict {
+        options.tag_syntax = TagSyntax::obsidian_strict();
     }
 
-    backlinks.sort();
-    backlinks.dedup();
-
-    Ok(backlinks)
-}
-
-fn calculate_stats(vault_path: &PathBuf) -> Result<StatsOutput, String> {
-    let tag_counts = collect_all_tags(vault_path)?;
-    let (links, all_notes) = collect_all_links(vault_path)?;
-    let orphans = find_orphans(vault_path)?;
-
-    let broken_links = links.iter().filter(|l| !l.exists).count();
-
-    Ok(StatsOutput {
-        total_notes: all_notes.len(),
-        total_tags: tag_counts.len(),
-        total_links: links.len(),
-        broken_links,
-        orphaned_notes: orphans.len(),
-    })
-}
-
-fn main() {
-    let cli = Cli::parse();
-
-    if cli.tags {
-        match collect_all_tags(&cli.vault_path) {
+    let command = cli.command.unwrap_or(Commands::Stats {
+        format: OutputFormat::Json,
+        tree: false,
+        depth: None,
+    });
+    match command {
+        Commands::Tags { action: None, tree: true, .. } => match serde_json::to_string_pretty(&tag_tree(&vault_path, &options)) {
+            Ok(json) => print_json(&json),
+            Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        },
+        Commands::Tags {
+            action: None,
+            cooccurrence: true,
+            min_count,
+            format,
+            ..
+        } => {
+            let pairs = tag_cooccurrence(&vault_path, min_count, &options);
+            match format {
+                ListFormat::Json => match serde_json::to_string_pretty(&TagCooccurrenceOutput { pairs }) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                },
+                ListFormat::Ndjson => print_ndjson(&pairs),
+                _ => print_listing(&tag_pairs_table(&pairs), format),
+            }
+        }
+        Commands::Tags { action: None, format, .. } => match collect_all_tags(&vault_path, &options) {
             Ok(tag_counts) => {
                 let tags: Vec<TagCount> = tag_counts
                     .into_iter()
                     .map(|(tag, count)| TagCount { tag, count })
                     .collect();
+                if format == ListFormat::Ndjson {
+                    print_ndjson(&tags);
+                    return;
+                }
+                if format != ListFormat::Json {
+                    print_listing(&tags_table(&tags), format);
+                    return;
+                }
                 let output = TagsOutput { tags };
                 match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
             }
             Err(e) => eprintln!("Error collecting tags: {}", e),
-        }
-    } else if cli.stats {
-        match calculate_stats(&cli.vault_path) {
-            Ok(stats) => {
-                match serde_json::to_string_pretty(&stats) {
-                    Ok(json) => println!("{}", json),
-                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        },
+        Commands::Tags {
+            action: Some(TagsCommand::Aliases { report }),
+            ..
+        } => match serde_json::to_string_pretty(&tag_aliases(&vault_path, report, &options)) {
+            Ok(json) => print_json(&json),
+            Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        },
+        Commands::Tags {
+            action: Some(TagsCommand::Materialize { folder, write }),
+            ..
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match materialize_tags(&vault_path, &folder, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error generating tag pages: {}", e);
+                    std::process::exit(2);
                 }
             }
-            Err(e) => eprintln!("Error calculating stats: {}", e),
         }
-    } else if cli.files {
-        match collect_all_files(&cli.vault_path) {
+        Commands::Stats { tree: true, depth, .. } => match folder_tree(&vault_path, depth, &options) {
+            Ok(tree) => match serde_json::to_string_pretty(&tree) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error calculating stats: {}", e),
+        },
+        Commands::Stats { format, .. } => print_stats(&vault_path, format, &options),
+        Commands::Files { format } => match collect_all_files(&vault_path, &options) {
             Ok(files) => {
+                if format == ListFormat::Ndjson {
+                    print_ndjson(&files);
+                    return;
+                }
+                if format != ListFormat::Json {
+                    print_listing(&files_table(&files), format);
+                    return;
+                }
                 let output = FilesOutput { files };
                 match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
             }
             Err(e) => eprintln!("Error collecting files: {}", e),
+        },
+        Commands::Links { fix: true, yes, write, .. } => {
+            let (mut fixes, unfixed) = match plan_link_fixes(&vault_path, &options) {
+                Ok(plan) => plan,
+                Err(e) => {
+                    eprintln!("Error collecting links: {}", e);
+                    std::process::exit(2);
+                }
+            };
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            if !yes && !writer.dry_run && !fixes.is_empty() {
+                if !std::io::stdin().is_terminal() {
+                    eprintln!("Error: links --fix asks before each change; pass --yes to fix them all without asking");
+                    std::process::exit(2);
+                }
+                fixes.retain(|fix| confirm(&format!("{}: [[{}]] -> [[{}]]?", fix.source, fix.target, fix.replacement)));
+            }
+            match apply_link_fixes(&vault_path, fixes, unfixed, &mut writer) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error fixing links: {}", e);
+                    std::process::exit(2);
+                }
+            }
         }
-    } else if cli.links {
-        match collect_all_links(&cli.vault_path) {
-            Ok((links, _)) => {
-                let broken_count = links.iter().filter(|l| !l.exists).count();
-                let output = LinksOutput { links, broken_count };
+        Commands::Links {
+            broken,
+            external: true,
+            check,
+            jobs,
+            timeout,
+            format,
+            ..
+        } => {
+            let mut output = external_links(&vault_path, check || broken, jobs, Duration::from_secs(timeout), &options);
+            if broken {
+                output.links.retain(|link| link.dead == Some(true));
+            }
+            match format {
+                ListFormat::Json => match serde_json::to_string_pretty(&output) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                },
+                ListFormat::Ndjson => print_ndjson(&output.links),
+                _ => print_listing(&external_links_table(&output.links), format),
+            }
+        }
+        Commands::Links {
+            broken,
+            embeds_only,
+            no_embeds,
+            format: ListFormat::Ndjson,
+            ..
+        } => {
+            // Stream links note by note rather than collecting the whole vault first
+            let mut out = std::io::stdout().lock();
+            let mut closed = false;
+            let visited = visit_links(&vault_path, &options, |link| {
+                let wanted = if link.embed { !no_embeds } else { !embeds_only };
+                if !closed && wanted && (!broken || (!link.exists && !link.placeholder) || link.broken_heading || link.broken_block) {
+                    closed = write_json_line(&mut out, &link).is_err();
+                }
+            });
+            if let Err(e) = visited {
+                eprintln!("Error collecting links: {}", e);
+            }
+        }
+        Commands::Links {
+            broken,
+            embeds_only,
+            no_embeds,
+            format,
+            ..
+        } => match collect_all_links(&vault_path, &options) {
+            Ok((mut links, _)) => {
+                links.retain(|l| if l.embed { !no_embeds } else { !embeds_only });
+                let broken_count = links.iter().filter(|l| !l.exists && !l.placeholder).count();
+                let broken_heading_count = links.iter().filter(|l| l.broken_heading).count();
+                let broken_block_count = links.iter().filter(|l| l.broken_block).count();
+                if broken {
+                    links.retain(|l| (!l.exists && !l.placeholder) || l.broken_heading || l.broken_block);
+                }
+                if format != ListFormat::Json {
+                    print_listing(&links_table(&links), format);
+                    if format == ListFormat::Table {
+                        println!(
+                            "\n{} broken, {} broken headings, {} broken blocks",
+                            broken_count, broken_heading_count, broken_block_count
+                        );
+                    }
+                    return;
+                }
+                let output = LinksOutput {
+                    links,
+                    broken_count,
+                    broken_heading_count,
+                    broken_block_count,
+                };
                 match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
             }
             Err(e) => eprintln!("Error collecting links: {}", e),
+        },
+        Commands::Query { query } => match run_query(&vault_path, &query, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => {
+                eprintln!("Error running query: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::Tables {
+            note,
+            select,
+            condition,
+            format,
+            ..
+        } => match note_tables(&vault_path, note.as_deref(), &select, condition.as_deref(), &options) {
+            Ok(tables) => match format {
+                ListFormat::Json => match serde_json::to_string_pretty(&TablesOutput { tables }) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                },
+                ListFormat::Ndjson => print_ndjson(tables.into_iter().flat_map(|table| {
+                    table.rows.into_iter().map(move |mut row| {
+                        row.insert("path".to_string(), table.path.clone().into());
+                        row.insert("line".to_string(), table.line.into());
+                        row
+                    })
+                })),
+                _ => print_listing(&note_tables_table(&tables), format),
+            },
+            Err(e) => {
+                eprintln!("Error reading tables: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::Find { query, limit, format } => {
+            let mut notes = find_notes(&vault_path, &query, &options);
+            notes.truncate(limit.unwrap_or(usize::MAX));
+            match format {
+                ListFormat::Json => match serde_json::to_string_pretty(&FindOutput { query, notes }) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                },
+                ListFormat::Ndjson => print_ndjson(&notes),
+                ListFormat::Table | ListFormat::Csv => print_listing(&found_notes_table(&notes), format),
+            }
         }
-    } else if cli.orphans {
-        match find_orphans(&cli.vault_path) {
+        Commands::Search {
+            query,
+            ranked: true,
+            limit,
+            format,
+            ..
+        } => match rank_notes(&vault_path, &query, &options) {
+            Ok(mut results) => {
+                results.truncate(limit.unwrap_or(usize::MAX));
+                match format {
+                    ListFormat::Json => match serde_json::to_string_pretty(&RankedSearchOutput { query, results }) {
+                        Ok(json) => print_json(&json),
+                        Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                    },
+                    ListFormat::Ndjson => print_ndjson(&results),
+                    ListFormat::Table => print_listing(&ranked_search_table(&results, use_color()), format),
+                    ListFormat::Csv => print_listing(&ranked_search_table(&results, false), format),
+                }
+            }
+            Err(e) => eprintln!("Error searching notes: {}", e),
+        },
+        Commands::Search {
+            query,
+            regex,
+            limit,
+            render_matches,
+            context,
+            format,
+            ..
+        } => match search_notes(&vault_path, &query, regex, &options) {
+            Ok(mut matches) => {
+                matches.truncate(limit.unwrap_or(usize::MAX));
+                if render_matches {
+                    print!("{}", render_search_matches(&vault_path, &matches, context, use_color()));
+                    return;
+                }
+                match format {
+                    ListFormat::Json => match serde_json::to_string_pretty(&SearchOutput { query, matches }) {
+                        Ok(json) => print_json(&json),
+                        Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                    },
+                    ListFormat::Ndjson => print_ndjson(&matches),
+                    ListFormat::Table => print_listing(&search_table(&matches, use_color()), format),
+                    ListFormat::Csv => print_listing(&search_table(&matches, false), format),
+                }
+            }
+            Err(e) => eprintln!("Error searching notes: {}", e),
+        },
+        Commands::Orphans { scored: true, threshold, format } => match score_orphans(&vault_path, threshold, &options) {
+            Ok(notes) => {
+                if format == ListFormat::Ndjson {
+                    print_ndjson(&notes);
+                    return;
+                }
+                if format != ListFormat::Json {
+                    print_listing(&scored_orphans_table(&notes), format);
+                    return;
+                }
+                match serde_json::to_string_pretty(&ScoredOrphansOutput { threshold, notes }) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                }
+            }
+            Err(e) => eprintln!("Error scoring orphans: {}", e),
+        },
+        Commands::Orphans { format, .. } => match find_orphans(&vault_path, &options) {
             Ok(orphans) => {
+                if format == ListFormat::Ndjson {
+                    print_ndjson(&orphans);
+                    return;
+                }
+                if format != ListFormat::Json {
+                    print_listing(&orphans_table(&orphans), format);
+                    return;
+                }
                 let output = OrphansOutput { orphans };
                 match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
             }
             Err(e) => eprintln!("Error finding orphans: {}", e),
+        },
+        Commands::Tag {
+            action: Some(TagCommand::Rename { old, new, write }),
+            ..
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match rename_tag(&vault_path, &old, &new, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error renaming tag: {}", e);
+                    std::process::exit(2);
+                }
+            }
         }
-    } else if let Some(tag) = &cli.tag {
-        match find_notes_with_tag(&cli.vault_path, tag) {
-            Ok(files) => {
-                let output = TagSearchOutput {
-                    tag: tag.clone(),
-                    files,
-                };
-                match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+        Commands::Tag { tag, include_subtags, .. } => {
+            // Required unless a subcommand is given
+            let tag = tag.unwrap_or_default();
+            match find_notes_with_tag(&vault_path, &tag, include_subtags, &options) {
+                Ok(files) => {
+                    let output = TagSearchOutput { tag, files };
+                    match serde_json::to_string_pretty(&output) {
+                        Ok(json) => print_json(&json),
+                        Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                    }
+                }
+                Err(e) => eprintln!("Error finding notes with tag: {}", e),
+            }
+        }
+        Commands::Graph {
+            action: Some(GraphCommand::SuggestPrune),
+            ..
+        } => match suggest_prune(&vault_path, &config, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error suggesting prunes: {}", e),
+        },
+        Commands::Templates { action } => {
+            let json = match action {
+                TemplatesAction::List => list_templates(&vault_path, &config, &options)
+                    .and_then(|output| serde_json::to_string_pretty(&output).map_err(|e| e.to_string())),
+                TemplatesAction::Check => check_templates(&vault_path, &config)
+                    .and_then(|issues| serde_json::to_string_pretty(&TemplateCheckOutput { issues }).map_err(|e| e.to_string())),
+                TemplatesAction::Leftovers => find_template_leftovers(&vault_path, &config, &options)
+                    .and_then(|output| serde_json::to_string_pretty(&output).map_err(|e| e.to_string())),
+            };
+            match json {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error inspecting templates: {}", e),
+            }
+        }
+        Commands::Symbols => match collect_symbols(&vault_path, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error collecting symbols: {}", e),
+        },
+        Commands::Attachments {
+            action: AttachmentsCommand::Dedupe { write },
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match dedupe_attachments(&vault_path, &mut writer) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error deduplicating attachments: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::SizeAudit => match size_audit(&vault_path, &config) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error auditing vault size: {}", e),
+        },
+        Commands::Bookmarks => match collect_bookmarks(&vault_path, &options) {
+            Ok(bookmarks) => match serde_json::to_string_pretty(&BookmarksOutput { bookmarks }) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error reading bookmarks: {}", e),
+        },
+        Commands::Recent { from_workspace } => match collect_recent(&vault_path, from_workspace, &options) {
+            Ok(recent) => {
+                let source = if from_workspace { "workspace" } else { "modified" };
+                match serde_json::to_string_pretty(&RecentOutput { source, recent }) {
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
             }
-            Err(e) => eprintln!("Error finding notes with tag: {}", e),
+            Err(e) => eprintln!("Error finding recent notes: {}", e),
+        },
+        Commands::Doctor => match run_doctor(&vault_path) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error diagnosing vault: {}", e),
+        },
+        Commands::Code { lang, out } => {
+            let json = match &out {
+                Some(out_dir) => extract_code(&vault_path, lang.as_deref(), out_dir, &options)
+                    .and_then(|extracted| serde_json::to_string_pretty(&CodeExtractOutput { extracted }).map_err(|e| e.to_string())),
+                None => serde_json::to_string_pretty(&code_inventory(&vault_path, lang.as_deref(), &options)).map_err(|e| e.to_string()),
+            };
+            match json {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error collecting code blocks: {}", e),
+            }
         }
-    } else if let Some(file) = &cli.backlinks {
-        match find_backlinks(&cli.vault_path, file) {
-            Ok(backlinks) => {
-                let output = BacklinksOutput {
-                    file: file.clone(),
-                    backlinks,
-                };
-                match serde_json::to_string_pretty(&output) {
-                    Ok(json) => println!("{}", json),
+        Commands::Session { action } => {
+            let result = match action {
+                SessionAction::Start => start_session(&vault_path, &options),
+                SessionAction::Status => session_changes(&vault_path, false, &options),
+                SessionAction::Stop => session_changes(&vault_path, true, &options),
+            };
+            match result {
+                Ok(output) => match serde_json::to_string_pretty(&output) {
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                },
+                Err(e) => eprintln!("Error tracking session: {}", e),
+            }
+        }
+        Commands::Moc {
+            folder,
+            group_by,
+            excerpts,
+            write,
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match generate_moc(&vault_path, &folder, group_by, excerpts, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error generating map of content: {}", e);
+                    std::process::exit(2);
                 }
             }
-            Err(e) => eprintln!("Error finding backlinks: {}", e),
         }
-    } else {
-        // Default: show stats
-        match calculate_stats(&cli.vault_path) {
-            Ok(stats) => {
-                match serde_json::to_string_pretty(&stats) {
-                    Ok(json) => println!("{}", json),
+        Commands::FrontmatterAudit { strip_unused, write } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match frontmatter_audit(&vault_path, strip_unused, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error auditing frontmatter: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Capabilities { .. } | Commands::VaultMerge { .. } => unreachable!("handled before opening the vault"),
+        Commands::Scrub { out } => match scrub_vault(&vault_path, &out, &config, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => {
+                eprintln!("Error scrubbing the vault: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::Properties => match list_properties(&vault_path, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error listing properties: {}", e),
+        },
+        Commands::Placeholders => match find_placeholders(&vault_path, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error collecting placeholders: {}", e),
+        },
+        Commands::Bench { threads } => match run_bench(&vault_path, threads, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error running benchmark: {}", e),
+        },
+        Commands::SlugCollisions => match find_slug_collisions(&vault_path, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error checking slugs: {}", e),
+        },
+        Commands::Inspect { note } => match inspect_note(&vault_path, &note, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error inspecting note: {}", e),
+        },
+        Commands::Activity { words } => match git_activity(&vault_path, words) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error reading git history: {}", e),
+        },
+        Commands::Graph {
+            action: Some(GraphCommand::History { snapshots, format }),
+            ..
+        } => match graph_history(&vault_path, snapshots, &options) {
+            Ok(snapshots) if format == HistoryFormat::Ndjson => print_ndjson(snapshots),
+            Ok(snapshots) => match serde_json::to_string_pretty(&GraphHistoryOutput { snapshots }) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error reconstructing graph history: {}", e),
+        },
+        Commands::Graph {
+            action: None,
+            format,
+            color_by,
+            exclude_orphans,
+            around,
+            depth,
+            tag,
+            query,
+            neighbors,
+        } => {
+            let mut graph = link_graph(&vault_path, exclude_orphans, &options);
+            if let Some(note) = &around
+                && let Err(e) = graph.restrict_to_neighborhood(note, depth)
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            if (tag.is_some() || query.is_some())
+                && let Err(e) = graph.restrict_to_matching(&vault_path, tag.as_deref(), query.as_deref(), neighbors, &options)
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            match format {
+                GraphFormat::Dot => println!("{}", render_dot(&graph, color_by)),
+                GraphFormat::Graphml => println!("{}", render_graphml(&graph)),
+                GraphFormat::Gexf => println!("{}", render_gexf(&graph)),
+                GraphFormat::Mermaid => println!("{}", render_mermaid(&graph, around.as_deref())),
+            }
+        }
+        Commands::Graph {
+            action: Some(GraphCommand::Diff { from, to }),
+            ..
+        } => match graph_diff(&vault_path, &from, &to, &options) {
+            Ok(diff) => match serde_json::to_string_pretty(&diff) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error comparing graphs: {}", e),
+        },
+        Commands::Tasks {
+            sort,
+            agenda,
+            week_starts,
+            include_done,
+        } => {
+            let mut tasks = collect_tasks(&vault_path, include_done, &options);
+            let json = match agenda {
+                Some(days) => serde_json::to_string_pretty(&build_agenda(tasks, days, week_starts.unwrap_or(config.week_start))),
+                None => {
+                    if let Some(key) = sort {
+                        sort_tasks(&mut tasks, key);
+                    }
+                    serde_json::to_string_pretty(&TasksOutput { tasks })
+                }
+            };
+            match json {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            }
+        }
+        Commands::LinkFragility => match link_fragility(&vault_path, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error scoring link fragility: {}", e),
+        },
+        #[cfg(feature = "fuse")]
+        Commands::Mount { mountpoint } => {
+            if let Err(e) = mount_views(&vault_path, &mountpoint, &options) {
+                eprintln!("Error mounting views: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Hook {
+            action: HookCommand::PreCommit,
+        } => match run_pre_commit(&vault_path, &config, &options) {
+            Ok(output) => {
+                match serde_json::to_string_pretty(&output) {
+                    Ok(json) => print_json(&json),
                     Err(e) => eprintln!("Error serializing to JSON: {}", e),
                 }
+                if !output.problems.is_empty() {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error checking staged notes: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::Guard { interval, no_notify } => {
+            let mut guard = Guard::new(&vault_path, &options);
+            eprintln!("Watching {} for broken links and invalid frontmatter", vault_path.display());
+            let mut out = std::io::stdout().lock();
+            let mut notify = !no_notify;
+            loop {
+                std::thread::sleep(Duration::from_secs(interval.max(1)));
+                for problem in guard.check(&vault_path, &options) {
+                    let _ = write_json_line(&mut out, &problem);
+                    if notify && let Err(e) = notify_desktop(&problem.path, &problem.detail) {
+                        eprintln!("Warning: {}; continuing without notifications", e);
+                        notify = false;
+                    }
+                }
+            }
+        }
+        Commands::New {
+            title,
+            note_template,
+            folder,
+            related,
+            write,
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            let related = match related.map(|limit| related_links(&vault_path, &config, &title, limit, &options)) {
+                Some(Ok(links)) => links,
+                Some(Err(e)) => {
+                    eprintln!("Error finding related notes: {}", e);
+                    std::process::exit(2);
+                }
+                None => Vec::new(),
+            };
+            match new_note(&vault_path, &config, &title, folder.as_deref(), note_template.as_deref(), &related, &mut writer) {
+                Ok(_) if writer.plan_only => print_mutation(&(), &writer),
+                Ok(path) => println!("{}", vault_path.join(path).display()),
+                Err(e) => {
+                    eprintln!("Error creating note: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Rename { old, new, write } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match rename_note(&vault_path, &old, &new, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error renaming note: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Move { note, folder, write } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match move_note(&vault_path, &note, &folder, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error moving note: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Delete {
+            note,
+            redirect_to,
+            force,
+            write,
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match delete_note(&vault_path, &note, redirect_to.as_deref(), force, &mut writer, &options) {
+                Ok(output) => {
+                    print_mutation(&output, &writer);
+                    let linking = output.backlinks.len() + output.private_backlinks;
+                    if !output.deleted {
+                        eprintln!(
+                            "{} notes still link to {}; pass --redirect-to NOTE to repoint them or --force to delete anyway",
+                            linking, note
+                        );
+                        std::process::exit(1);
+                    }
+                    if force && linking > 0 {
+                        eprintln!("Warning: {} notes now have broken links to {}", linking, note);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error deleting note: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Report { group_by: Some(ReportGrouping::Tag), tags, .. } => {
+            print!("{}", tag_rollup_table(&tag_rollup(&vault_path, &tags, &options)).to_markdown());
+        }
+        Commands::Report { html, week_starts, .. } => {
+            // Required unless --group-by is given
+            let html = html.unwrap_or_default();
+            let written = render_html_report(&vault_path, week_starts.unwrap_or(config.week_start), &options)
+                .and_then(|report| std::fs::write(&html, report).map_err(|e| format!("failed to write {}: {}", html.display(), e)));
+            if let Err(e) = written {
+                eprintln!("Error writing report: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Lint {
+            fix_names,
+            fix_tags,
+            schema,
+            write,
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            let result = schema
+                .as_deref()
+                .map(load_schema)
+                .transpose()
+                .and_then(|schema| lint(&vault_path, &config, fix_names, fix_tags, schema.as_ref(), &mut writer, &options));
+            match result {
+                Ok(output) => {
+                    print_mutation(&output, &writer);
+                    if output.has_violations() {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error linting vault: {}", e);
+                    std::process::exit(2);
+                }
             }
-            Err(e) => eprintln!("Error calculating stats: {}", e),
         }
+        Commands::Gate { note, rules } => {
+            match load_gate_rules(&rules).and_then(|rules| run_gate(&vault_path, &note, &rules, &options)) {
+                Ok(output) => {
+                    match serde_json::to_string_pretty(&output) {
+                        Ok(json) => print_json(&json),
+                        Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                    }
+                    if !output.passed {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error checking note: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::ApplyTemplate {
+            template_note,
+            condition,
+            write,
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match apply_template(&vault_path, &config, &template_note, condition.as_deref(), &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error applying template: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Meta { action } => {
+            let (key, value, condition, write) = match action {
+                MetaCommand::Set { key, value, condition, write } => (key, Some(value), condition, write),
+                MetaCommand::Remove { key, condition, write } => (key, None, condition, write),
+            };
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match edit_frontmatter(&vault_path, &config, &key, value.as_deref(), condition.as_deref(), &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error editing frontmatter: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Urls {
+            action: UrlsCommand::Enrich { jobs, timeout, write },
+        } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match enrich_urls(&vault_path, jobs, Duration::from_secs(timeout), &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error enriching URLs: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::OnThisDay { date, append, write } => {
+            let mut writer = VaultWriter::new(write.dry_run, write.plan_only);
+            match on_this_day(&vault_path, &config, date.as_deref(), append, &mut writer, &options) {
+                Ok(output) => print_mutation(&output, &writer),
+                Err(e) => {
+                    eprintln!("Error finding notes from this day: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Ml {
+            action: MlCommand::TrainTags { min_notes, out },
+        } => match train_tag_model(&vault_path, min_notes, out.as_deref(), &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => {
+                eprintln!("Error training tag model: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::SuggestTags {
+            note,
+            model,
+            threshold,
+            limit,
+        } => match suggest_tags(&vault_path, note.as_deref(), model.as_deref(), threshold, limit, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => {
+                eprintln!("Error suggesting tags: {}", e);
+                std::process::exit(2);
+            }
+        },
+        Commands::Cache { action } => match run_cache_action(&vault_path, action, &options) {
+            Ok(output) => match serde_json::to_string_pretty(&output) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => eprintln!("Error managing cache: {}", e),
+        },
+        Commands::Whatsnew => match serde_json::to_string_pretty(&whats_new(&vault_path, &options)) {
+            Ok(json) => print_json(&json),
+            Err(e) => eprintln!("Error serializing to JSON: {}", e),
+        },
+        Commands::Backlinks { note, ranked: true, .. } => match rank_backlinks(&vault_path, &note, &options) {
+            Ok(backlinks) => match serde_json::to_string_pretty(&RankedBacklinksOutput { file: note, backlinks }) {
+                Ok(json) => print_json(&json),
+                Err(e) => eprintln!("Error serializing to JSON: {}", e),
+            },
+            Err(e) => {
+                eprintln!("Error finding backlinks: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Backlinks { note, by_heading: true, .. } => match find_backlinks_by_heading(&vault_path, &note, &options) {
+            Ok(sections) => {
+                let output = BacklinksByHeadingOutput { file: note, sections };
+                match serde_json::to_string_pretty(&output) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                }
+            }
+            Err(e) => {
+                eprintln!("Error finding backlinks: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Backlinks { note, .. } => match find_backlinks(&vault_path, &note, &options) {
+            Ok(backlinks) => {
+                let output = BacklinksOutput { file: note, backlinks };
+                match serde_json::to_string_pretty(&output) {
+                    Ok(json) => print_json(&json),
+                    Err(e) => eprintln!("Error serializing to JSON: {}", e),
+                }
+            }
+            Err(e) => {
+                eprintln!("Error finding backlinks: {}", e);
+                std::process::exit(1);
+            }
+        },
     }
 }
@@ -0,0 +1,235 @@
+//! Read-only FUSE filesystem exposing derived views of the vault as symlinks:
+//! `/by-tag/<tag>/`, `/orphans/` and `/backlinks/<note>/`. Views are built once at mount time.
+
+use crate::config::ScanOptions;
+use crate::links::{collect_all_links, find_orphans};
+use crate::scan::{markdown_files, read_note, relative_path};
+use fuser::{
+    Errno, FileAttr, FileHandle, FileType, Filesystem, Generation, INodeNo, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEntry, Request,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+const TTL: Duration = Duration::from_secs(60);
+
+enum Node {
+    Dir(BTreeMap<String, u64>),
+    Link(PathBuf),
+}
+
+struct ViewFs {
+    // Inode N lives at nodes[N - 1]; inode 1 is the root directory
+    nodes: Vec<Node>,
+    uid: u32,
+    gid: u32,
+}
+
+impl ViewFs {
+    fn dir(&mut self, parent: u64, name: &str) -> u64 {
+        if let Node::Dir(children) = &self.nodes[parent as usize - 1]
+            && let Some(ino) = children.get(name)
+        {
+            return *ino;
+        }
+        self.nodes.push(Node::Dir(BTreeMap::new()));
+        let ino = self.nodes.len() as u64;
+        if let Node::Dir(children) = &mut self.nodes[parent as usize - 1] {
+            children.insert(name.to_string(), ino);
+        }
+        ino
+    }
+
+    fn dir_path(&mut self, path: &str) -> u64 {
+        path.split('/').filter(|c| !c.is_empty()).fold(1, |parent, component| self.dir(parent, component))
+    }
+
+    fn add_link(&mut self, parent: u64, note: &str, target: PathBuf) {
+        let Node::Dir(children) = &self.nodes[parent as usize - 1] else {
+            return;
+        };
+        // Fall back to the flattened vault path when two notes share a file name
+        let file_name = note.rsplit('/').next().unwrap_or(note).to_string();
+        let name = match children.get(&file_name) {
+            Some(&ino) if matches!(&self.nodes[ino as usize - 1], Node::Link(t) if *t == target) => return,
+            Some(_) => note.replace('/', "_"),
+            None => file_name,
+        };
+        self.nodes.push(Node::Link(target));
+        let ino = self.nodes.len() as u64;
+        if let Node::Dir(children) = &mut self.nodes[parent as usize - 1] {
+            children.insert(name, ino);
+        }
+    }
+
+    fn attr(&self, ino: u64) -> Option<FileAttr> {
+        let (kind, size, perm, nlink) = match self.nodes.get(ino as usize - 1)? {
+            Node::Dir(_) => (FileType::Directory, 0, 0o555, 2),
+            Node::Link(target) => (FileType::Symlink, target.as_os_str().len() as u64, 0o777, 1),
+        };
+        Some(FileAttr {
+            ino: INodeNo(ino),
+            size,
+            blocks: 0,
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            flags: 0,
+            blksize: 512,
+        })
+    }
+}
+
+impl Filesystem for ViewFs {
+    fn lookup(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let child = match self.nodes.get(u64::from(parent) as usize - 1) {
+            Some(Node::Dir(children)) => name.to_str().and_then(|n| children.get(n)),
+            _ => None,
+        };
+        match child.and_then(|ino| self.attr(*ino)) {
+            Some(attr) => reply.entry(&TTL, &attr, Generation(0)),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn getattr(&self, _req: &Request, ino: INodeNo, _fh: Option<FileHandle>, reply: ReplyAttr) {
+        match self.attr(u64::from(ino)) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn readlink(&self, _req: &Request, ino: INodeNo, reply: ReplyData) {
+        match self.nodes.get(u64::from(ino) as usize - 1) {
+            Some(Node::Link(target)) => reply.data(target.as_os_str().as_encoded_bytes()),
+            _ => reply.error(Errno::EINVAL),
+        }
+    }
+
+    fn readdir(&self, _req: &Request, ino: INodeNo, _fh: FileHandle, offset: u64, mut reply: ReplyDirectory) {
+        let Some(Node::Dir(children)) = self.nodes.get(u64::from(ino) as usize - 1) else {
+            reply.error(Errno::ENOTDIR);
+            return;
+        };
+        let mut entries = vec![(u64::from(ino), FileType::Directory, "."), (1, FileType::Directory, "..")];
+        for (name, child) in children {
+            let kind = match self.nodes[*child as usize - 1] {
+                Node::Dir(_) => FileType::Directory,
+                Node::Link(_) => FileType::Symlink,
+            };
+            entries.push((*child, kind, name.as_str()));
+        }
+        for (i, (child, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(INodeNo(child), (i + 1) as u64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+fn build_views(vault_path: &Path, options: &ScanOptions) -> Result<ViewFs, String> {
+    let root = vault_path.canonicalize().map_err(|e| format!("failed to resolve vault path: {}", e))?;
+    let metadata = fs::metadata(&root).map_err(|e| e.to_string())?;
+    let mut views = ViewFs {
+        nodes: vec![Node::Dir(BTreeMap::new())],
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+    };
+
+    views.dir(1, "by-tag");
+    for path in markdown_files(vault_path) {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let note = relative_path(vault_path, &path);
+        let tags: BTreeSet<String> = options.note_tags(&content).into_iter().collect();
+        for tag in tags {
+            let dir = views.dir_path(&format!("by-tag/{}", tag));
+            views.add_link(dir, &note, root.join(&note));
+        }
+    }
+
+    let orphans = views.dir(1, "orphans");
+    for note in find_orphans(vault_path, options)? {
+        views.add_link(orphans, &note, root.join(&note));
+    }
+
+    let backlinks = views.dir(1, "backlinks");
+    let (links, _) = collect_all_links(vault_path, options)?;
+    for link in links.iter().filter(|l| l.exists && l.source != l.target) {
+        let target_name = link.target.rsplit('/').next().unwrap_or(&link.target).to_string();
+        let dir = views.dir(backlinks, &target_name);
+        views.add_link(dir, &link.source, root.join(&link.source));
+    }
+
+    Ok(views)
+}
+
+pub fn mount_views(vault_path: &Path, mountpoint: &Path, options: &ScanOptions) -> Result<(), String> {
+    let views = build_views(vault_path, options)?;
+    let mut config = fuser::Config::default();
+    config.mount_options.extend([
+        MountOption::RO,
+        MountOption::FSName("obsidian-cli".to_string()),
+        MountOption::AutoUnmount,
+    ]);
+    config.acl = fuser::SessionACL::All;
+    fuser::mount(views, mountpoint, &config).map_err(|e| format!("failed to mount {}: {}", mountpoint.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names in the view directory at `path`, with the vault path each link points to.
+    fn listing(views: &ViewFs, root: &Path, path: &str) -> Vec<(String, String)> {
+        let ino = path.split('/').filter(|c| !c.is_empty()).fold(1, |ino, name| match &views.nodes[ino as usize - 1] {
+            Node::Dir(children) => children[name],
+            Node::Link(_) => panic!("{} is not a directory", path),
+        });
+        let Node::Dir(children) = &views.nodes[ino as usize - 1] else {
+            panic!("{} is not a directory", path);
+        };
+        children
+            .iter()
+            .map(|(name, child)| match &views.nodes[*child as usize - 1] {
+                Node::Link(target) => (name.clone(), relative_path(root, target)),
+                Node::Dir(_) => (name.clone(), String::new()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn views_link_notes_by_tag_orphans_and_backlinks() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-mount-{}", std::process::id()));
+        fs::create_dir_all(root.join("Work")).unwrap();
+        fs::write(root.join("Ideas.md"), "#project/web [[Plan]]").unwrap();
+        fs::write(root.join("Work/Ideas.md"), "#project/web [[Plan]]").unwrap();
+        fs::write(root.join("Plan.md"), "[[Plan#Self]]").unwrap();
+        fs::write(root.join("Lonely.md"), "").unwrap();
+        let root = root.canonicalize().unwrap();
+
+        let views = build_views(&root, &ScanOptions::default()).unwrap();
+        let pair = |name: &str, target: &str| (name.to_string(), target.to_string());
+        assert_eq!(listing(&views, &root, ""), [pair("backlinks", ""), pair("by-tag", ""), pair("orphans", "")]);
+        assert_eq!(listing(&views, &root, "by-tag/project"), [pair("web", "")]);
+        assert_eq!(listing(&views, &root, "by-tag/project/web"), [pair("Ideas.md", "Ideas.md"), pair("Work_Ideas.md", "Work/Ideas.md")]);
+        assert_eq!(listing(&views, &root, "orphans"), [pair("Lonely.md", "Lonely.md")]);
+        assert_eq!(listing(&views, &root, "backlinks"), [pair("Plan.md", "")]);
+        assert_eq!(listing(&views, &root, "backlinks/Plan.md").len(), 2);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
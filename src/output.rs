@@ -0,0 +1,588 @@
+//! Terminal output: tables, colors, rendered markdown, templates and the capability list.
+
+use crate::links::{IsolationScore, LinkInfo};
+use crate::scan::body_start;
+use crate::search::{FoundNote, NoteTable, RankedNote, SearchMatch};
+use crate::stats::{FileInfo, StatsOutput};
+use crate::tags::{TagCount, TagPair};
+use crate::urls::ExternalLink;
+use crate::write::CHANGE_SET_VERSION;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Table,
+    Openmetrics,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    Json,
+    Table,
+    Csv,
+    /// One JSON object per line, printed as soon as it is found
+    Ndjson,
+}
+
+/// Version of the shape of the JSON output, raised when a field is removed or changes meaning
+/// (new fields can appear at any time).
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What the installed binary can do, for wrapper tools and editor plugins.
+#[derive(Serialize)]
+pub struct Capabilities {
+    version: &'static str,
+    schema_version: u32,
+    change_set_version: u32,
+    /// Every optional cargo feature, with whether this binary was built with it
+    features: BTreeMap<&'static str, bool>,
+    pub commands: Vec<CommandCapability>,
+}
+
+#[derive(Serialize)]
+pub struct CommandCapability {
+    /// The full command, like `tags rename`
+    name: String,
+    about: String,
+    /// Values `--format` takes; empty when the command only prints JSON
+    formats: Vec<String>,
+    /// Whether the command can modify the vault (and takes --dry-run and --plan-only)
+    writes: bool,
+}
+
+/// Renders a command's JSON result through a Handlebars template. Output is plain text, so
+/// nothing is HTML-escaped.
+pub fn render_template(template: &str, data: &serde_json::Value) -> Result<String, String> {
+    let mut handlebars = handlebars::Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.render_template(template, data).map_err(|e| e.to_string())
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Rows of a listing, rendered as a terminal table (columns padded to their widest cell,
+/// numbers right-aligned, optionally a bold header and colored rows) or as CSV.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<(Vec<String>, Option<&'static str>)>,
+}
+
+impl Table {
+    pub(crate) fn new(headers: &[&str]) -> Self {
+        Table { headers: headers.iter().map(|h| h.to_string()).collect(), rows: Vec::new() }
+    }
+
+    pub(crate) fn row(&mut self, cells: Vec<String>, color: Option<&'static str>) {
+        self.rows.push((cells, color));
+    }
+
+    pub fn render(&self, color: bool) -> String {
+        let columns = self.headers.len();
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for (cells, _) in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let numeric = self.numeric_columns();
+
+        let line = |cells: &[String]| -> String {
+            let padded: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if numeric[i] {
+                        format!("{:>width$}", cell, width = widths[i])
+                    } else if i + 1 == columns {
+                        cell.clone()
+                    } else {
+                        format!("{:<width$}", cell, width = widths[i])
+                    }
+                })
+                .collect();
+            padded.join("  ")
+        };
+
+        let mut out = String::new();
+        match color {
+            true => out.push_str(&format!("{}{}{}\n", BOLD, line(&self.headers), RESET)),
+            false => out.push_str(&format!("{}\n", line(&self.headers))),
+        }
+        for (cells, row_color) in &self.rows {
+            match row_color.filter(|_| color) {
+                Some(code) => out.push_str(&format!("{}{}{}\n", code, line(cells), RESET)),
+                None => out.push_str(&format!("{}\n", line(cells))),
+            }
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+
+    /// Columns holding only numbers, which are right-aligned.
+    fn numeric_columns(&self) -> Vec<bool> {
+        (0..self.headers.len())
+            .map(|i| !self.rows.is_empty() && self.rows.iter().all(|(cells, _)| cells[i].parse::<f64>().is_ok()))
+            .collect()
+    }
+
+    /// A Markdown table, with numeric columns right-aligned.
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: &mut dyn Iterator<Item = String>| -> String {
+            format!("| {} |\n", cells.map(|cell| cell.replace('|', "\\|")).collect::<Vec<_>>().join(" | "))
+        };
+        let mut out = line(&mut self.headers.iter().cloned());
+        out.push_str(&line(&mut self.numeric_columns().into_iter().map(|numeric| if numeric { "---:" } else { "---" }.to_string())));
+        for (cells, _) in &self.rows {
+            out.push_str(&line(&mut cells.iter().cloned()));
+        }
+        out
+    }
+
+    /// RFC 4180 CSV with snake_case column names, one record per line.
+    pub fn to_csv(&self) -> String {
+        let record = |cells: &mut dyn Iterator<Item = String>| -> String {
+            let fields: Vec<String> = cells
+                .map(|cell| {
+                    if cell.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell
+                    }
+                })
+                .collect();
+            fields.join(",") + "\r\n"
+        };
+        let mut out = record(&mut self.headers.iter().map(|h| h.to_lowercase().replace(' ', "_")));
+        for (cells, _) in &self.rows {
+            out.push_str(&record(&mut cells.iter().cloned()));
+        }
+        out
+    }
+}
+
+pub fn tags_table(tags: &[TagCount]) -> Table {
+    let mut table = Table::new(&["TAG", "NOTES"]);
+    for tag in tags {
+        table.row(vec![tag.tag.clone(), tag.count.to_string()], None);
+    }
+    table
+}
+
+pub fn tag_pairs_table(pairs: &[TagPair]) -> Table {
+    let mut table = Table::new(&["TAG", "TAG", "NOTES", "OVERLAP"]);
+    for pair in pairs {
+        table.row(vec![pair.first.clone(), pair.second.clone(), pair.count.to_string(), format!("{:.3}", pair.overlap)], None);
+    }
+    table
+}
+
+pub fn files_table(files: &[FileInfo]) -> Table {
+    let mut table = Table::new(&["PATH", "WORDS", "LINKS", "TAGS", "MODIFIED"]);
+    for file in files {
+        let cells = vec![
+            file.path.clone(),
+            file.word_count.to_string(),
+            file.link_count.to_string(),
+            file.tag_count.to_string(),
+            file.modified.clone(),
+        ];
+        table.row(cells, None);
+    }
+    table
+}
+
+/// Broken links are shown in red and placeholders in yellow.
+pub fn links_table(links: &[LinkInfo]) -> Table {
+    let mut table = Table::new(&["SOURCE", "TARGET", "KIND", "STATUS"]);
+    for link in links {
+        let (status, row_color) = match (link.exists, link.placeholder) {
+            (true, _) if link.broken_heading => ("broken heading", Some(RED)),
+            (true, _) if link.broken_block => ("broken block", Some(RED)),
+            (true, _) => ("ok", None),
+            (false, true) => ("placeholder", Some(YELLOW)),
+            (false, false) => ("broken", Some(RED)),
+        };
+        let kind = if link.embed { "embed" } else { "link" };
+        let target = match &link.heading {
+            Some(heading) => format!("{}#{}", link.target, heading),
+            None => link.target.clone(),
+        };
+        table.row(vec![link.source.clone(), target, kind.to_string(), status.to_string()], row_color);
+    }
+    table
+}
+
+pub fn scored_orphans_table(scores: &[IsolationScore]) -> Table {
+    let mut table = Table::new(&["NOTE", "SCORE", "INBOUND", "OUTBOUND", "TAG NEIGHBORS", "FOLDER SIBLINGS"]);
+    for score in scores {
+        let cells = vec![
+            score.note.clone(),
+            format!("{:.3}", score.score),
+            score.inbound.to_string(),
+            score.outbound.to_string(),
+            score.tag_neighbors.to_string(),
+            score.folder_siblings.to_string(),
+        ];
+        table.row(cells, None);
+    }
+    table
+}
+
+/// A match's snippet, with the match itself in bold red when `color` is set.
+fn highlighted_snippet(found: &SearchMatch, color: bool) -> String {
+    if !color {
+        return found.snippet.clone();
+    }
+    let chars: Vec<char> = found.snippet.chars().collect();
+    let [start, end] = found.highlight;
+    format!(
+        "{}{}{}{}{}{}",
+        chars[..start].iter().collect::<String>(),
+        BOLD,
+        RED,
+        chars[start..end].iter().collect::<String>(),
+        RESET,
+        chars[end..].iter().collect::<String>()
+    )
+}
+
+pub fn search_table(matches: &[SearchMatch], color: bool) -> Table {
+    let mut table = Table::new(&["PATH", "LINE", "SNIPPET"]);
+    for found in matches {
+        table.row(vec![found.path.clone(), found.line.to_string(), highlighted_snippet(found, color)], None);
+    }
+    table
+}
+
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const REVERSE: &str = "\x1b[7m";
+const GREEN: &str = "\x1b[32m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+const HEADING: &str = "\x1b[1;34m";
+const LINK: &str = "\x1b[4;34m";
+
+/// Styles for each byte of a line of Markdown outside code blocks. Nothing is taken out of
+/// the line, only colored: headings, quotes, list markers, emphasis, inline code, links and tags.
+fn markdown_styles(line: &str) -> Vec<&'static str> {
+    static BLOCK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:(?P<heading> {0,3}#{1,6}\s.*)|(?P<quote>\s*>.*)|\s*(?P<marker>(?:[-*+]|\d+[.)])(?: \[.\])?)\s)").unwrap());
+    static INLINE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?P<code>`[^`]+`)|(?P<link>!?\[\[[^\]]+\]\]|!?\[[^\]]*\]\([^)]*\))|(?P<bold>\*\*[^*]+\*\*|__[^_]+__)|(?P<italic>\*[^*\s][^*]*\*|\b_[^_\s][^_]*_\b)|(?:^|\s)(?P<tag>#[\p{L}\p{N}_/-]*[\p{L}_/-][\p{L}\p{N}_/-]*)").unwrap()
+    });
+    let mut styles = vec![""; line.len()];
+    if let Some(block) = BLOCK.captures(line) {
+        if let Some(heading) = block.name("heading") {
+            styles[heading.range()].fill(HEADING);
+            return styles;
+        }
+        for (name, style) in [("quote", ITALIC), ("marker", YELLOW)] {
+            if let Some(found) = block.name(name) {
+                styles[found.range()].fill(style);
+            }
+        }
+    }
+    for found in INLINE.captures_iter(line) {
+        let styled = [("code", CYAN), ("link", LINK), ("bold", BOLD), ("italic", ITALIC), ("tag", YELLOW)]
+            .into_iter()
+            .find_map(|(name, style)| Some((found.name(name)?, style)));
+        if let Some((range, style)) = styled {
+            styles[range.range()].fill(style);
+        }
+    }
+    styles
+}
+
+/// Styles for each byte of a line of code: comments, strings, numbers and the keywords
+/// of common languages. Comments start with `#` or `--` in the languages that use them.
+fn code_styles(line: &str, language: &str) -> Vec<&'static str> {
+    const PATTERN: &str = r#"(?P<string>"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*')|(?P<number>\b\d[\d_]*(?:\.\d+)?\b)|(?P<keyword>\b(?:fn|let|mut|pub|struct|enum|impl|trait|use|mod|match|if|else|elif|for|while|loop|return|break|continue|in|as|const|static|def|class|import|from|function|var|async|await|yield|try|except|catch|finally|raise|throw|with|lambda|new|this|self|Self|true|false|True|False|None|null|nil|and|or|not|type|interface|export|package|func|go|defer|select|where|insert|update|delete|create|table)\b)"#;
+    static SLASH: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!(r"(?P<comment>//.*|/\*.*?(?:\*/|$))|{}", PATTERN)).unwrap());
+    static HASH: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!(r"(?P<comment>#.*)|{}", PATTERN)).unwrap());
+    static DASH: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!(r"(?P<comment>--.*)|{}", PATTERN)).unwrap());
+    let regex: &Regex = match language.to_lowercase().as_str() {
+        "python" | "py" | "sh" | "bash" | "zsh" | "shell" | "ruby" | "rb" | "yaml" | "yml" | "toml" | "r" | "perl" | "make" | "dockerfile" => &HASH,
+        "sql" | "lua" | "haskell" | "hs" => &DASH,
+        _ => &SLASH,
+    };
+    let mut styles = vec![""; line.len()];
+    for found in regex.captures_iter(line) {
+        let styled = [("comment", DIM), ("string", GREEN), ("number", CYAN), ("keyword", MAGENTA)]
+            .into_iter()
+            .find_map(|(name, style)| Some((found.name(name)?, style)));
+        if let Some((range, style)) = styled {
+            styles[range.range()].fill(style);
+        }
+    }
+    styles
+}
+
+/// Lines `first..=last` (counting from 1) of a note, numbered and colored for a terminal the
+/// way `bat` shows Markdown: syntax is kept but styled, and code blocks are highlighted by
+/// their fence's language. Characters `mark.1` (from 0) of line `mark.0` are shown reversed.
+fn render_markdown(content: &str, first: usize, last: usize, mark: Option<(usize, [usize; 2])>, color: bool) -> String {
+    let frontmatter_lines = content[..body_start(content)].matches('\n').count();
+    let mut fence: Option<(String, String)> = None;
+    let mut rendered = String::new();
+    for (index, line) in content.lines().enumerate().take(last) {
+        let number = index + 1;
+        let trimmed = line.trim_start();
+        let ticks: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+        let is_fence = ticks.len() >= 3 && fence.as_ref().is_none_or(|(open, _)| ticks.starts_with(open.as_str()));
+        let styles = if index < frontmatter_lines || is_fence {
+            vec![DIM; line.len()]
+        } else {
+            match &fence {
+                Some((_, language)) => code_styles(line, language),
+                None => markdown_styles(line),
+            }
+        };
+        if is_fence {
+            fence = match fence {
+                Some(_) => None,
+                None => Some((ticks.clone(), trimmed[ticks.len()..].trim().to_string())),
+            };
+        }
+        if number < first {
+            continue;
+        }
+
+        let mut marked = vec![false; line.len()];
+        if let Some((mark_line, [start, end])) = mark
+            && mark_line == number
+        {
+            for (i, (byte, c)) in line.char_indices().enumerate() {
+                if (start..end).contains(&i) {
+                    marked[byte..byte + c.len_utf8()].fill(true);
+                }
+            }
+        }
+        if !color {
+            rendered.push_str(&format!("{:>5} │ {}\n", number, line));
+            continue;
+        }
+        rendered.push_str(&format!("{}{:>5} │{} ", DIM, number, RESET));
+        let mut current = ("", false);
+        for (byte, c) in line.char_indices() {
+            let style = (styles[byte], marked[byte]);
+            if style != current {
+                rendered.push_str(RESET);
+                rendered.push_str(style.0);
+                if style.1 {
+                    rendered.push_str(REVERSE);
+                }
+                current = style;
+            }
+            rendered.push(c);
+        }
+        rendered.push_str(RESET);
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// Each match with `context` lines either side of it rendered as Markdown for a terminal,
+/// under its note's path and line.
+pub fn render_search_matches(vault_path: &Path, matches: &[SearchMatch], context: usize, color: bool) -> String {
+    let mut rendered = String::new();
+    for found in matches {
+        let Ok(content) = fs::read_to_string(vault_path.join(&found.path)) else {
+            continue;
+        };
+        let start = found.column - 1;
+        let mark = [start, start + found.highlight[1] - found.highlight[0]];
+        let heading = format!("{}:{}", found.path, found.line);
+        match color {
+            true => rendered.push_str(&format!("{}{}{}\n", BOLD, heading, RESET)),
+            false => rendered.push_str(&format!("{}\n", heading)),
+        }
+        let first = found.line.saturating_sub(context).max(1);
+        rendered.push_str(&render_markdown(&content, first, found.line + context, Some((found.line, mark)), color));
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// Ranked search results with the first matching line of each note.
+pub fn ranked_search_table(results: &[RankedNote], color: bool) -> Table {
+    let mut table = Table::new(&["PATH", "SCORE", "SNIPPET"]);
+    for result in results {
+        let snippet = result.matches.first().map(|found| highlighted_snippet(found, color)).unwrap_or_default();
+        table.row(vec![result.path.clone(), format!("{:.3}", result.score), snippet], None);
+    }
+    table
+}
+
+pub fn external_links_table(links: &[ExternalLink]) -> Table {
+    let checked = links.iter().any(|link| link.dead.is_some());
+    let mut table = match checked {
+        true => Table::new(&["SOURCE", "LINE", "URL", "STATUS"]),
+        false => Table::new(&["SOURCE", "LINE", "URL"]),
+    };
+    for link in links {
+        let mut cells = vec![link.source.clone(), link.line.to_string(), link.url.clone()];
+        if checked {
+            cells.push(match (&link.status, &link.error) {
+                (Some(status), _) => status.to_string(),
+                (None, Some(error)) => error.clone(),
+                (None, None) => String::new(),
+            });
+        }
+        table.row(cells, link.dead.filter(|dead| *dead).map(|_| RED));
+    }
+    table
+}
+
+/// The subcommands of `cli`, nested ones included, with their formats and whether they write.
+pub fn capabilities(cli: &clap::Command) -> Capabilities {
+    fn visit(command: &clap::Command, prefix: &str, found: &mut Vec<CommandCapability>) {
+        for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set() && sub.get_name() != "help") {
+            let name = format!("{}{}", prefix, sub.get_name());
+            found.push(CommandCapability {
+                name: name.clone(),
+                about: sub.get_about().map(|about| about.to_string()).unwrap_or_default(),
+                formats: sub
+                    .get_arguments()
+                    .find(|arg| arg.get_id() == "format")
+                    .map(|arg| arg.get_possible_values().iter().map(|value| value.get_name().to_string()).collect())
+                    .unwrap_or_default(),
+                writes: sub.get_arguments().any(|arg| arg.get_id() == "dry_run"),
+            });
+            visit(sub, &format!("{} ", name), found);
+        }
+    }
+    let mut commands = Vec::new();
+    visit(cli, "", &mut commands);
+    commands.sort_by(|a, b| a.name.cmp(&b.name));
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        schema_version: SCHEMA_VERSION,
+        change_set_version: CHANGE_SET_VERSION,
+        features: BTreeMap::from([("fuse", cfg!(feature = "fuse"))]),
+        commands,
+    }
+}
+
+pub fn capabilities_table(capabilities: &Capabilities) -> Table {
+    let mut table = Table::new(&["COMMAND", "FORMATS", "WRITES"]);
+    for command in &capabilities.commands {
+        let writes = if command.writes { "yes" } else { "" };
+        table.row(vec![command.name.clone(), command.formats.join(" "), writes.to_string()], None);
+    }
+    table
+}
+
+/// Rows of every table, after their note's path and line, under the columns of all of them.
+pub fn note_tables_table(tables: &[NoteTable]) -> Table {
+    let mut columns: Vec<&str> = Vec::new();
+    for column in tables.iter().flat_map(|t| &t.columns) {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+    let mut table = Table::new(&[&["PATH", "LINE"], columns.as_slice()].concat());
+    for note_table in tables {
+        for row in &note_table.rows {
+            let cells = columns.iter().map(|column| row.get(*column).and_then(|v| v.as_str()).unwrap_or_default().to_string());
+            table.row([note_table.path.clone(), note_table.line.to_string()].into_iter().chain(cells).collect(), None);
+        }
+    }
+    table
+}
+
+pub fn found_notes_table(notes: &[FoundNote]) -> Table {
+    let mut table = Table::new(&["PATH", "MATCHED", "SCORE"]);
+    for note in notes {
+        table.row(vec![note.path.clone(), note.matched.clone(), note.score.to_string()], None);
+    }
+    table
+}
+
+pub fn orphans_table(orphans: &[String]) -> Table {
+    let mut table = Table::new(&["NOTE"]);
+    for orphan in orphans {
+        table.row(vec![orphan.clone()], None);
+    }
+    table
+}
+
+pub fn render_stats_table(stats: &StatsOutput, color: bool) -> String {
+    let mut table = Table::new(&["METRIC", "VALUE"]);
+    let rows = [
+        ("notes", stats.total_notes),
+        ("tags", stats.total_tags),
+        ("links", stats.total_links),
+        ("broken links", stats.broken_links),
+        ("orphaned notes", stats.orphaned_notes),
+        ("archived notes", stats.archived_notes),
+    ];
+    for (name, value) in rows {
+        let row_color = (name == "broken links" && value > 0).then_some(RED);
+        table.row(vec![name.to_string(), value.to_string()], row_color);
+    }
+    table.render(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_align_columns_and_right_align_numbers() {
+        let tags = [TagCount { tag: "a".into(), count: 12 }, TagCount { tag: "long/tag".into(), count: 3 }];
+        assert_eq!(tags_table(&tags).render(false), "TAG       NOTES\na            12\nlong/tag      3");
+        assert!(tags_table(&tags).render(true).starts_with("\x1b[1mTAG"));
+        let links = [LinkInfo {
+            source: "a, b.md".into(),
+            target: "say \"hi\"".into(),
+            heading: None,
+            exists: false,
+            broken_heading: false,
+            broken_block: false,
+            placeholder: false,
+            embed: true,
+        }];
+        assert_eq!(links_table(&links).to_csv(), "source,target,kind,status\r\n\"a, b.md\",\"say \"\"hi\"\"\",embed,broken\r\n");
+    }
+
+    #[test]
+    fn rendered_markdown_keeps_the_text_and_marks_the_match() {
+        let content = "---\ntags: [x]\n---\n# Title\n```py\nx = 1  # one\n```\nUse **it**";
+        let plain = render_markdown(content, 4, 8, Some((8, [0, 3])), false);
+        assert_eq!(plain, "    4 │ # Title\n    5 │ ```py\n    6 │ x = 1  # one\n    7 │ ```\n    8 │ Use **it**\n");
+        let colored = render_markdown(content, 6, 8, Some((8, [0, 3])), true);
+        assert!(colored.contains(&format!("{}1{}  {}{}# one", CYAN, RESET, RESET, DIM)));
+        assert!(colored.contains(&format!("{}{}Use{} {}{}**it**", RESET, REVERSE, RESET, RESET, BOLD)));
+    }
+
+    #[test]
+    fn templates_render_json_results_unescaped() {
+        let data = serde_json::json!({"links": [{"source": "a.md", "target": "B & C"}], "broken_count": 1});
+        let template = "{{broken_count}} broken:\n{{#each links}}- [[{{target}}]] in {{source}}\n{{/each}}";
+        assert_eq!(render_template(template, &data).unwrap(), "1 broken:\n- [[B & C]] in a.md\n");
+    }
+
+    #[test]
+    fn capabilities_list_nested_commands_with_formats() {
+        use clap::{Arg, ArgAction, Command};
+        let cli = Command::new("obsidian-cli")
+            .subcommand(Command::new("links").arg(Arg::new("format").long("format").value_parser(["json", "csv"])))
+            .subcommand(Command::new("tag").subcommand(Command::new("rename").arg(Arg::new("dry_run").long("dry-run").action(ArgAction::SetTrue))))
+            .subcommand(Command::new("secret").hide(true));
+        let found = capabilities(&cli);
+        let names: Vec<&str> = found.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["links", "tag", "tag rename"]);
+        assert_eq!(found.commands[0].formats, ["json", "csv"]);
+        assert!(found.commands[2].writes && !found.commands[1].writes);
+    }
+}
@@ -0,0 +1,714 @@
+//! The parsing rules behind every command: tags, wikilinks, reference links, frontmatter and
+//! tables. They work on a note's text alone, so other tools can read notes exactly the way
+//! this CLI does.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// What counts as part of an inline `#tag`. Any character not allowed here ends the tag,
+/// so `#tag.` is the tag "tag" unless "." is listed in `extra_chars`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TagSyntax {
+    /// Accept non-ASCII letters and digits, as Obsidian does (#café)
+    pub unicode: bool,
+    /// Reject purely numeric tags like #123, as Obsidian does
+    pub require_non_numeric: bool,
+    /// Skip hashes inside frontmatter text, fenced code blocks and inline code
+    pub ignore_code: bool,
+    /// Extra characters allowed inside a tag, e.g. "." for #v1.2
+    pub extra_chars: String,
+}
+
+impl TagSyntax {
+    /// The tag grammar of the Obsidian app itself.
+    pub fn obsidian_strict() -> Self {
+        TagSyntax {
+            unicode: true,
+            require_non_numeric: true,
+            ignore_code: true,
+            extra_chars: String::new(),
+        }
+    }
+
+    pub(crate) fn is_tag_char(&self, c: char) -> bool {
+        c.is_ascii_alphanumeric()
+            || matches!(c, '_' | '-' | '/')
+            || (self.unicode && c.is_alphanumeric())
+            || self.extra_chars.contains(c)
+    }
+}
+
+/// All of a note's tags: inline tags (see [`extract_inline_tags`]) followed by its
+/// frontmatter `tags`, duplicates included, in the order they appear.
+pub fn extract_tags_from_file(content: &str, syntax: &TagSyntax) -> Vec<String> {
+    let mut tags = extract_inline_tags(content, syntax);
+
+    tags.extend(Frontmatter::of(content).tags());
+    tags
+}
+
+/// Blanks out frontmatter, fenced code blocks and inline code spans, keeping line breaks
+/// so positions in the masked text still line up with the original.
+pub fn mask_code(content: &str) -> String {
+    let blank = |text: &str| -> String {
+        text.chars()
+            .map(|c| if c == '\n' { "\n".to_string() } else { " ".repeat(c.len_utf8()) })
+            .collect()
+    };
+    let mut masked = String::with_capacity(content.len());
+
+    let mut body = content;
+    if let Some(frontmatter) = extract_frontmatter(content) {
+        let end = frontmatter.len() + 8;
+        masked.push_str(&blank(&content[..end]));
+        body = &content[end..];
+    }
+
+    let mut in_fence: Option<String> = None;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+        match &in_fence {
+            Some(open) => {
+                if fence.len() >= open.len() && fence.starts_with(&open[..1]) {
+                    in_fence = None;
+                }
+                masked.push_str(&blank(line));
+            }
+            None if fence.len() >= 3 => {
+                in_fence = Some(fence);
+                masked.push_str(&blank(line));
+            }
+            None => {
+                let mut rest = line;
+                while let Some(start) = rest.find('`') {
+                    let ticks = rest[start..].chars().take_while(|c| *c == '`').count();
+                    let delimiter = &rest[start..start + ticks];
+                    match rest[start + ticks..].find(delimiter) {
+                        Some(len) => {
+                            let end = start + ticks + len + ticks;
+                            masked.push_str(&rest[..start]);
+                            masked.push_str(&blank(&rest[start..end]));
+                            rest = &rest[end..];
+                        }
+                        None => break,
+                    }
+                }
+                masked.push_str(rest);
+            }
+        }
+    }
+
+    masked
+}
+
+/// Like [`mask_code`], but keeps the frontmatter: links in properties count, as in Obsidian.
+pub(crate) fn mask_code_blocks(content: &str) -> String {
+    let frontmatter_end = extract_frontmatter(content).map_or(0, |frontmatter| frontmatter.len() + 8);
+    let mut masked = mask_code(content);
+    masked.replace_range(..frontmatter_end, &content[..frontmatter_end]);
+    masked
+}
+
+/// Finds inline tags like #tag or #tag/subtag. A tag has to start a line or follow
+/// whitespace, so headings, URL fragments and [[Note#Heading]] links are not tags.
+pub fn extract_inline_tags(content: &str, syntax: &TagSyntax) -> Vec<String> {
+    let masked;
+    let text = if syntax.ignore_code {
+        masked = mask_code(content);
+        &masked
+    } else {
+        content
+    };
+
+    let mut tags = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c == '#' && previous.is_none_or(|p| p.is_whitespace()) {
+            let start = index + 1;
+            let mut end = start;
+            while let Some(&(i, next)) = chars.peek() {
+                if !syntax.is_tag_char(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+
+            // Sentence punctuation allowed through extra_chars shouldn't end up in the tag
+            let tag = text[start..end].trim_end_matches(|c| syntax.extra_chars.contains(c));
+            let rejected = syntax.require_non_numeric && tag.chars().all(|c| c.is_ascii_digit());
+            if !tag.is_empty() && !rejected {
+                tags.push(tag.to_string());
+            }
+            previous = text[..end].chars().next_back();
+            continue;
+        }
+        previous = Some(c);
+    }
+
+    tags
+}
+
+/// The YAML between a leading `---` line and the next `---` line, without the fences.
+pub fn extract_frontmatter(content: &str) -> Option<String> {
+    if let Some(rest) = content.strip_prefix("---\n")
+        && let Some(end_pos) = rest.find("\n---\n")
+    {
+        return Some(rest[..end_pos].to_string());
+    }
+    None
+}
+
+/// A note's frontmatter properties, parsed as YAML. Keys keep the order they're written in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frontmatter {
+    properties: serde_yaml::Mapping,
+}
+
+impl Frontmatter {
+    /// Parses the YAML of a frontmatter block, as returned by [`extract_frontmatter`]. An
+    /// empty block, or one that is a plain value rather than key/value pairs, has no
+    /// properties.
+    pub fn parse(frontmatter: &str) -> Result<Frontmatter, serde_yaml::Error> {
+        let properties = match serde_yaml::from_str::<serde_yaml::Value>(frontmatter)? {
+            serde_yaml::Value::Mapping(properties) => properties,
+            _ => serde_yaml::Mapping::new(),
+        };
+        Ok(Frontmatter { properties })
+    }
+
+    /// The note's frontmatter. Notes without any, or whose frontmatter isn't valid YAML,
+    /// have no properties, as in the app.
+    pub fn of(content: &str) -> Frontmatter {
+        extract_frontmatter(content)
+            .and_then(|frontmatter| Frontmatter::parse(&frontmatter).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Property names in the order they're written.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.properties.keys().filter_map(serde_yaml::Value::as_str)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&serde_yaml::Value> {
+        self.properties.get(key)
+    }
+
+    /// Whether the property is set to true (`true` or `yes`).
+    pub fn flag(&self, key: &str) -> bool {
+        match self.get(key) {
+            Some(serde_yaml::Value::Bool(value)) => *value,
+            Some(serde_yaml::Value::String(value)) => matches!(value.as_str(), "true" | "yes"),
+            _ => false,
+        }
+    }
+
+    /// Values of a list property the way Obsidian reads them: a YAML list or a single
+    /// string split on `separators`, under the key or its legacy singular form. Tags lose
+    /// their `#`.
+    pub fn list(&self, key: &str, legacy_key: &str, separators: &[char]) -> Vec<String> {
+        let scalar = |value: &serde_yaml::Value| match value {
+            serde_yaml::Value::String(s) => Some(s.clone()),
+            serde_yaml::Value::Number(n) => Some(n.to_string()),
+            serde_yaml::Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        };
+
+        let mut values = Vec::new();
+        for name in [key, legacy_key] {
+            let items: Vec<String> = match self.get(name) {
+                Some(serde_yaml::Value::Sequence(items)) => items.iter().filter_map(scalar).collect(),
+                Some(value) => scalar(value)
+                    .map(|s| s.split(separators).map(|v| v.to_string()).collect())
+                    .unwrap_or_default(),
+                None => continue,
+            };
+            for item in items {
+                let item = item.trim();
+                let item = if key == "tags" { item.trim_start_matches('#') } else { item };
+                if !item.is_empty() {
+                    values.push(item.to_string());
+                }
+            }
+        }
+        values
+    }
+
+    /// The `tags` (or legacy `tag`) property, as a list or a string of tags separated by
+    /// commas or spaces.
+    pub fn tags(&self) -> Vec<String> {
+        self.list("tags", "tag", &[',', ' '])
+    }
+
+    /// The properties as a JSON object, for output and queries. Keys that aren't strings
+    /// are written as text.
+    pub fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        match serde_json::to_value(&self.properties) {
+            Ok(serde_json::Value::Object(properties)) => properties,
+            _ => serde_json::Map::new(),
+        }
+    }
+}
+
+/// The frontmatter `tags` (or legacy `tag`) property, as in [`Frontmatter::tags`]; None when
+/// there are none.
+pub fn parse_frontmatter_tags(frontmatter: &str) -> Option<Vec<String>> {
+    let tags = frontmatter_list(frontmatter, "tags", "tag", &[',', ' ']);
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags)
+    }
+}
+
+/// [`Frontmatter::list`] on a frontmatter block. Frontmatter that isn't valid YAML has no
+/// properties, as in the app.
+pub fn frontmatter_list(frontmatter: &str, key: &str, legacy_key: &str, separators: &[char]) -> Vec<String> {
+    Frontmatter::parse(frontmatter)
+        .map(|frontmatter| frontmatter.list(key, legacy_key, separators))
+        .unwrap_or_default()
+}
+
+/// Targets of the note's `[[wikilinks]]` in order, without their `|alias`. Any `#heading`
+/// is kept; see [`split_link_anchor`]. Code is skipped.
+pub fn extract_links_from_file(content: &str) -> Vec<String> {
+    extract_links_with_embeds(content).into_iter().map(|(link, _)| link).collect()
+}
+
+/// Like [`extract_links_from_file`], with whether each link is an `![[embed]]`.
+pub fn extract_links_with_embeds(content: &str) -> Vec<(String, bool)> {
+    // Match [[link]], [[link|alias]] and ![[embed]]
+    static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(!?)\[\[([^\]|]+)(?:\|[^\]]*)?\]\]").unwrap());
+    LINK_REGEX
+        .captures_iter(&mask_code_blocks(content))
+        .map(|cap| (cap[2].to_string(), !cap[1].is_empty()))
+        .collect()
+}
+
+/// Destinations of the note's Markdown links to other notes, `[text](Note.md)` or
+/// `[text](<My Note.md#Heading>)`, with whether each is an `![embed]`. Only `.md` files
+/// count: links to web pages and attachments are left out. Destinations are as written,
+/// still percent-encoded (see [`percent_decode`]), and code is skipped.
+pub fn extract_markdown_links(content: &str) -> Vec<(String, bool)> {
+    static MARKDOWN_LINK_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"(!?)\[[^\[\]]*\]\(\s*(<[^>\n]*>|[^)\s]+)(?:\s+"[^"\n]*")?\s*\)"#).unwrap());
+    MARKDOWN_LINK_REGEX
+        .captures_iter(&mask_code(content))
+        .filter_map(|cap| {
+            let destination = cap[2].trim_start_matches('<').trim_end_matches('>');
+            let path = destination.split('#').next().unwrap_or_default();
+            let is_note = !path.contains("://") && percent_decode(path).to_lowercase().ends_with(".md");
+            is_note.then(|| (destination.to_string(), !cap[1].is_empty()))
+        })
+        .collect()
+}
+
+/// Splits a link target into the note and the heading after `#`, if any. `[[#Heading]]`
+/// links within the same note have an empty note part. Block references, `[[Note#^id]]` or
+/// `[[Note^id]]`, have the anchor `^id`.
+pub fn split_link_anchor(link: &str) -> (&str, Option<&str>) {
+    // Split [[Note#Heading]] into the note part and the anchor part
+    match link.split_once('#') {
+        Some((note, anchor)) => (note, Some(anchor).filter(|a| !a.is_empty())),
+        None => match link.find('^') {
+            Some(start) => (&link[..start], Some(&link[start..]).filter(|a| a.len() > 1)),
+            None => (link, None),
+        },
+    }
+}
+
+/// The ids of the note's blocks, marked with `^id` at the end of a paragraph, list item
+/// or other block (or on a line of their own after it), skipping code.
+pub fn extract_block_ids(content: &str) -> Vec<String> {
+    static BLOCK_ID_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)(?:^|\s)\^([A-Za-z0-9-]+)[ \t]*$").unwrap());
+    BLOCK_ID_REGEX
+        .captures_iter(&mask_code(content))
+        .map(|cap| cap[1].to_string())
+        .collect()
+}
+
+/// The text of the note's `# Headings`, in order, skipping frontmatter and code blocks.
+pub fn extract_headings(content: &str) -> Vec<String> {
+    static HEADING_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^ {0,3}#{1,6}[ \t]+(.+?)(?:[ \t]+#+)?[ \t]*$").unwrap());
+    let masked = mask_code(content);
+    HEADING_REGEX
+        .captures_iter(&masked)
+        .map(|cap| content[cap.get(1).unwrap().range()].to_string())
+        .collect()
+}
+
+/// Whether a link's `#anchor` names one of the headings. For nested anchors like
+/// `Plan#Goals` the last heading is checked. As in Obsidian, case and the characters that
+/// can't appear in a link (`#|^:%[]`) don't matter.
+pub fn anchor_matches_heading(anchor: &str, headings: &[String]) -> bool {
+    let normalize = |text: &str| {
+        text.chars()
+            .map(|c| if "#|^:%[]".contains(c) { ' ' } else { c })
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let anchor = normalize(anchor.rsplit('#').next().unwrap_or(anchor));
+    headings.iter().any(|heading| normalize(heading) == anchor)
+}
+
+/// A GitHub-flavored Markdown table in a note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownTable {
+    /// 1-based line of the header row
+    pub line: usize,
+    pub headers: Vec<String>,
+    /// Body rows, padded or cut to as many cells as there are headers
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Cells of a table row: split on pipes not escaped as `\|`, without the outer pipes.
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") { &line[..line.len() - 1] } else { line };
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+/// Finds the note's tables, skipping any in frontmatter or code blocks. A table is a row of
+/// headers, a delimiter row like `| --- | :-: |` with as many cells, and the rows after it
+/// up to the first line without a pipe.
+pub fn extract_tables(content: &str) -> Vec<MarkdownTable> {
+    static DELIMITER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*:?-+:?\s*$").unwrap());
+    let masked = mask_code(content);
+    let lines: Vec<(&str, &str)> = content.lines().zip(masked.lines()).collect();
+    let is_row = |i: usize| lines.get(i).is_some_and(|(line, masked)| line.contains('|') && !masked.trim().is_empty());
+
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let headers = table_cells(lines[i].0);
+        let delimiters = table_cells(lines[i + 1].0);
+        if !(is_row(i) && is_row(i + 1) && delimiters.len() == headers.len() && delimiters.iter().all(|d| DELIMITER.is_match(d))) {
+            i += 1;
+            continue;
+        }
+        let line = i + 1;
+        i += 2;
+        let mut rows = Vec::new();
+        while is_row(i) {
+            let mut cells = table_cells(lines[i].0);
+            cells.resize(headers.len(), String::new());
+            rows.push(cells);
+            i += 1;
+        }
+        tables.push(MarkdownTable { line, headers, rows });
+    }
+    tables
+}
+
+/// A `[text][label]` reference to a link definition, or a `[^label]` footnote reference.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkReference {
+    /// 1-based line
+    pub line: usize,
+    /// Lowercased with runs of whitespace collapsed, the way labels are matched
+    pub label: String,
+    pub footnote: bool,
+    /// A lone `[label]`, which is only a reference when the label is defined
+    pub shortcut: bool,
+}
+
+/// A `[label]: destination` link definition, or a `[^label]: text` footnote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkDefinition {
+    /// 1-based line
+    pub line: usize,
+    /// Lowercased with runs of whitespace collapsed, the way labels are matched
+    pub label: String,
+    /// Where the link goes, without `<>` or a title; empty for footnotes
+    pub destination: String,
+    pub footnote: bool,
+}
+
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+static DEFINITION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ {0,3}\[(\^?)([^\]]+)\]:[ \t]*(<[^>]*>|\S*)").unwrap());
+
+/// The note's link and footnote definitions, skipping code and frontmatter.
+pub fn extract_link_definitions(content: &str) -> Vec<LinkDefinition> {
+    mask_code(content)
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let cap = DEFINITION_REGEX.captures(line)?;
+            let footnote = !cap[1].is_empty();
+            let destination = if footnote { "" } else { cap[3].trim_start_matches('<').trim_end_matches('>') };
+            let label = normalize_label(&cap[2]);
+            (!label.is_empty() && (footnote || !destination.is_empty())).then(|| LinkDefinition {
+                line: i + 1,
+                label,
+                destination: destination.to_string(),
+                footnote,
+            })
+        })
+        .collect()
+}
+
+/// The note's reference-style links (`[text][label]`, `[label][]` and `[label]`) and
+/// footnote references (`[^label]`), skipping code, frontmatter, wikilinks, inline links
+/// and the definitions themselves.
+pub fn extract_link_references(content: &str) -> Vec<LinkReference> {
+    static WIKILINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[[^\]\n]*\]\]").unwrap());
+    static REFERENCE_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\[\^([^\]\s]+)\]|\[([^\[\]]*)\]\[([^\[\]]*)\]|\[([^\[\]]+)\](\()?").unwrap());
+
+    let masked = mask_code(content);
+    let masked = WIKILINK_REGEX.replace_all(&masked, |cap: &regex::Captures| " ".repeat(cap[0].len()));
+    let mut references = Vec::new();
+    for (i, line) in masked.lines().enumerate() {
+        if DEFINITION_REGEX.is_match(line) {
+            continue;
+        }
+        for cap in REFERENCE_REGEX.captures_iter(line) {
+            let (label, footnote, shortcut) = if let Some(label) = cap.get(1) {
+                (label.as_str(), true, false)
+            } else if let Some(label) = cap.get(3) {
+                // [text][] uses the text as the label
+                let label = if label.as_str().trim().is_empty() { &cap[2] } else { label.as_str() };
+                (label, false, false)
+            } else if cap.get(5).is_none() {
+                (&cap[4], false, true)
+            } else {
+                continue;
+            };
+            let label = normalize_label(label);
+            if !label.is_empty() {
+                references.push(LinkReference {
+                    line: i + 1,
+                    label,
+                    footnote,
+                    shortcut,
+                });
+            }
+        }
+    }
+    references
+}
+
+/// Decodes `%XX` escapes, as in `[text](My%20Note.md)`. Invalid escapes are kept as written.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |b: u8| (b as char).to_digit(16);
+        if bytes[i] == b'%'
+            && let Some(high) = bytes.get(i + 1).and_then(|b| hex(*b))
+            && let Some(low) = bytes.get(i + 2).and_then(|b| hex(*b))
+        {
+            decoded.push((high * 16 + low) as u8);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::{dedup_by_key, is_private_note};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn masking_keeps_byte_offsets_and_lines(content in "(---\n|```\n|`|#|[a-zé ]|\n){0,60}") {
+            let masked = mask_code(&content);
+            prop_assert_eq!(masked.len(), content.len());
+            let newlines = |text: &str| text.match_indices('\n').map(|(i, _)| i).collect::<Vec<_>>();
+            prop_assert_eq!(newlines(&masked), newlines(&content));
+        }
+
+        #[test]
+        fn inline_tags_only_contain_tag_characters(content in "(#|[a-z0-9_/.é-]| |\n){0,60}") {
+            let syntax = TagSyntax::default();
+            for tag in extract_inline_tags(&content, &syntax) {
+                prop_assert!(!tag.is_empty());
+                prop_assert!(tag.chars().all(|c| syntax.is_tag_char(c)), "{:?}", tag);
+            }
+        }
+
+        #[test]
+        fn wikilinks_round_trip(note in "[^\\[\\]|#^`\n]{1,20}", heading in "[^\\[\\]|#`\n]{0,10}", alias in "[^\\[\\]`\n]{0,10}") {
+            let target = if heading.is_empty() { note.clone() } else { format!("{}#{}", note, heading) };
+            let content = format!("see [[{}|{}]] and [[{}]]", target, alias, target);
+            let links = extract_links_from_file(&content);
+            prop_assert_eq!(links.clone(), vec![target.clone(), target]);
+            let (parsed_note, parsed_heading) = split_link_anchor(&links[0]);
+            prop_assert_eq!(parsed_note, note.as_str());
+            prop_assert_eq!(parsed_heading, Some(heading.as_str()).filter(|h| !h.is_empty()));
+        }
+
+        #[test]
+        fn frontmatter_tags_read_the_same_as_list_or_string(tags in prop::collection::vec("[a-z][a-z0-9/_-]{0,8}", 1..5)) {
+            let list = format!("tags: [{}]", tags.join(", "));
+            let string = format!("tags: \"{}\"", tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "));
+            prop_assert_eq!(parse_frontmatter_tags(&list), Some(tags.clone()));
+            prop_assert_eq!(parse_frontmatter_tags(&string), Some(tags));
+        }
+
+        #[test]
+        fn reference_labels_match_their_definitions(label in "[A-Za-z][A-Za-z0-9 ]{0,8}[A-Za-z0-9]", text in "[a-z ]{1,8}") {
+            let content = format!("See [{}][{}], [{}][] and [^{}].\n\n[{}]: <https://example.com/a b>\n[^{}]: A note.\n", text, label, label, label.replace(' ', "-"), label.to_uppercase(), label.replace(' ', "-"));
+            let references = extract_link_references(&content);
+            let definitions = extract_link_definitions(&content);
+            let normalized = normalize_label(&label);
+            prop_assert_eq!(references.iter().map(|r| (r.label.clone(), r.footnote)).collect::<Vec<_>>(), vec![
+                (normalized.clone(), false),
+                (normalized.clone(), false),
+                (normalize_label(&label.replace(' ', "-")), true),
+            ]);
+            prop_assert_eq!(definitions.iter().map(|d| (d.label.clone(), d.destination.clone())).collect::<Vec<_>>(), vec![
+                (normalized, "https://example.com/a b".to_string()),
+                (normalize_label(&label.replace(' ', "-")), String::new()),
+            ]);
+        }
+
+        #[test]
+        fn markdown_links_keep_note_destinations(name in "[A-Za-z][A-Za-z0-9 ]{0,10}", heading in "[a-z]{0,6}") {
+            let encoded = name.replace(' ', "%20");
+            let anchor = if heading.is_empty() { String::new() } else { format!("#{}", heading) };
+            let content = format!("[a]({}.md{}) ![b](<{}.md>) [c](https://x.io/{}.md) [d](pic.png) `[e]({}.md)`", encoded, anchor, name, encoded, name);
+            let links = extract_markdown_links(&content);
+            prop_assert_eq!(links, vec![(format!("{}.md{}", encoded, anchor), false), (format!("{}.md", name), true)]);
+            prop_assert_eq!(percent_decode(&encoded), name);
+        }
+
+        #[test]
+        fn heading_anchors_find_their_headings(heading in "[A-Za-z][A-Za-z0-9 ]{0,10}[A-Za-z0-9]", level in 1usize..=6) {
+            let content = format!("---\n# comment: x\n---\n{} {} ##\n```\n# Code\n```\n", "#".repeat(level), heading);
+            let headings = extract_headings(&content);
+            prop_assert_eq!(headings.clone(), vec![heading.clone()]);
+            prop_assert!(anchor_matches_heading(&heading.to_uppercase(), &headings));
+            let nested = format!("Parent#{}", heading);
+            prop_assert!(anchor_matches_heading(&nested, &headings));
+            prop_assert!(!anchor_matches_heading("Code", &headings));
+        }
+
+        #[test]
+        fn block_references_find_their_ids(note in "[A-Za-z][A-Za-z ]{0,10}", id in "[a-z0-9][a-z0-9-]{0,8}") {
+            let block = format!("^{}", id);
+            for link in [format!("{}#{}", note, block), format!("{}{}", note, block)] {
+                prop_assert_eq!(split_link_anchor(&link), (note.as_str(), Some(block.as_str())));
+            }
+            let content = format!("A paragraph. {}\n- item\n\n{}\n`x ^{}`\n```\ncode ^skipped\n```\n", block, block, id);
+            prop_assert_eq!(extract_block_ids(&content), vec![id.clone(), id]);
+        }
+
+        #[test]
+        fn tables_round_trip(headers in prop::collection::vec("[a-z|]{1,6}", 1..4), rows in prop::collection::vec(prop::collection::vec("[a-z0-9 |]{0,6}", 4), 0..4)) {
+            let row = |cells: &[String]| format!("| {} |", cells.iter().map(|c| c.replace('|', "\\|")).collect::<Vec<_>>().join(" | "));
+            let rows: Vec<Vec<String>> = rows.iter().map(|r| r[..headers.len()].iter().map(|c| c.trim().to_string()).collect()).collect();
+            let mut content = format!("Intro\n\n{}\n|{}\n", row(&headers), " --- |".repeat(headers.len()));
+            for cells in &rows {
+                content.push_str(&row(cells));
+                content.push('\n');
+            }
+            content.push_str("\n```\n| a |\n| - |\n```\n");
+            let tables = extract_tables(&content);
+            prop_assert_eq!(tables, vec![MarkdownTable { line: 3, headers, rows }]);
+        }
+    }
+
+    fn inline(content: &str, syntax: &TagSyntax) -> Vec<String> {
+        extract_inline_tags(content, syntax)
+    }
+
+    #[test]
+    fn default_tags_match_legacy_grammar() {
+        let syntax = TagSyntax::default();
+        assert_eq!(inline("#tag #nested/tag #with-dash_underscore", &syntax), ["tag", "nested/tag", "with-dash_underscore"]);
+        assert_eq!(inline("end of sentence #tag.", &syntax), ["tag"]);
+        assert_eq!(inline("#123", &syntax), ["123"]);
+        assert_eq!(inline("`#code`", &syntax), Vec::<String>::new());
+        assert_eq!(inline("inline `code #tag`", &syntax), ["tag"]);
+        assert_eq!(inline("#café", &syntax), ["caf"]);
+    }
+
+    #[test]
+    fn tags_need_leading_whitespace() {
+        let syntax = TagSyntax::default();
+        assert_eq!(inline("# Heading", &syntax), Vec::<String>::new());
+        assert_eq!(inline("[[Note#Heading]] and https://example.com/#anchor", &syntax), Vec::<String>::new());
+        assert_eq!(inline("#a#b", &syntax), ["a"]);
+        assert_eq!(inline("line\n#next", &syntax), ["next"]);
+    }
+
+    #[test]
+    fn obsidian_strict_matches_app_grammar() {
+        let syntax = TagSyntax::obsidian_strict();
+        assert_eq!(inline("#123 #y1984 #2024/q1", &syntax), ["y1984", "2024/q1"]);
+        assert_eq!(inline("#café #日本語", &syntax), ["café", "日本語"]);
+        assert_eq!(inline("#tag. #tag, #tag!", &syntax), ["tag", "tag", "tag"]);
+        assert_eq!(inline("inline `code #tag` #real", &syntax), ["real"]);
+        assert_eq!(inline("```\n#fenced\n```\n#after", &syntax), ["after"]);
+        assert_eq!(inline("---\ntitle: a #b\n---\n#body", &syntax), ["body"]);
+    }
+
+    #[test]
+    fn extra_chars_extend_tags_but_not_trailing_punctuation() {
+        let syntax = TagSyntax {
+            extra_chars: ".".to_string(),
+            ..TagSyntax::default()
+        };
+        assert_eq!(inline("#v1.2 and #end.", &syntax), ["v1.2", "end"]);
+    }
+
+    #[test]
+    fn frontmatter_lists_accept_every_obsidian_form() {
+        assert_eq!(frontmatter_list("tags:\n  - a\n  - '#b'", "tags", "tag", &[',', ' ']), ["a", "b"]);
+        assert_eq!(frontmatter_list("tags: a, b c", "tags", "tag", &[',', ' ']), ["a", "b", "c"]);
+        assert_eq!(frontmatter_list("tags: a #comment", "tags", "tag", &[',', ' ']), ["a"]);
+        assert_eq!(frontmatter_list("tag: [x, 2024]", "tags", "tag", &[',', ' ']), ["x", "2024"]);
+        assert_eq!(frontmatter_list("aliases: First, Second one", "aliases", "alias", &[',']), ["First", "Second one"]);
+        assert_eq!(frontmatter_list("tags: [unclosed", "tags", "tag", &[',']), Vec::<String>::new());
+        assert_eq!(dedup_by_key(["Tag", "tag", "other"].map(String::from), |t| t.to_lowercase()), ["Tag", "other"]);
+    }
+
+    #[test]
+    fn frontmatter_is_read_as_yaml() {
+        let note = "---\n\"title\": Plan\nprivate: yes\ntags: [\"a b\", '#c']\nproject:\n  owner: me\n  steps:\n    - one\n---\nBody #d\n";
+        let frontmatter = Frontmatter::of(note);
+        assert_eq!(frontmatter.keys().collect::<Vec<_>>(), ["title", "private", "tags", "project"]);
+        assert!(frontmatter.flag("private"));
+        assert_eq!(frontmatter.tags(), ["a b", "c"]);
+        assert_eq!(frontmatter.to_json()["project"], serde_json::json!({"owner": "me", "steps": ["one"]}));
+        assert!(is_private_note(note, &frontmatter, &TagSyntax::default()));
+        let nested = "---\nproject:\n  private: true\n---\n";
+        assert!(!is_private_note(nested, &Frontmatter::of(nested), &TagSyntax::default()));
+        assert!(Frontmatter::of("---\ntags: [unclosed\n---\n").is_empty());
+    }
+}
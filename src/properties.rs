@@ -0,0 +1,524 @@
+//! Frontmatter properties: listing, editing and auditing them.
+
+use crate::config::{Config, ScanOptions};
+use crate::parse::{Frontmatter, extract_frontmatter, extract_inline_tags};
+use crate::query;
+use crate::scan::{dedup_by_key, find_note_path, markdown_files, read_note, relative_path};
+use crate::search::note_facts;
+use crate::workspace::read_obsidian_json;
+use crate::write::VaultWriter;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Serialize)]
+pub struct MetaEditOutput {
+    dry_run: bool,
+    key: String,
+    /// The value written, as YAML; None when the key is removed
+    value: Option<String>,
+    /// Notes whose frontmatter changed
+    notes: Vec<String>,
+    /// Matching notes left alone because their frontmatter isn't valid YAML
+    skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PluginFrontmatter {
+    tool: &'static str,
+    plugin_id: Option<&'static str>,
+    /// Whether the plugin is enabled in .obsidian/community-plugins.json; core keys are always in use
+    in_use: bool,
+    keys: Vec<String>,
+    notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct FrontmatterAuditOutput {
+    tools: Vec<PluginFrontmatter>,
+    dry_run: bool,
+    stripped: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PropertyType {
+    /// As Obsidian names property types: text, list, number, checkbox, date, datetime,
+    /// plus object for nested mappings and empty for keys without a value
+    #[serde(rename = "type")]
+    kind: &'static str,
+    notes: usize,
+    /// A few of the notes using it, to find the odd ones out
+    examples: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PropertyUsage {
+    key: String,
+    notes: usize,
+    /// Most used first
+    types: Vec<PropertyType>,
+}
+
+#[derive(Serialize)]
+pub struct PropertiesOutput {
+    properties: Vec<PropertyUsage>,
+}
+
+#[derive(Serialize)]
+pub struct InspectOutput {
+    path: String,
+    /// Frontmatter tags then inline tags, deduplicated ignoring case
+    tags: Vec<String>,
+    frontmatter_tags: Vec<String>,
+    inline_tags: Vec<String>,
+    aliases: Vec<String>,
+    cssclasses: Vec<String>,
+    /// Every frontmatter property as parsed
+    properties: serde_json::Value,
+}
+
+/// Frontmatter keys written by tools rather than by hand: (tool, community plugin id,
+/// keys). Keys ending in `-` match as prefixes. Tools without a plugin id are core
+/// Obsidian and never counted as unused.
+const PLUGIN_FRONTMATTER_KEYS: &[(&str, Option<&str>, &[&str])] = &[
+    ("Kanban", Some("obsidian-kanban"), &["kanban-plugin"]),
+    ("Excalidraw", Some("obsidian-excalidraw-plugin"), &["excalidraw-"]),
+    ("Banners", Some("obsidian-banners"), &["banner", "banner_x", "banner_y", "banner_icon", "banner_lock"]),
+    ("Linter", Some("obsidian-linter"), &["date created", "date modified"]),
+    ("CSS classes", None, &["cssclasses", "cssclass"]),
+];
+
+fn plugin_key_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('-') {
+        Some(prefix) => key.starts_with(prefix) && key[prefix.len()..].starts_with('-'),
+        None => key == pattern,
+    }
+}
+
+/// The property type Obsidian would show for a frontmatter value.
+pub(crate) fn property_type(value: &serde_yaml::Value) -> &'static str {
+    static DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap());
+    static DATETIME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(:\d{2})?$").unwrap());
+    match value {
+        serde_yaml::Value::Null => "empty",
+        serde_yaml::Value::Bool(_) => "checkbox",
+        serde_yaml::Value::Number(_) => "number",
+        serde_yaml::Value::Sequence(_) => "list",
+        serde_yaml::Value::Mapping(_) => "object",
+        serde_yaml::Value::String(s) if DATE.is_match(s) => "date",
+        serde_yaml::Value::String(s) if DATETIME.is_match(s) => "datetime",
+        serde_yaml::Value::String(_) => "text",
+        serde_yaml::Value::Tagged(tagged) => property_type(&tagged.value),
+    }
+}
+
+/// Every frontmatter key used in the vault with how many notes use it and the types its
+/// values have, most used keys first.
+pub fn list_properties(vault_path: &Path, options: &ScanOptions) -> Result<PropertiesOutput, String> {
+    const EXAMPLES: usize = 3;
+
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut usage: BTreeMap<String, BTreeMap<&'static str, Vec<String>>> = BTreeMap::new();
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let frontmatter = Frontmatter::of(&content);
+        let relative = relative_path(vault_path, &path);
+        for key in frontmatter.keys() {
+            let kind = frontmatter.get(key).map_or("empty", property_type);
+            usage.entry(key.to_string()).or_default().entry(kind).or_default().push(relative.clone());
+        }
+    }
+
+    let mut properties: Vec<PropertyUsage> = usage
+        .into_iter()
+        .map(|(key, kinds)| {
+            let mut types: Vec<PropertyType> = kinds
+                .into_iter()
+                .map(|(kind, notes)| PropertyType {
+                    kind,
+                    notes: notes.len(),
+                    examples: notes.into_iter().take(EXAMPLES).collect(),
+                })
+                .collect();
+            types.sort_by(|a, b| b.notes.cmp(&a.notes).then(a.kind.cmp(b.kind)));
+            PropertyUsage {
+                key,
+                notes: types.iter().map(|t| t.notes).sum(),
+                types,
+            }
+        })
+        .collect();
+    properties.sort_by(|a, b| b.notes.cmp(&a.notes).then_with(|| a.key.cmp(&b.key)));
+    Ok(PropertiesOutput { properties })
+}
+
+/// Removes top-level frontmatter keys (with any indented or list lines that belong to
+/// them). Drops the frontmatter block entirely when nothing is left in it.
+fn strip_frontmatter_keys(content: &str, remove: impl Fn(&str) -> bool) -> Option<String> {
+    let frontmatter = extract_frontmatter(content)?;
+    let body = &content[frontmatter.len() + 9..];
+
+    let mut kept = Vec::new();
+    let mut removing = false;
+    for line in frontmatter.lines() {
+        if !line.starts_with([' ', '\t', '-']) {
+            removing = is_frontmatter_key_line(line, &remove);
+        }
+        if !removing {
+            kept.push(line);
+        }
+    }
+
+    if kept.len() == frontmatter.lines().count() {
+        None
+    } else if kept.iter().all(|line| line.trim().is_empty()) {
+        Some(body.trim_start_matches('\n').to_string())
+    } else {
+        Some(format!("---\n{}\n---\n{}", kept.join("\n"), body))
+    }
+}
+
+/// Whether `line` is the top-level frontmatter line for `key`. Continuation lines (indented,
+/// or list items) belong to the key above them.
+pub(crate) fn is_frontmatter_key_line(line: &str, key: impl Fn(&str) -> bool) -> bool {
+    !line.starts_with([' ', '\t', '-'])
+        && line
+            .split_once(':')
+            .is_some_and(|(name, _)| key(name.trim().trim_matches(['"', '\''])))
+}
+
+/// Sets a top-level frontmatter key to `value`, already written as YAML, replacing its old
+/// value in place or adding it at the end of the frontmatter. Other lines are left exactly
+/// as they were; a note without frontmatter gets a new block.
+fn set_frontmatter_key(content: &str, key: &str, value: &str) -> String {
+    let line = format!("{}: {}", key, value);
+    let Some(frontmatter) = extract_frontmatter(content) else {
+        return format!("---\n{}\n---\n{}", line, content);
+    };
+    let body = &content[frontmatter.len() + 9..];
+
+    let mut lines = Vec::new();
+    let mut replacing = false;
+    let mut found = false;
+    for existing in frontmatter.lines() {
+        if !existing.starts_with([' ', '\t', '-']) {
+            replacing = !found && is_frontmatter_key_line(existing, |name| name == key);
+            if replacing {
+                found = true;
+                lines.push(line.as_str());
+            }
+        }
+        if !replacing {
+            lines.push(existing);
+        }
+    }
+    if !found {
+        lines.push(&line);
+    }
+    format!("---\n{}\n---\n{}", lines.join("\n"), body)
+}
+
+/// A value from the command line as it should be written in YAML: as typed when it is a
+/// plain YAML value (`done`, `5`, `true`, `[a, b]`), quoted when it would otherwise read as
+/// something else.
+fn yaml_value(value: &str) -> Result<String, String> {
+    let plain = !value.contains('\n')
+        && !value.trim().is_empty()
+        && serde_yaml::from_str::<serde_yaml::Value>(value).is_ok_and(|parsed| !matches!(parsed, serde_yaml::Value::Mapping(_) | serde_yaml::Value::Tagged(_)));
+    if plain {
+        return Ok(value.to_string());
+    }
+    let quoted = serde_yaml::to_string(&value).map_err(|e| e.to_string())?;
+    Ok(quoted.trim_end().to_string())
+}
+
+/// Sets (`value` is Some) or removes a frontmatter key in every note matching `condition`,
+/// a query `WHERE` clause, or in every note when there is none. Notes already holding the
+/// value are left alone, as are archived notes (unless the scan includes them) and, without
+/// a condition to pick them out, the templates.
+pub fn edit_frontmatter(
+    vault_path: &Path,
+    config: &Config,
+    key: &str,
+    value: Option<&str>,
+    condition: Option<&str>,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<MetaEditOutput, String> {
+    if key.trim().is_empty() || key != key.trim() || key.contains([':', '\n', '#']) || key.starts_with(['-', '"', '\'']) {
+        return Err(format!("'{}' can't be used as a frontmatter key", key));
+    }
+    let condition = condition.map(query::parse_condition).transpose()?;
+    let value = value.map(yaml_value).transpose()?;
+    let parsed = value.as_deref().map(serde_yaml::from_str::<serde_yaml::Value>).transpose().map_err(|e| e.to_string())?;
+
+    let templates_folder = format!("{}/", config.templates_folder.trim_matches('/'));
+
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut notes = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) || (condition.is_none() && relative.starts_with(&templates_folder)) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        if let Some(condition) = &condition
+            && !condition.holds(&note_facts(&path, relative.clone(), &content, options))
+        {
+            continue;
+        }
+        let updated = match (&value, &parsed) {
+            (Some(value), Some(parsed)) => {
+                if extract_frontmatter(&content).is_some_and(|fm| Frontmatter::parse(&fm).is_err()) {
+                    skipped.push(relative);
+                    continue;
+                }
+                if Frontmatter::of(&content).get(key) == Some(parsed) {
+                    continue;
+                }
+                set_frontmatter_key(&content, key, value)
+            }
+            _ => match strip_frontmatter_keys(&content, |name| name == key) {
+                Some(updated) => updated,
+                None => continue,
+            },
+        };
+        writer.write(vault_path, &path, &updated)?;
+        notes.push(relative);
+    }
+
+    Ok(MetaEditOutput {
+        dry_run: writer.dry_run,
+        key: key.to_string(),
+        value,
+        notes,
+        skipped,
+    })
+}
+
+/// The top-level keys of a frontmatter block, each with its lines as written (nested and
+/// list lines included).
+pub(crate) fn frontmatter_entries(frontmatter: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in frontmatter.lines() {
+        if !line.starts_with([' ', '\t', '-'])
+            && let Some((key, _)) = line.split_once(':')
+        {
+            entries.push((key.trim().trim_matches(['"', '\'']).to_string(), line.to_string()));
+        } else if let Some((_, lines)) = entries.last_mut() {
+            lines.push('\n');
+            lines.push_str(line);
+        }
+    }
+    entries
+}
+
+pub fn frontmatter_audit(vault_path: &Path, strip_unused: bool, writer: &mut VaultWriter, options: &ScanOptions) -> Result<FrontmatterAuditOutput, String> {
+    let enabled = read_obsidian_json(vault_path, "community-plugins.json")?;
+    if strip_unused && enabled.is_none() {
+        return Err("can't tell which plugins are in use: no .obsidian/community-plugins.json found".to_string());
+    }
+    let enabled: HashSet<String> = enabled
+        .as_ref()
+        .and_then(|json| json.as_array())
+        .map(|ids| ids.iter().filter_map(|id| id.as_str()).map(|id| id.to_string()).collect())
+        .unwrap_or_default();
+
+    let mut tools: Vec<PluginFrontmatter> = PLUGIN_FRONTMATTER_KEYS
+        .iter()
+        .map(|&(tool, plugin_id, _)| PluginFrontmatter {
+            tool,
+            plugin_id,
+            in_use: plugin_id.is_none_or(|id| enabled.contains(id)),
+            keys: Vec::new(),
+            notes: Vec::new(),
+        })
+        .collect();
+    let tool_for_key = |key: &str| {
+        PLUGIN_FRONTMATTER_KEYS
+            .iter()
+            .position(|(_, _, patterns)| patterns.iter().any(|pattern| plugin_key_matches(pattern, key)))
+    };
+
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut stripped = Vec::new();
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let frontmatter = Frontmatter::of(&content);
+        if frontmatter.is_empty() {
+            continue;
+        }
+        let relative = relative_path(vault_path, &path);
+
+        let mut touched = BTreeSet::new();
+        for key in frontmatter.keys() {
+            if let Some(index) = tool_for_key(key) {
+                touched.insert(index);
+                if !tools[index].keys.iter().any(|k| k == key) {
+                    tools[index].keys.push(key.to_string());
+                }
+            }
+        }
+        for &index in &touched {
+            tools[index].notes.push(relative.clone());
+        }
+
+        if strip_unused
+            && touched.iter().any(|&index| !tools[index].in_use)
+            && let Some(updated) = strip_frontmatter_keys(&content, |key| tool_for_key(key).is_some_and(|index| !tools[index].in_use))
+        {
+            writer.write(vault_path, &path, &updated)?;
+            stripped.push(relative);
+        }
+    }
+
+    tools.retain(|tool| !tool.notes.is_empty());
+    for tool in &mut tools {
+        tool.keys.sort();
+    }
+
+    Ok(FrontmatterAuditOutput {
+        tools,
+        dry_run: writer.dry_run,
+        stripped,
+    })
+}
+
+pub fn inspect_note(vault_path: &Path, note: &str, options: &ScanOptions) -> Result<InspectOutput, String> {
+    let all_notes: HashSet<String> = markdown_files(vault_path).iter().map(|p| relative_path(vault_path, p)).collect();
+    let relative = find_note_path(vault_path, note, &all_notes).ok_or_else(|| format!("no note named {} in the vault", note))?;
+    let path = vault_path.join(&relative);
+    let content = read_note(&path, options).ok_or_else(|| format!("{} can't be read or is private", relative))?;
+
+    let frontmatter = Frontmatter::of(&content);
+    let frontmatter_tags = frontmatter.tags();
+    let inline_tags = extract_inline_tags(&content, &options.tag_syntax);
+    let tags = dedup_by_key(
+        frontmatter_tags.iter().chain(&inline_tags).map(|tag| options.canonical_tag(tag)),
+        |tag| tag.to_lowercase(),
+    );
+    let properties = serde_json::Value::Object(frontmatter.to_json());
+
+    Ok(InspectOutput {
+        path: relative,
+        tags,
+        frontmatter_tags,
+        inline_tags,
+        aliases: dedup_by_key(frontmatter.list("aliases", "alias", &[',']), str::to_string),
+        cssclasses: dedup_by_key(frontmatter.list("cssclasses", "cssclass", &[',', ' ']), str::to_string),
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stripping_frontmatter_keys_removes_their_values() {
+        let note = "---\ntitle: X\nexcalidraw-plugin: parsed\ncssclasses:\n  - wide\n---\nbody\n";
+        let stripped = strip_frontmatter_keys(note, |key| key == "cssclasses" || plugin_key_matches("excalidraw-", key));
+        assert_eq!(stripped.as_deref(), Some("---\ntitle: X\n---\nbody\n"));
+        assert_eq!(strip_frontmatter_keys("---\nkanban-plugin: basic\n---\n\n## Todo\n", |key| key == "kanban-plugin").as_deref(), Some("## Todo\n"));
+        assert_eq!(strip_frontmatter_keys(note, |key| key == "missing"), None);
+        assert!(!plugin_key_matches("excalidraw-", "excalidrawing"));
+    }
+
+    #[test]
+    fn property_types_follow_obsidian() {
+        let frontmatter = Frontmatter::of("---\na: 2024-01-02\nb: 2024-01-02 10:30\nc: '5'\nd: 5\ne: [x]\nf: no\ng:\n---\n");
+        let kinds: Vec<&str> = frontmatter.keys().map(|key| frontmatter.get(key).map_or("empty", property_type)).collect();
+        assert_eq!(kinds, ["date", "datetime", "text", "number", "list", "text", "empty"]);
+    }
+
+    #[test]
+    fn setting_frontmatter_keys_keeps_other_lines() {
+        let note = "---\ntitle:   Plan # keep\nstatus:\n  - old\n  - older\ndue: 2024-01-02\n---\nBody\n";
+        assert_eq!(set_frontmatter_key(note, "status", "done"), "---\ntitle:   Plan # keep\nstatus: done\ndue: 2024-01-02\n---\nBody\n");
+        assert_eq!(set_frontmatter_key(note, "owner", "me"), "---\ntitle:   Plan # keep\nstatus:\n  - old\n  - older\ndue: 2024-01-02\nowner: me\n---\nBody\n");
+        assert_eq!(set_frontmatter_key("Body\n", "status", "done"), "---\nstatus: done\n---\nBody\n");
+        assert_eq!(yaml_value("[a, b]").unwrap(), "[a, b]");
+        assert_eq!(yaml_value("a: b").unwrap(), "'a: b'");
+        assert_eq!(yaml_value("").unwrap(), "''");
+    }
+
+    #[test]
+    fn meta_set_skips_templates_and_archive_unless_targeted() {
+        let dir = std::env::temp_dir().join(format!("obsidian-cli-meta-set-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for folder in ["Templates", "Archive"] {
+            std::fs::create_dir_all(dir.join(folder)).unwrap();
+        }
+        std::fs::write(dir.join("Note.md"), "body\n").unwrap();
+        std::fs::write(dir.join("Templates/Daily.md"), "body\n").unwrap();
+        std::fs::write(dir.join("Archive/Old.md"), "body\n").unwrap();
+        let config = Config::default();
+        let options = ScanOptions::from_config(&config);
+
+        let mut writer = VaultWriter::new(true, false);
+        let output = edit_frontmatter(&dir, &config, "status", Some("done"), None, &mut writer, &options).unwrap();
+        assert_eq!(output.notes, ["Note.md"]);
+        let output = edit_frontmatter(&dir, &config, "status", Some("done"), Some("folder = \"Templates\""), &mut writer, &options).unwrap();
+        assert_eq!(output.notes, ["Templates/Daily.md"]);
+        let options = ScanOptions { include_archived: true, ..options };
+        let output = edit_frontmatter(&dir, &config, "status", Some("done"), None, &mut writer, &options).unwrap();
+        assert_eq!(output.notes, ["Archive/Old.md", "Note.md"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn frontmatter_audit_strips_keys_of_disabled_plugins_only() {
+        let dir = std::env::temp_dir().join(format!("obsidian-cli-frontmatter-audit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Board.md"), "---\nkanban-plugin: basic\ncssclasses: wide\n---\n## Todo\n").unwrap();
+        std::fs::write(dir.join("Drawing.md"), "---\nexcalidraw-plugin: parsed\ntitle: Sketch\n---\n").unwrap();
+        let mut writer = VaultWriter::new(false, false);
+        let options = ScanOptions::default();
+        assert!(frontmatter_audit(&dir, true, &mut writer, &options).is_err());
+
+        std::fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        std::fs::write(dir.join(".obsidian/community-plugins.json"), r#"["obsidian-excalidraw-plugin"]"#).unwrap();
+        let output = frontmatter_audit(&dir, true, &mut writer, &options).unwrap();
+        let tools: Vec<(&str, bool)> = output.tools.iter().map(|t| (t.tool, t.in_use)).collect();
+        assert_eq!(tools, [("Kanban", false), ("Excalidraw", true), ("CSS classes", true)]);
+        assert_eq!(output.tools[0].notes, ["Board.md"]);
+        assert_eq!(output.stripped, ["Board.md"]);
+        assert_eq!(std::fs::read_to_string(dir.join("Board.md")).unwrap(), "---\ncssclasses: wide\n---\n## Todo\n");
+        assert!(std::fs::read_to_string(dir.join("Drawing.md")).unwrap().contains("excalidraw-plugin"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn inspect_shows_effective_tags_aliases_and_classes() {
+        let dir = std::env::temp_dir().join(format!("obsidian-cli-inspect-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Plan.md"), "---\ntags: \"#js, work\"\nalias: [Roadmap, Roadmap]\ncssclass: wide narrow\nrank: 2\n---\n#Work #idea\n").unwrap();
+        std::fs::write(dir.join("Diary.md"), "#private").unwrap();
+
+        let options = ScanOptions { tag_aliases: BTreeMap::from([("js".to_string(), "javascript".to_string())]), ..ScanOptions::default() };
+        let output = inspect_note(&dir, "Plan", &options).unwrap();
+        assert_eq!(output.frontmatter_tags, ["js", "work"]);
+        assert_eq!(output.inline_tags, ["Work", "idea"]);
+        assert_eq!(output.tags, ["javascript", "work", "idea"]);
+        assert_eq!(output.aliases, ["Roadmap"]);
+        assert_eq!(output.cssclasses, ["wide", "narrow"]);
+        assert_eq!(output.properties["rank"], 2);
+        assert_eq!(inspect_note(&dir, "Diary", &options).err().as_deref(), Some("Diary.md can't be read or is private"));
+        assert!(inspect_note(&dir, "Missing", &options).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,532 @@
+//! A small Dataview-style query language over notes:
+//!
+//! ```text
+//! LIST | TABLE field, ...  [FROM source]  [WHERE condition]  [SORT field [ASC|DESC], ...]  [LIMIT n]
+//! ```
+//!
+//! Sources are `#tag` (including its nested tags) and `"folder"`, combined with `AND`,
+//! `OR`, `-`/`NOT` and parentheses. Conditions compare fields with `=`, `!=`, `<`, `<=`,
+//! `>` and `>=`, call `contains(field, value)` and `missing(field)`, and combine with `AND`,
+//! `OR` and `NOT`.
+//! Fields are the note's computed metadata or its frontmatter properties, with `a.b`
+//! reaching into nested properties. Conditions can also be parsed on their own, to filter
+//! other records the same way.
+
+use serde_json::Value;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Text(String),
+    Tag(String),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Source {
+    Tag(String),
+    Folder(String),
+    Not(Box<Source>),
+    And(Box<Source>, Box<Source>),
+    Or(Box<Source>, Box<Source>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Expr {
+    Field(String),
+    Literal(Value),
+    Compare(Box<Expr>, &'static str, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Query {
+    /// Columns of a TABLE query; empty for LIST
+    pub columns: Vec<String>,
+    pub source: Option<Source>,
+    pub filter: Option<Expr>,
+    /// Fields to sort by, and whether descending
+    pub sort: Vec<(String, bool)>,
+    pub limit: Option<usize>,
+}
+
+/// What a query can see of one note.
+pub(crate) struct NoteFacts {
+    pub path: String,
+    pub tags: Vec<String>,
+    /// Frontmatter properties, overlaid with the computed fields
+    pub fields: serde_json::Map<String, Value>,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let take_while = |i: &mut usize, keep: &dyn Fn(char) -> bool| {
+        let start = *i;
+        while *i < chars.len() && keep(chars[*i]) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' | ')' | ',' => {
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+                i += 1;
+            }
+            '"' | '\'' => {
+                i += 1;
+                let text = take_while(&mut i, &|ch| ch != c);
+                if i == chars.len() {
+                    return Err(format!("unterminated string {}{}", c, text));
+                }
+                i += 1;
+                tokens.push(Token::Text(text));
+            }
+            '#' => {
+                i += 1;
+                let tag = take_while(&mut i, &|ch| !ch.is_whitespace() && !matches!(ch, '(' | ')' | ','));
+                if tag.is_empty() {
+                    return Err("expected a tag after #".to_string());
+                }
+                tokens.push(Token::Tag(tag));
+            }
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) => {
+                i += 1;
+                let number = format!("{}{}", c, take_while(&mut i, &|ch| ch.is_ascii_digit() || ch == '.'));
+                tokens.push(Token::Number(number.parse().map_err(|_| format!("invalid number {}", number))?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let word = take_while(&mut i, &|ch| ch.is_alphanumeric() || matches!(ch, '_' | '.' | '-'));
+                tokens.push(Token::Word(word));
+            }
+            _ => {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                let op = ["!=", "<=", ">=", "==", "=", "<", ">", "-"]
+                    .into_iter()
+                    .find(|op| rest.starts_with(op))
+                    .ok_or_else(|| format!("unexpected '{}'", c))?;
+                i += op.chars().count();
+                tokens.push(Token::Op(if op == "==" { "=" } else { op }));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn field(&mut self, after: &str) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err(format!("expected a field name after {}", after)),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Close) => Ok(()),
+            _ => Err("expected )".to_string()),
+        }
+    }
+
+    fn source(&mut self) -> Result<Source, String> {
+        let mut left = self.source_and()?;
+        while self.keyword("OR") {
+            left = Source::Or(Box::new(left), Box::new(self.source_and()?));
+        }
+        Ok(left)
+    }
+
+    fn source_and(&mut self) -> Result<Source, String> {
+        let mut left = self.source_unary()?;
+        while self.keyword("AND") {
+            left = Source::And(Box::new(left), Box::new(self.source_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn source_unary(&mut self) -> Result<Source, String> {
+        if self.peek() == Some(&Token::Op("-")) {
+            self.position += 1;
+            return Ok(Source::Not(Box::new(self.source_unary()?)));
+        }
+        if self.keyword("NOT") {
+            return Ok(Source::Not(Box::new(self.source_unary()?)));
+        }
+        match self.next() {
+            Some(Token::Tag(tag)) => Ok(Source::Tag(tag)),
+            Some(Token::Text(folder)) => Ok(Source::Folder(folder.trim_matches('/').to_string())),
+            Some(Token::Open) => {
+                let source = self.source()?;
+                self.expect_close()?;
+                Ok(source)
+            }
+            _ => Err("expected #tag or \"folder\" after FROM".to_string()),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.keyword("OR") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while self.keyword("AND") {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        let left = self.operand()?;
+        match self.peek() {
+            Some(&Token::Op(op)) if op != "-" => {
+                self.position += 1;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(self.operand()?)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::from(n))),
+            Some(Token::Text(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Tag(tag)) => Ok(Expr::Literal(Value::String(tag))),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("true") => Ok(Expr::Literal(Value::Bool(true))),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("false") => Ok(Expr::Literal(Value::Bool(false))),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("null") => Ok(Expr::Literal(Value::Null)),
+            Some(Token::Word(word)) if self.peek() == Some(&Token::Open) => {
+                self.position += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::Close) {
+                    args.push(self.expr()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.position += 1;
+                        args.push(self.expr()?);
+                    }
+                }
+                self.expect_close()?;
+                Ok(Expr::Call(word.to_lowercase(), args))
+            }
+            Some(Token::Word(word)) => Ok(Expr::Field(word)),
+            Some(Token::Open) => {
+                let expr = self.expr()?;
+                self.expect_close()?;
+                Ok(expr)
+            }
+            Some(token) => Err(format!("unexpected {:?} in WHERE", token)),
+            None => Err("WHERE ends early".to_string()),
+        }
+    }
+}
+
+impl Query {
+    pub(crate) fn parse(text: &str) -> Result<Query, String> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+        let mut columns = Vec::new();
+        if parser.keyword("TABLE") {
+            columns.push(parser.field("TABLE")?);
+            while parser.peek() == Some(&Token::Comma) {
+                parser.position += 1;
+                columns.push(parser.field(",")?);
+            }
+        } else if !parser.keyword("LIST") {
+            return Err("a query starts with LIST or TABLE".to_string());
+        }
+
+        let source = if parser.keyword("FROM") { Some(parser.source()?) } else { None };
+        let filter = if parser.keyword("WHERE") { Some(parser.expr()?) } else { None };
+        let mut sort = Vec::new();
+        if parser.keyword("SORT") {
+            loop {
+                let field = parser.field("SORT")?;
+                let descending = parser.keyword("DESC");
+                if !descending {
+                    parser.keyword("ASC");
+                }
+                sort.push((field, descending));
+                if parser.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                parser.position += 1;
+            }
+        }
+        let limit = if parser.keyword("LIMIT") {
+            match parser.next() {
+                Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => Some(n as usize),
+                _ => return Err("expected a whole number after LIMIT".to_string()),
+            }
+        } else {
+            None
+        };
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {:?} after the query", token));
+        }
+        Ok(Query {
+            columns,
+            source,
+            filter,
+            sort,
+            limit,
+        })
+    }
+
+    /// The notes the query selects, sorted and limited, with the value of each column.
+    pub(crate) fn run(&self, notes: Vec<NoteFacts>) -> Vec<(String, Vec<Value>)> {
+        let mut selected: Vec<NoteFacts> = notes
+            .into_iter()
+            .filter(|note| self.source.as_ref().is_none_or(|source| source.matches(note)))
+            .filter(|note| self.filter.as_ref().is_none_or(|filter| truthy(&filter.eval(note))))
+            .collect();
+        selected.sort_by(|a, b| {
+            self.sort
+                .iter()
+                .map(|(field, descending)| {
+                    let order = compare_for_sort(&field_value(a, field), &field_value(b, field));
+                    if *descending { order.reverse() } else { order }
+                })
+                .find(|order| order.is_ne())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        selected.truncate(self.limit.unwrap_or(usize::MAX));
+        selected
+            .iter()
+            .map(|note| {
+                let values = self.columns.iter().map(|column| field_value(note, column)).collect();
+                (note.path.clone(), values)
+            })
+            .collect()
+    }
+}
+
+/// Parses a condition as written after WHERE.
+pub(crate) fn parse_condition(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+    };
+    let condition = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {:?} after the condition", token));
+    }
+    Ok(condition)
+}
+
+impl Source {
+    fn matches(&self, note: &NoteFacts) -> bool {
+        match self {
+            Source::Tag(tag) => note
+                .tags
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(tag) || t.to_lowercase().starts_with(&format!("{}/", tag.to_lowercase()))),
+            Source::Folder(folder) => {
+                folder.is_empty()
+                    || note.path.starts_with(&format!("{}/", folder))
+                    || note.path.strip_suffix(".md") == Some(folder.strip_suffix(".md").unwrap_or(folder))
+            }
+            Source::Not(source) => !source.matches(note),
+            Source::And(a, b) => a.matches(note) && b.matches(note),
+            Source::Or(a, b) => a.matches(note) || b.matches(note),
+        }
+    }
+}
+
+impl Expr {
+    pub(crate) fn holds(&self, note: &NoteFacts) -> bool {
+        truthy(&self.eval(note))
+    }
+
+    fn eval(&self, note: &NoteFacts) -> Value {
+        match self {
+            Expr::Field(field) => field_value(note, field),
+            Expr::Literal(value) => value.clone(),
+            Expr::Compare(a, op, b) => Value::Bool(compare(&a.eval(note), op, &b.eval(note))),
+            Expr::Call(name, args) => {
+                let args: Vec<Value> = args.iter().map(|arg| arg.eval(note)).collect();
+                match (name.as_str(), args.as_slice()) {
+                    ("contains", [Value::Array(items), needle]) => Value::Bool(items.iter().any(|item| loosely_equal(item, needle))),
+                    ("contains", [Value::String(text), Value::String(needle)]) => Value::Bool(text.contains(needle.as_str())),
+                    // Absent, or present without a value
+                    ("missing", [value]) => Value::Bool(match value {
+                        Value::Null => true,
+                        Value::String(text) => text.trim().is_empty(),
+                        Value::Array(items) => items.is_empty(),
+                        _ => false,
+                    }),
+                    _ => Value::Null,
+                }
+            }
+            Expr::Not(expr) => Value::Bool(!truthy(&expr.eval(note))),
+            Expr::And(a, b) => Value::Bool(truthy(&a.eval(note)) && truthy(&b.eval(note))),
+            Expr::Or(a, b) => Value::Bool(truthy(&a.eval(note)) || truthy(&b.eval(note))),
+        }
+    }
+}
+
+fn field_value(note: &NoteFacts, field: &str) -> Value {
+    let mut parts = field.split('.');
+    let first = parts.next().unwrap_or_default();
+    let mut value = note.fields.get(first);
+    for part in parts {
+        value = value.and_then(|v| v.get(part));
+    }
+    value.cloned().unwrap_or(Value::Null)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Equality that lets numbers written as strings in frontmatter match numbers, and
+/// compares tags without their `#`.
+fn loosely_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => {
+            s.parse::<f64>().ok() == n.as_f64()
+        }
+        (Value::String(x), Value::String(y)) => x.trim_start_matches('#') == y.trim_start_matches('#'),
+        _ => a == b,
+    }
+}
+
+fn order(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        (Value::String(s), Value::Number(n)) => s.parse::<f64>().ok()?.partial_cmp(&n.as_f64()?),
+        (Value::Number(n), Value::String(s)) => n.as_f64()?.partial_cmp(&s.parse::<f64>().ok()?),
+        _ => None,
+    }
+}
+
+fn compare(a: &Value, op: &str, b: &Value) -> bool {
+    match op {
+        // A list equals a value it contains, so `tags = "x"` works like Dataview
+        "=" | "!=" => {
+            let equal = match (a, b) {
+                (Value::Array(items), other) | (other, Value::Array(items)) if !other.is_array() => {
+                    items.iter().any(|item| loosely_equal(item, other))
+                }
+                _ => loosely_equal(a, b),
+            };
+            equal == (op == "=")
+        }
+        _ => match order(a, b) {
+            Some(ordering) => match op {
+                "<" => ordering.is_lt(),
+                "<=" => ordering.is_le(),
+                ">" => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            },
+            None => false,
+        },
+    }
+}
+
+/// Missing values sort last; values of different types by type.
+fn compare_for_sort(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Bool(_) => 0,
+        Value::Number(_) => 1,
+        Value::String(_) => 2,
+        Value::Array(_) | Value::Object(_) => 3,
+        Value::Null => 4,
+    };
+    order(a, b).unwrap_or_else(|| rank(a).cmp(&rank(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(path: &str, tags: &[&str], fields: Value) -> NoteFacts {
+        NoteFacts {
+            path: path.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            fields: fields.as_object().unwrap().clone(),
+        }
+    }
+
+    #[test]
+    fn queries_filter_sort_and_project() {
+        let notes = || {
+            vec![
+                note("a.md", &["project/alpha"], serde_json::json!({"words": 800, "status": "active"})),
+                note("b.md", &["project"], serde_json::json!({"words": 300, "status": "done"})),
+                note("c.md", &["project"], serde_json::json!({"words": 1200})),
+                note("Archive/d.md", &["project"], serde_json::json!({"words": 900})),
+                note("e.md", &["other"], serde_json::json!({"words": 5000})),
+            ]
+        };
+        let query = Query::parse("TABLE words, status FROM #project AND -\"Archive\" WHERE words > 500 SORT words DESC").unwrap();
+        let rows = query.run(notes());
+        assert_eq!(rows[0], ("c.md".to_string(), vec![Value::from(1200), Value::Null]));
+        assert_eq!(rows[1], ("a.md".to_string(), vec![Value::from(800), Value::from("active")]));
+        assert_eq!(rows.len(), 2);
+
+        let query = Query::parse("list where status != 'done' and not contains(tags, 'x') limit 1").unwrap();
+        assert_eq!(query.run(notes()).len(), 1);
+        assert_eq!(Query::parse("LIST WHERE missing(status)").unwrap().run(notes()).len(), 3);
+        assert!(Query::parse("LIST WHERE (words > 1").is_err());
+        assert!(Query::parse("SELECT *").is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Reports: OpenMetrics output and the standalone HTML report.
+
+use crate::config::{ScanOptions, WeekStart};
+use crate::dates::{days_since_epoch, format_date, today};
+use crate::graph::{link_graph, xml_escape};
+use crate::links::collect_all_links;
+use crate::output::Table;
+use crate::scan::{markdown_files, read_note, relative_path};
+use crate::stats::{StatsOutput, calculate_stats};
+use crate::tags::{collect_all_tags, tag_matches};
+use crate::tasks::extract_tasks;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportGrouping {
+    Tag,
+}
+
+#[derive(Serialize)]
+pub struct TagRollup {
+    pub tag: String,
+    pub notes: usize,
+    pub words: usize,
+    pub open_tasks: usize,
+    /// Newest modification date, as YYYY-MM-DD, of a note with the tag
+    pub last_activity: Option<String>,
+}
+
+pub fn render_openmetrics(stats: &StatsOutput, scan_duration: Duration) -> String {
+    let metrics = [
+        ("obsidian_notes", "Number of markdown notes in the vault", stats.total_notes as f64),
+        ("obsidian_tags", "Number of distinct tags in the vault", stats.total_tags as f64),
+        ("obsidian_links", "Number of wikilinks in the vault", stats.total_links as f64),
+        ("obsidian_broken_links", "Number of wikilinks pointing at missing notes", stats.broken_links as f64),
+        ("obsidian_orphaned_notes", "Number of notes with no incoming or outgoing links", stats.orphaned_notes as f64),
+        ("obsidian_archived_notes", "Number of notes in the archive folder", stats.archived_notes as f64),
+        ("obsidian_scan_duration_seconds", "Time taken to scan the vault", scan_duration.as_secs_f64()),
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in metrics {
+        out.push_str(&format!("# TYPE {} gauge\n", name));
+        out.push_str(&format!("# HELP {} {}\n", name, help));
+        out.push_str(&format!("{} {}\n", name, value));
+    }
+    out.push_str("# EOF");
+    out
+}
+
+/// How many entries the report's charts and top lists show.
+const REPORT_TOP: usize = 15;
+
+/// Horizontal bars, one per labelled count.
+fn svg_bar_chart(items: &[(String, usize)]) -> String {
+    let max = items.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let mut svg = format!(r#"<svg width="640" height="{}" role="img">"#, items.len() * 22);
+    for (i, (label, count)) in items.iter().enumerate() {
+        let y = i * 22;
+        let width = 380 * count / max;
+        svg.push_str(&format!(
+            r##"<text x="0" y="{}">{}</text><rect x="200" y="{}" width="{}" height="16" fill="#7c5cbf"/><text x="{}" y="{}">{}</text>"##,
+            y + 13,
+            xml_escape(label),
+            y + 2,
+            width,
+            206 + width,
+            y + 13,
+            count
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Cumulative note count by month, as an area chart.
+fn svg_growth_chart(months: &[(String, usize)]) -> String {
+    let (width, height) = (640.0, 180.0);
+    let max = months.last().map_or(1, |(_, total)| *total).max(1) as f64;
+    let step = width / months.len().saturating_sub(1).max(1) as f64;
+    let points: Vec<String> = months
+        .iter()
+        .enumerate()
+        .map(|(i, (_, total))| format!("{:.1},{:.1}", i as f64 * step, height - height * *total as f64 / max))
+        .collect();
+    let mut svg = format!(r#"<svg width="{}" height="{}" role="img">"#, width, height + 20.0);
+    svg.push_str(&format!(
+        r##"<polygon points="0,{h} {} {:.1},{h}" fill="#d9cff2"/><polyline points="{}" fill="none" stroke="#7c5cbf" stroke-width="2"/>"##,
+        points.join(" "),
+        (months.len().max(1) - 1) as f64 * step,
+        points.join(" "),
+        h = height
+    ));
+    if let (Some((first, _)), Some((last, total))) = (months.first(), months.last()) {
+        svg.push_str(&format!(
+            r#"<text x="0" y="{y}">{}</text><text x="{w}" y="{y}" text-anchor="end">{} ({} notes)</text>"#,
+            first,
+            last,
+            total,
+            y = height + 16.0,
+            w = width
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A year of days as a week-per-column grid, each column starting on `week_start`, shaded by
+/// how many notes were last changed that day.
+fn svg_heatmap(counts: &BTreeMap<i64, usize>, today: i64, week_start: WeekStart) -> String {
+    const SHADES: [&str; 5] = ["#ebedf0", "#d9cff2", "#b39ddb", "#8e6cc9", "#5e35b1"];
+    let start = week_start.week_of(today) - 52 * 7;
+    let max = counts.range(start..=today).map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let mut svg = r#"<svg width="660" height="96" role="img">"#.to_string();
+    for day in start..=today {
+        let count = counts.get(&day).copied().unwrap_or(0);
+        let shade = if count == 0 { 0 } else { (4 * count).div_ceil(max) };
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="10" height="10" fill="{}"><title>{}: {}</title></rect>"#,
+            (day - start) / 7 * 12,
+            (day - week_start.week_of(day)) * 12,
+            SHADES[shade],
+            format_date(day),
+            count
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn html_list(items: &[(String, usize)], unit: &str) -> String {
+    let rows: String = items
+        .iter()
+        .map(|(name, count)| format!("<li>{} <span class=\"count\">{} {}</span></li>", xml_escape(name), count, unit))
+        .collect();
+    format!("<ol>{}</ol>", rows)
+}
+
+/// A self-contained HTML dashboard of the vault: statistics, tag distribution, growth,
+/// an edit heatmap, top lists and broken links. Charts are inline SVG, so the file
+/// needs nothing else to display.
+/// Per-tag totals for notes tagged with a tag matching `pattern`, so `project/*` sums up
+/// each project, counting notes with tags nested deeper (`project/alpha/design`) towards
+/// it. Last activity is the newest modification date of a note in the group.
+pub fn tag_rollup(vault_path: &Path, pattern: &str, options: &ScanOptions) -> Vec<TagRollup> {
+    let depth = pattern.split('/').count();
+    let mut groups: BTreeMap<String, TagRollup> = BTreeMap::new();
+    for path in markdown_files(vault_path) {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let tags: BTreeSet<String> = options
+            .note_tags(&content)
+            .into_iter()
+            .map(|tag| tag.split('/').take(depth).collect::<Vec<_>>().join("/"))
+            .filter(|group| tag_matches(pattern, group, false))
+            .collect();
+        if tags.is_empty() {
+            continue;
+        }
+        let words = content.split_whitespace().count();
+        let open_tasks = extract_tasks(&content, &relative).iter().filter(|task| !task.done).count();
+        let modified = fs::metadata(&path).ok().and_then(|m| m.modified().ok()).map(|time| format_date(days_since_epoch(time)));
+        for tag in tags {
+            let group = groups.entry(tag.clone()).or_insert_with(|| TagRollup {
+                tag,
+                notes: 0,
+                words: 0,
+                open_tasks: 0,
+                last_activity: None,
+            });
+            group.notes += 1;
+            group.words += words;
+            group.open_tasks += open_tasks;
+            group.last_activity = group.last_activity.take().max(modified.clone());
+        }
+    }
+    groups.into_values().collect()
+}
+
+pub fn tag_rollup_table(groups: &[TagRollup]) -> Table {
+    let mut table = Table::new(&["Tag", "Notes", "Words", "Open tasks", "Last activity"]);
+    for group in groups {
+        let cells = vec![
+            group.tag.clone(),
+            group.notes.to_string(),
+            group.words.to_string(),
+            group.open_tasks.to_string(),
+            group.last_activity.clone().unwrap_or_default(),
+        ];
+        table.row(cells, None);
+    }
+    table
+}
+
+pub fn render_html_report(vault_path: &Path, week_start: WeekStart, options: &ScanOptions) -> Result<String, String> {
+    let stats = calculate_stats(vault_path, options)?;
+    let tags = collect_all_tags(vault_path, options)?;
+    let (links, _) = collect_all_links(vault_path, options)?;
+    let graph = link_graph(vault_path, false, options);
+    let today = today();
+
+    let mut top_tags: Vec<(String, usize)> = tags.into_iter().collect();
+    top_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_tags.truncate(REPORT_TOP);
+
+    // Creation times aren't available everywhere; a note's modification time stands in
+    let mut created_by_month: BTreeMap<String, usize> = BTreeMap::new();
+    let mut modified_by_day: BTreeMap<i64, usize> = BTreeMap::new();
+    for note in graph.nodes.keys() {
+        let Ok(metadata) = fs::metadata(vault_path.join(note)) else {
+            continue;
+        };
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        let created = metadata.created().unwrap_or(modified);
+        *created_by_month.entry(format_date(days_since_epoch(created))[..7].to_string()).or_insert(0) += 1;
+        *modified_by_day.entry(days_since_epoch(modified)).or_insert(0) += 1;
+    }
+    let growth: Vec<(String, usize)> = created_by_month
+        .into_iter()
+        .scan(0, |total, (month, count)| {
+            *total += count;
+            Some((month, *total))
+        })
+        .collect();
+
+    let mut inbound: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (source, target, _) in graph.edges.keys() {
+        if source != target {
+            inbound.entry(target).or_default().insert(source);
+        }
+    }
+    let mut most_linked: Vec<(String, usize)> = inbound.into_iter().map(|(note, sources)| (note.to_string(), sources.len())).collect();
+    most_linked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    most_linked.truncate(REPORT_TOP);
+    let mut longest: Vec<(String, usize)> = graph.nodes.iter().map(|(note, node)| (note.clone(), node.words)).collect();
+    longest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    longest.truncate(REPORT_TOP);
+
+    let broken_rows: String = links
+        .iter()
+        .filter(|l| !l.exists && !l.placeholder && !options.skips_archived(&l.source))
+        .map(|l| format!("<tr><td>{}</td><td>[[{}]]</td></tr>", xml_escape(&l.source), xml_escape(&l.target)))
+        .collect();
+    let cards: String = [
+        ("Notes", stats.total_notes),
+        ("Tags", stats.total_tags),
+        ("Links", stats.total_links),
+        ("Broken links", stats.broken_links),
+        ("Orphans", stats.orphaned_notes),
+        ("Archived", stats.archived_notes),
+    ]
+    .iter()
+    .map(|(label, value)| format!("<div class=\"card\"><b>{}</b>{}</div>", value, label))
+    .collect();
+    let vault_name = vault_path.canonicalize().ok().and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+    let title = xml_escape(&format!("{} vault report", vault_name.as_deref().unwrap_or("Obsidian")));
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 720px; margin: 2em auto; color: #222; }}
+.cards {{ display: flex; flex-wrap: wrap; gap: 8px; }}
+.card {{ border: 1px solid #ddd; border-radius: 6px; padding: 8px 14px; min-width: 80px; }}
+.card b {{ display: block; font-size: 1.6em; }}
+svg text {{ font-size: 12px; fill: #444; }}
+.count {{ color: #777; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td, th {{ border-bottom: 1px solid #eee; padding: 4px; text-align: left; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<p>Generated {date}</p>
+<div class="cards">{cards}</div>
+<h2>Tags</h2>
+{tag_chart}
+<h2>Growth</h2>
+{growth_chart}
+<h2>Edits over the last year</h2>
+{heatmap}
+<h2>Most linked notes</h2>
+{most_linked}
+<h2>Longest notes</h2>
+{longest}
+<h2>Broken links</h2>
+<table><tr><th>Note</th><th>Link</th></tr>{broken_rows}</table>
+</body>
+</html>
+"#,
+        date = format_date(today),
+        tag_chart = svg_bar_chart(&top_tags),
+        growth_chart = svg_growth_chart(&growth),
+        heatmap = svg_heatmap(&modified_by_day, today, week_start),
+        most_linked = html_list(&most_linked, "backlinks"),
+        longest = html_list(&longest, "words"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::days_from_civil;
+
+    #[test]
+    fn report_charts_escape_labels_and_place_weekdays() {
+        let chart = svg_bar_chart(&[("a<b".to_string(), 4), ("c".to_string(), 2)]);
+        assert!(chart.contains(">a&lt;b</text>"));
+        assert!(chart.contains(r#"width="380""#) && chart.contains(r#"width="190""#));
+        let today = days_from_civil(2026, 10, 15);
+        // 2026-10-15 is a Thursday: the fourth row when weeks start on Monday, the fifth on Sunday
+        let heatmap = svg_heatmap(&BTreeMap::from([(today, 3)]), today, WeekStart::Monday);
+        assert!(heatmap.contains(r##"<rect x="624" y="36" width="10" height="10" fill="#5e35b1"><title>2026-10-15: 3</title>"##));
+        let heatmap = svg_heatmap(&BTreeMap::from([(today, 3)]), today, WeekStart::Sunday);
+        assert!(heatmap.contains(r##"<rect x="624" y="48" width="10" height="10" fill="#5e35b1"><title>2026-10-15: 3</title>"##));
+    }
+
+    #[test]
+    fn openmetrics_lists_each_gauge_and_ends_with_eof() {
+        let stats = StatsOutput { total_notes: 12, total_tags: 3, total_links: 40, broken_links: 2, orphaned_notes: 1, archived_notes: 0 };
+        let metrics = render_openmetrics(&stats, Duration::from_millis(250));
+        assert!(metrics.contains("# TYPE obsidian_notes gauge\n# HELP obsidian_notes Number of markdown notes in the vault\nobsidian_notes 12\n"));
+        assert!(metrics.contains("\nobsidian_broken_links 2\n"));
+        assert!(metrics.contains("\nobsidian_scan_duration_seconds 0.25\n"));
+        assert!(metrics.ends_with("\n# EOF"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Walking a vault: which notes are visible, and how link targets resolve to them.
+
+use crate::config::ScanOptions;
+use crate::links::relative_to_vault;
+use crate::parse::{self, Frontmatter, TagSyntax, extract_frontmatter, extract_inline_tags};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+pub(crate) fn is_private_note(content: &str, frontmatter: &Frontmatter, syntax: &TagSyntax) -> bool {
+    frontmatter.flag("private")
+        || frontmatter.tags().iter().any(|t| t == "private")
+        || extract_inline_tags(content, syntax).iter().any(|t| t == "private")
+}
+
+/// Whether the note's frontmatter sets `key` to true.
+pub(crate) fn is_ignored_note(frontmatter: &Frontmatter, key: &str) -> bool {
+    !key.is_empty() && frontmatter.flag(key)
+}
+
+/// Reads a note, returning None when it can't be read, is ignored, or is private and
+/// the scan doesn't include private notes.
+pub(crate) fn read_note(path: &Path, options: &ScanOptions) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    if options.hides(&content) {
+        return None;
+    }
+    Some(content)
+}
+
+/// Vault-relative paths of the notes a scan with these options leaves out.
+pub(crate) fn hidden_notes(vault_path: &Path, options: &ScanOptions) -> HashSet<String> {
+    markdown_files(vault_path)
+        .into_iter()
+        .filter(|path| fs::read_to_string(path).is_ok() && read_note(path, options).is_none())
+        .map(|path| relative_path(vault_path, &path))
+        .collect()
+}
+
+/// Every file and folder in the vault, following symlinks. Entries are visited in file
+/// name order, so output built up while walking is the same on every run and filesystem.
+pub(crate) fn walk_vault(vault_path: &Path) -> WalkDir {
+    WalkDir::new(vault_path).follow_links(true).sort_by_file_name()
+}
+
+/// Skips .obsidian, .trash, .git and other hidden folders below the vault root.
+pub(crate) fn is_visible_entry(entry: &DirEntry) -> bool {
+    entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Every note in the vault outside hidden folders, in walk order.
+pub(crate) fn markdown_files(vault_path: &Path) -> Vec<PathBuf> {
+    walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md"))
+        .collect()
+}
+
+pub(crate) fn relative_path(vault_path: &Path, path: &Path) -> String {
+    path.strip_prefix(vault_path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+pub(crate) fn note_stem(note: &str) -> &str {
+    let name = note.rsplit('/').next().unwrap_or(note);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+pub(crate) fn normalize_path(_vault_path: &Path, note_path: &str) -> String {
+    // Remove .md extension if present for comparison
+    let normalized = note_path.strip_suffix(".md").unwrap_or(note_path);
+    normalized.to_string()
+}
+
+/// Resolves link targets to note paths without scanning every note per link. Each note is
+/// indexed under its path without `.md` and under every shorter suffix starting after a `/`,
+/// so `[[c]]` and `[[b/c]]` both find `a/b/c.md`. A name shared by several notes resolves to
+/// the least nested one. Notes can also be found by their frontmatter `aliases`, as in
+/// Obsidian, when no note has the name.
+pub(crate) struct NoteResolver {
+    by_suffix: HashMap<String, String>,
+    by_alias: HashMap<String, String>,
+}
+
+impl NoteResolver {
+    pub(crate) fn new<S: AsRef<str>>(notes: impl IntoIterator<Item = S>) -> Self {
+        let mut by_suffix: HashMap<String, String> = HashMap::new();
+        for note in notes {
+            let note = note.as_ref();
+            let normalized = note.strip_suffix(".md").unwrap_or(note);
+            let suffixes = std::iter::once(0).chain(normalized.match_indices('/').map(|(i, _)| i + 1));
+            for start in suffixes {
+                let key = &normalized[start..];
+                let depth = |path: &str| path.matches('/').count();
+                match by_suffix.get_mut(key) {
+                    Some(existing) if (depth(existing), existing.as_str()) <= (depth(note), note) => {}
+                    Some(existing) => *existing = note.to_string(),
+                    None => {
+                        by_suffix.insert(key.to_string(), note.to_string());
+                    }
+                }
+            }
+        }
+        NoteResolver {
+            by_suffix,
+            by_alias: HashMap::new(),
+        }
+    }
+
+    /// Lets links find `note` by the aliases in its frontmatter. An alias shared by several
+    /// notes finds the first one added.
+    pub(crate) fn add_aliases(&mut self, note: &str, aliases: Vec<String>) {
+        for alias in aliases {
+            self.by_alias.entry(alias).or_insert_with(|| note.to_string());
+        }
+    }
+
+    /// Reads every note from the vault for its aliases, in path order.
+    pub(crate) fn read_aliases(mut self, vault_path: &Path) -> Self {
+        let notes: BTreeSet<String> = self.by_suffix.values().cloned().collect();
+        for note in notes {
+            if let Ok(content) = fs::read_to_string(vault_path.join(&note)) {
+                self.add_aliases(&note, note_aliases(&content));
+            }
+        }
+        self
+    }
+
+    /// The note at exactly this vault-relative path, with or without `.md`.
+    pub(crate) fn resolve_path(&self, path: &str) -> Option<&String> {
+        let path = path.strip_suffix(".md").unwrap_or(path);
+        self.by_suffix.get(path).filter(|note| note.strip_suffix(".md") == Some(path))
+    }
+
+    pub(crate) fn resolve(&self, link: &str) -> Option<&String> {
+        self.by_suffix
+            .get(link.strip_suffix(".md").unwrap_or(link))
+            .or_else(|| self.by_alias.get(link))
+    }
+}
+
+/// The note a Markdown link destination in `source` points to, like `Plan.md`,
+/// `../Projects/My%20Plan.md#Goals` or `/Projects/Plan`. Paths are tried relative to the
+/// linking note's folder first, then as links by name or path. URLs point to no note.
+pub(crate) fn resolve_markdown_link(source: &str, destination: &str, resolver: &NoteResolver) -> Option<String> {
+    let destination = destination.split('#').next().unwrap_or_default();
+    if destination.is_empty() || destination.contains("://") || destination.starts_with("mailto:") {
+        return None;
+    }
+    let destination = parse::percent_decode(destination);
+    let folder = match destination.starts_with('/') {
+        true => "",
+        false => source.rsplit_once('/').map_or("", |(folder, _)| folder),
+    };
+    resolver
+        .resolve_path(&relative_to_vault(folder, &destination)?)
+        .or_else(|| resolver.resolve(destination.trim_start_matches("./").trim_start_matches('/')))
+        .cloned()
+}
+
+/// The note's frontmatter `aliases` (or legacy `alias`), other names links can use for it.
+pub(crate) fn note_aliases(content: &str) -> Vec<String> {
+    Frontmatter::of(content).list("aliases", "alias", &[','])
+}
+
+/// Resolves a single link; build a [`NoteResolver`] once when resolving many. Notes are
+/// only read for their aliases when no note has the name.
+pub(crate) fn find_note_path(vault_path: &Path, link: &str, all_notes: &HashSet<String>) -> Option<String> {
+    let resolver = NoteResolver::new(all_notes);
+    match resolver.resolve(link) {
+        Some(path) => Some(path.clone()),
+        None => resolver.read_aliases(vault_path).resolve(link).cloned(),
+    }
+}
+
+/// Where `content` starts after its frontmatter, in bytes.
+pub(crate) fn body_start(content: &str) -> usize {
+    extract_frontmatter(content).map_or(0, |frontmatter| frontmatter.len() + 9)
+}
+
+pub(crate) fn attachment_files(vault_path: &Path) -> Vec<PathBuf> {
+    walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().is_none_or(|ext| ext != "md"))
+        .collect()
+}
+
+/// Every file in the vault outside hidden folders like .obsidian and .trash, vault-relative.
+pub(crate) fn vault_files(vault_path: &Path) -> Vec<String> {
+    walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| relative_path(vault_path, e.path()))
+        .collect()
+}
+
+pub(crate) fn join_path(folder: &str, name: &str) -> String {
+    if folder.is_empty() { name.to_string() } else { format!("{}/{}", folder, name) }
+}
+
+/// Keeps the first of each value, comparing with `key` (e.g. lowercase for tags).
+pub(crate) fn dedup_by_key(values: impl IntoIterator<Item = String>, key: impl Fn(&str) -> String) -> Vec<String> {
+    let mut seen = HashSet::new();
+    values.into_iter().filter(|value| seen.insert(key(value))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolver_matches_path_suffixes_and_prefers_shallow_notes() {
+        let resolver = NoteResolver::new(["a/b/Plan.md", "Plan.md", "a/Other.md"]);
+        assert_eq!(resolver.resolve("Plan").map(String::as_str), Some("Plan.md"));
+        assert_eq!(resolver.resolve("b/Plan.md").map(String::as_str), Some("a/b/Plan.md"));
+        assert_eq!(resolver.resolve("Other").map(String::as_str), Some("a/Other.md"));
+        assert_eq!(resolver.resolve("b/Other"), None);
+        assert_eq!(resolver.resolve("lan"), None);
+    }
+
+    #[test]
+    fn markdown_links_resolve_relative_to_the_note() {
+        let resolver = NoteResolver::new(["Projects/My Plan.md", "Projects/Sub/Notes.md", "Home.md"]);
+        let resolve = |source, destination| resolve_markdown_link(source, destination, &resolver);
+        assert_eq!(resolve("Projects/Sub/Notes.md", "../My%20Plan.md#Goals").as_deref(), Some("Projects/My Plan.md"));
+        assert_eq!(resolve("Projects/My Plan.md", "Sub/Notes.md").as_deref(), Some("Projects/Sub/Notes.md"));
+        assert_eq!(resolve("Projects/My Plan.md", "/Home.md").as_deref(), Some("Home.md"));
+        assert_eq!(resolve("Projects/My Plan.md", "Home.md").as_deref(), Some("Home.md"));
+        assert_eq!(resolve("Home.md", "../../Home.md"), None);
+        assert_eq!(resolve("Home.md", "https://example.com/Home.md"), None);
+    }
+
+    #[test]
+    fn resolver_falls_back_to_aliases() {
+        let mut resolver = NoteResolver::new(["a/Plan.md", "Other.md"]);
+        resolver.add_aliases("a/Plan.md", note_aliases("---\naliases: [Big Plan, Other]\n---\n"));
+        resolver.add_aliases("Other.md", note_aliases("---\nalias: Big Plan\n---\n"));
+        assert_eq!(resolver.resolve("Big Plan").map(String::as_str), Some("a/Plan.md"));
+        assert_eq!(resolver.resolve("Other").map(String::as_str), Some("Other.md"));
+        assert_eq!(resolver.resolve("big plan"), None);
+    }
+
+    #[test]
+    fn ignore_key_hides_notes_even_with_private_included() {
+        let options = ScanOptions {
+            include_private: true,
+            ignore_key: "obsidian-cli-ignore".to_string(),
+            ..ScanOptions::default()
+        };
+        assert!(options.hides("---\nobsidian-cli-ignore: true\n---\nBody"));
+        assert!(!options.hides("---\nobsidian-cli-ignore-links: true\n---\nBody"));
+        assert!(!options.hides("obsidian-cli-ignore: true"));
+        assert!(!options.hides("---\nprivate: true\n---\nBody"));
+        assert!(!ScanOptions::default().hides("---\n: true\n---\n"));
+    }
+}
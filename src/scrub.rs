@@ -0,0 +1,286 @@
+//! Anonymising a vault while keeping its structure, for sharing bug reports.
+
+use crate::config::{Config, ScanOptions};
+use crate::parse::{TagSyntax, extract_frontmatter};
+use crate::properties::is_frontmatter_key_line;
+use crate::scan::{attachment_files, markdown_files, read_note, relative_path};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{DefaultHasher, Hasher};
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Serialize)]
+pub struct ScrubOutput {
+    out: String,
+    notes: usize,
+    /// Attachments, written as empty files under their scrubbed names
+    attachments: usize,
+    /// Whether `.obsidian-cli.toml` was copied as it is, so the copy parses the same way
+    config_copied: bool,
+}
+
+const LOREM: &str = "loremipsumdolorsitametconsecteturadipiscingelitseddoeiusmodtemporincididuntutlaboreetdoloremagnaaliqua";
+
+fn scrub_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+/// A file or folder name replaced by a hash of it. The same name always gets the same hash,
+/// so links written with it still find it.
+fn scrub_component(name: &str) -> String {
+    format!("n{:012x}", scrub_hash(name) & 0xffff_ffff_ffff)
+}
+
+/// A word replaced by lorem ipsum of the same length, keeping its capitals, with digits
+/// replaced by digits. The same word always gets the same replacement, so `[[Note#Heading]]` still
+/// finds its heading.
+fn scrub_word(word: &str) -> String {
+    let hash = scrub_hash(word) as usize;
+    let lorem = LOREM.as_bytes();
+    word.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if c.is_numeric() {
+                // Never 0, so numbers don't gain a leading zero and turn into text
+                char::from(b'1' + ((hash / 7 + i * 3) % 9) as u8)
+            } else {
+                let letter = char::from(lorem[(hash + i) % lorem.len()]);
+                if c.is_uppercase() { letter.to_ascii_uppercase() } else { letter }
+            }
+        })
+        .collect()
+}
+
+/// Text with every word scrubbed and everything between words kept.
+fn scrub_words(text: &str) -> String {
+    static WORD_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[\p{L}\p{N}]+").unwrap());
+    WORD_REGEX.replace_all(text, |captures: &regex::Captures| scrub_word(&captures[0])).into_owned()
+}
+
+/// Replaces a vault's names and text while keeping what the parsers look at: Markdown
+/// punctuation, wikilinks (pointing at the scrubbed names), tags and frontmatter keys.
+struct Scrubber<'a> {
+    /// File names of the vault's attachments, whose extensions are kept
+    attachments: HashSet<String>,
+    /// Folders the config names, kept so the copy's folders mean the same
+    kept_folders: HashSet<&'a str>,
+    /// Daily note names, kept since they're dates rather than titles
+    daily: Regex,
+    placeholder_prefix: &'a str,
+    syntax: &'a TagSyntax,
+}
+
+impl Scrubber<'_> {
+    /// A file name: the stem is hashed, the extension of notes and attachments kept.
+    fn name(&self, name: &str, is_file: bool) -> String {
+        let stem = |stem: &str| {
+            if self.kept_folders.contains(stem) || self.daily.is_match(stem) {
+                stem.to_string()
+            } else {
+                scrub_component(stem)
+            }
+        };
+        if let Some(note) = name.strip_suffix(".md") {
+            return format!("{}.md", stem(note));
+        }
+        match name.rsplit_once('.') {
+            Some((base, extension)) if is_file || self.attachments.contains(name) => format!("{}.{}", stem(base), extension),
+            _ => stem(name),
+        }
+    }
+
+    /// A vault-relative path, or a link target written as one.
+    fn path(&self, path: &str, is_file: bool) -> String {
+        let components: Vec<&str> = path.split('/').collect();
+        let last = components.len() - 1;
+        components
+            .iter()
+            .enumerate()
+            .map(|(i, component)| if component.is_empty() { String::new() } else { self.name(component, is_file && i == last) })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Text with every word scrubbed except inline tags, and wikilinks rewritten to the
+    /// scrubbed names. In frontmatter values the YAML words that change a value's type are kept.
+    fn text(&self, text: &str, yaml: bool) -> String {
+        let mut scrubbed = String::with_capacity(text.len());
+        let mut rest = text;
+        let mut previous: Option<char> = None;
+        while let Some(c) = rest.chars().next() {
+            if let Some(inner) = rest.strip_prefix("[[")
+                && let Some(end) = inner.find("]]")
+                && !inner[..end].contains('\n')
+            {
+                let link = &inner[..end];
+                let split = link.find(['#', '|']).unwrap_or(link.len());
+                scrubbed.push_str("[[");
+                if !self.placeholder_prefix.is_empty()
+                    && let Some(title) = link[..split].strip_prefix(self.placeholder_prefix)
+                {
+                    scrubbed.push_str(self.placeholder_prefix);
+                    scrubbed.push_str(&self.text(title, false));
+                } else if !link[..split].is_empty() {
+                    scrubbed.push_str(&self.path(&link[..split], false));
+                }
+                // The heading and alias hold no tags, only words
+                scrubbed.push_str(&scrub_words(&link[split..]));
+                scrubbed.push_str("]]");
+                rest = &inner[end + 2..];
+                previous = Some(']');
+                continue;
+            }
+            if c == '#'
+                && previous.is_none_or(char::is_whitespace)
+                && rest[1..].chars().next().is_some_and(|next| self.syntax.is_tag_char(next))
+            {
+                let end = rest[1..].find(|c: char| !self.syntax.is_tag_char(c)).map_or(rest.len(), |i| i + 1);
+                scrubbed.push_str(&rest[..end]);
+                rest = &rest[end..];
+                previous = Some('#');
+                continue;
+            }
+            if c.is_alphanumeric() {
+                let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+                let word = &rest[..end];
+                if yaml && matches!(word, "true" | "false" | "yes" | "no" | "null") {
+                    scrubbed.push_str(word);
+                } else {
+                    scrubbed.push_str(&scrub_word(word));
+                }
+                rest = &rest[end..];
+                previous = Some('a');
+                continue;
+            }
+            scrubbed.push(c);
+            rest = &rest[c.len_utf8()..];
+            previous = Some(c);
+        }
+        scrubbed
+    }
+
+    /// A note's content. Frontmatter keeps its keys and its tags; everything else is scrubbed.
+    fn note(&self, content: &str) -> String {
+        static KEY_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^(\s*(?:-\s+)?(?:"[^"]*"|'[^']*'|[^\s:#'"\-][^:]*?):)(\s.*|)$"#).unwrap());
+        let Some(frontmatter) = extract_frontmatter(content) else {
+            return self.text(content, false);
+        };
+        let body = &content[frontmatter.len() + 9..];
+
+        let mut lines = Vec::new();
+        let mut in_tags = false;
+        for line in frontmatter.lines() {
+            if !line.starts_with([' ', '\t', '-']) {
+                in_tags = is_frontmatter_key_line(line, |key| matches!(key, "tags" | "tag"));
+            }
+            if in_tags {
+                lines.push(line.to_string());
+            } else if let Some(captures) = KEY_REGEX.captures(line) {
+                lines.push(format!("{}{}", &captures[1], self.text(&captures[2], true)));
+            } else {
+                lines.push(self.text(line, true));
+            }
+        }
+        format!("---\n{}\n---\n{}", lines.join("\n"), self.text(body, false))
+    }
+}
+
+/// Copies the vault to `out` with every name hashed and every word replaced by lorem ipsum
+/// of the same length, keeping the structure the parsers see: folders, Markdown syntax,
+/// links (which still resolve), tags, frontmatter keys, and the names of daily notes and of
+/// folders the config refers to. Attachments become empty files. The copy can be shared to
+/// reproduce parsing bugs without sharing what the notes say.
+pub fn scrub_vault(vault_path: &Path, out: &Path, config: &Config, options: &ScanOptions) -> Result<ScrubOutput, String> {
+    if out.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("{} already exists and is not empty", out.display()));
+    }
+    let absolute = |path: &Path| std::path::absolute(path).map_err(|e| format!("can't resolve {}: {}", path.display(), e));
+    if absolute(out)?.starts_with(absolute(vault_path)?) {
+        return Err("the scrubbed copy can't be written inside the vault".to_string());
+    }
+
+    let attachments = attachment_files(vault_path);
+    let scrubber = Scrubber {
+        attachments: attachments
+            .iter()
+            .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .collect(),
+        kept_folders: [&config.templates_folder, &config.archive_folder]
+            .into_iter()
+            .chain(&config.naming.date_prefix_folders)
+            .map(String::as_str)
+            .filter(|folder| !folder.is_empty())
+            .collect(),
+        daily: Regex::new(&config.daily_note_pattern).map_err(|e| format!("invalid daily_note_pattern: {}", e))?,
+        placeholder_prefix: &options.placeholder_prefix,
+        syntax: &options.tag_syntax,
+    };
+    let create = |relative: &str, content: &[u8]| -> Result<(), String> {
+        let path = out.join(scrubber.path(relative, true));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&path, content).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    };
+
+    fs::create_dir_all(out.join(".obsidian")).map_err(|e| format!("failed to create {}: {}", out.display(), e))?;
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut notes = 0;
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        create(&relative_path(vault_path, &path), scrubber.note(&content).as_bytes())?;
+        notes += 1;
+    }
+    for path in &attachments {
+        create(&relative_path(vault_path, path), &[])?;
+    }
+
+    let config = vault_path.join(".obsidian-cli.toml");
+    let config_copied = config.is_file();
+    if config_copied {
+        fs::copy(&config, out.join(".obsidian-cli.toml")).map_err(|e| format!("failed to copy {}: {}", config.display(), e))?;
+    }
+
+    Ok(ScrubOutput {
+        out: out.display().to_string(),
+        notes,
+        attachments: attachments.len(),
+        config_copied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubbing_keeps_links_tags_and_keys() {
+        let syntax = TagSyntax::default();
+        let scrubber = Scrubber {
+            attachments: HashSet::from(["photo.png".to_string()]),
+            kept_folders: HashSet::from(["Archive"]),
+            daily: Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap(),
+            placeholder_prefix: "TODO:",
+            syntax: &syntax,
+        };
+        let note = "---\ntags: [secret-project]\nprivate: true\nowner: Alice\n---\n# Plans\nSee [[Work/Plan#Plans|the plan]], ![[photo.png]], [[TODO: idea]] and [[2024-01-02]] #work.\n";
+        let scrubbed = scrubber.note(note);
+        let plan = scrubber.path("Work/Plan.md", true);
+        assert!(scrubbed.starts_with("---\ntags: [secret-project]\nprivate: true\nowner: "));
+        assert!(scrubbed.contains(&format!("[[{}#{}|", plan.trim_end_matches(".md"), scrub_word("Plans"))));
+        assert!(scrubbed.contains(&format!("\n# {}\n", scrub_word("Plans"))));
+        assert!(scrubbed.contains(&format!("![[{}.png]]", scrub_component("photo"))));
+        assert!(scrubbed.contains("[[TODO: ") && scrubbed.contains("[[2024-01-02]] #work."));
+        assert!(!scrubbed.contains("Alice") && !scrubbed.contains("Work") && !scrubbed.contains("plan"));
+        assert_eq!(scrubbed.chars().count() - note.chars().count(), scrubbed.len() - note.len());
+        assert_eq!(scrubber.path("Archive/Old.md", true), format!("Archive/{}.md", scrub_component("Old")));
+    }
+}
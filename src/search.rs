@@ -0,0 +1,532 @@
+//! Full-text search, ranked search, fuzzy note lookup and queries.
+
+use crate::config::ScanOptions;
+use crate::dates::modified_timestamp;
+use crate::parse::{self, Frontmatter, extract_links_from_file};
+use crate::query;
+use crate::scan::{NoteResolver, body_start, dedup_by_key, markdown_files, note_stem, read_note, relative_path};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize)]
+pub struct SearchMatch {
+    pub(crate) path: String,
+    pub(crate) line: usize,
+    /// Character offset of the match in its line, starting at 1
+    pub(crate) column: usize,
+    /// The line around the match, cut to a few words either side
+    pub(crate) snippet: String,
+    /// Character range of the match within `snippet`
+    pub(crate) highlight: [usize; 2],
+    /// What each capture group of a `--regex` search matched, in order (null when a group
+    /// didn't take part in the match)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    captures: Vec<Option<String>>,
+}
+
+#[derive(Serialize)]
+pub struct RankedNote {
+    pub(crate) path: String,
+    /// BM25 relevance to the query's words, only comparable within one search
+    pub(crate) score: f64,
+    /// Every line with one of the query's words
+    pub(crate) matches: Vec<SearchMatch>,
+}
+
+#[derive(Serialize)]
+pub struct RankedSearchOutput {
+    pub query: String,
+    pub results: Vec<RankedNote>,
+}
+
+#[derive(Serialize)]
+pub struct FoundNote {
+    pub(crate) path: String,
+    /// The note name or alias that matched best
+    pub(crate) matched: String,
+    pub(crate) score: i64,
+}
+
+#[derive(Serialize)]
+pub struct FindOutput {
+    pub query: String,
+    pub notes: Vec<FoundNote>,
+}
+
+#[derive(Serialize)]
+pub struct QueryRow {
+    path: String,
+    /// The TABLE columns, by name
+    #[serde(flatten)]
+    values: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+pub struct NoteTable {
+    pub path: String,
+    /// Line of the table's header row
+    pub line: usize,
+    pub columns: Vec<String>,
+    /// Each row's cells by column
+    pub rows: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Serialize)]
+pub struct TablesOutput {
+    pub tables: Vec<NoteTable>,
+}
+
+#[derive(Serialize)]
+pub struct QueryOutput {
+    pub columns: Vec<String>,
+    pub results: Vec<QueryRow>,
+}
+
+#[derive(Serialize)]
+pub struct SearchOutput {
+    pub query: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Characters of context kept either side of a search match in its snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Every match of `pattern` in a note's body, line by line.
+fn note_matches(path: &str, content: &str, pattern: &Regex) -> Vec<SearchMatch> {
+    let body_start = body_start(content);
+    let first_line = content[..body_start].matches('\n').count() + 1;
+    let mut matches = Vec::new();
+    for (i, line) in content[body_start..].lines().enumerate() {
+        for groups in pattern.captures_iter(line) {
+            let found = groups.get(0).expect("group 0 is the whole match");
+            // Patterns like `x*` also match nothing between every character
+            if found.is_empty() {
+                continue;
+            }
+            let captures = groups.iter().skip(1).map(|group| group.map(|g| g.as_str().to_string())).collect();
+            let before: Vec<char> = line[..found.start()].chars().collect();
+            let after: Vec<char> = line[found.end()..].chars().collect();
+            let lead = before.len().saturating_sub(SNIPPET_CONTEXT);
+            let trail = after.len().min(SNIPPET_CONTEXT);
+            let mut snippet = String::new();
+            if lead > 0 {
+                snippet.push('…');
+            }
+            snippet.extend(&before[lead..]);
+            let start = snippet.chars().count();
+            snippet.push_str(found.as_str());
+            let end = snippet.chars().count();
+            snippet.extend(&after[..trail]);
+            if trail < after.len() {
+                snippet.push('…');
+            }
+            matches.push(SearchMatch {
+                path: path.to_string(),
+                line: first_line + i,
+                column: before.len() + 1,
+                snippet,
+                highlight: [start, end],
+                captures,
+            });
+        }
+    }
+    matches
+}
+
+/// Every case-insensitive occurrence of `query` in note bodies (frontmatter is skipped),
+/// sorted by path and position. With `regex`, `query` is a case-sensitive regular
+/// expression matched within each line, and its capture groups are returned too.
+pub fn search_notes(
+    vault_path: &Path,
+    query: &str,
+    regex: bool,
+    options: &ScanOptions,
+) -> Result<Vec<SearchMatch>, String> {
+    if query.is_empty() {
+        return Err("the search query is empty".to_string());
+    }
+    let pattern = match regex {
+        true => Regex::new(query).map_err(|e| format!("invalid regex: {}", e))?,
+        false => Regex::new(&format!("(?i){}", regex::escape(query))).map_err(|e| e.to_string())?,
+    };
+
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut matches = Vec::new();
+    for path in paths {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        matches.extend(note_matches(&relative_path(vault_path, &path), &content, &pattern));
+    }
+    Ok(matches)
+}
+
+/// What queries see of a note: its frontmatter, overlaid with the computed fields.
+pub(crate) fn note_facts(path: &Path, relative: String, content: &str, options: &ScanOptions) -> query::NoteFacts {
+    let mut fields = Frontmatter::of(content).to_json();
+    let frontmatter = serde_json::Value::Object(fields.clone());
+    let tags = dedup_by_key(options.note_tags(content), str::to_string);
+    let metadata = fs::metadata(path).ok();
+    let computed = [
+        // The properties as written, even those a computed field shadows
+        ("frontmatter", frontmatter),
+        ("path", relative.clone().into()),
+        ("name", note_stem(&relative).into()),
+        ("folder", relative.rsplit_once('/').map_or("", |(folder, _)| folder).into()),
+        ("words", content.split_whitespace().count().into()),
+        ("links", extract_links_from_file(content).len().into()),
+        ("tags", tags.clone().into()),
+        ("tag", tags.clone().into()),
+        ("size", metadata.as_ref().map_or(0, |m| m.len()).into()),
+        ("modified", metadata.as_ref().and_then(modified_timestamp).into()),
+    ];
+    for (name, value) in computed {
+        fields.insert(name.to_string(), value);
+    }
+    query::NoteFacts { path: relative, tags, fields }
+}
+
+/// Runs a `LIST`/`TABLE` query against the notes' frontmatter and these computed fields:
+/// `path`, `name`, `folder`, `words`, `links`, `tags` (or `tag`), `size`, `modified` and
+/// `frontmatter`, the properties as written.
+pub fn run_query(vault_path: &Path, text: &str, options: &ScanOptions) -> Result<QueryOutput, String> {
+    let query = query::Query::parse(text)?;
+    let mut notes = Vec::new();
+    for path in markdown_files(vault_path) {
+        let relative = relative_path(vault_path, &path);
+        if options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        notes.push(note_facts(&path, relative, &content, options));
+    }
+
+    let results = query
+        .run(notes)
+        .into_iter()
+        .map(|(path, values)| QueryRow {
+            path,
+            values: query.columns.iter().cloned().zip(values).collect(),
+        })
+        .collect();
+    Ok(QueryOutput { columns: query.columns, results })
+}
+
+/// The Markdown tables in one note, or in every note, with their rows keyed by column.
+/// `select` keeps only those columns, leaving out tables that have none of them, and
+/// `condition` keeps rows it holds for, written as in a query's WHERE with columns as
+/// fields (spaces in a column name can be written as `_`).
+pub fn note_tables(vault_path: &Path, note: Option<&str>, select: &[String], condition: Option<&str>, options: &ScanOptions) -> Result<Vec<NoteTable>, String> {
+    let condition = condition.map(query::parse_condition).transpose()?;
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    if let Some(note) = note {
+        let visible: Vec<String> = paths.iter().filter(|p| read_note(p, options).is_some()).map(|p| relative_path(vault_path, p)).collect();
+        let relative = NoteResolver::new(&visible).resolve(note).cloned().ok_or_else(|| format!("no note named {} in the vault", note))?;
+        paths = vec![vault_path.join(relative)];
+    }
+
+    let mut tables = Vec::new();
+    for path in paths {
+        let relative = relative_path(vault_path, &path);
+        if note.is_none() && options.skips_archived(&relative) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        for table in parse::extract_tables(&content) {
+            let headers = dedup_by_key(table.headers.iter().cloned(), str::to_string);
+            let columns: Vec<String> = if select.is_empty() {
+                headers.clone()
+            } else {
+                select.iter().filter(|column| headers.contains(column)).cloned().collect()
+            };
+            if columns.is_empty() {
+                continue;
+            }
+            let mut rows = Vec::new();
+            for cells in table.rows {
+                let mut fields = serde_json::Map::new();
+                for (header, cell) in table.headers.iter().zip(cells) {
+                    fields.entry(header.replace(' ', "_")).or_insert_with(|| cell.clone().into());
+                    fields.entry(header.clone()).or_insert_with(|| cell.into());
+                }
+                let row = query::NoteFacts { path: relative.clone(), tags: Vec::new(), fields };
+                if condition.as_ref().is_some_and(|condition| !condition.holds(&row)) {
+                    continue;
+                }
+                rows.push(columns.iter().map(|column| (column.clone(), row.fields[column].clone())).collect());
+            }
+            if condition.is_some() && rows.is_empty() {
+                continue;
+            }
+            tables.push(NoteTable {
+                path: relative.clone(),
+                line: table.line,
+                columns,
+                rows,
+            });
+        }
+    }
+    Ok(tables)
+}
+
+/// BM25 term frequency saturation and document length normalization.
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+pub(crate) fn search_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Notes containing any word of `query`, best match first by BM25 over note bodies.
+pub fn rank_notes(vault_path: &Path, query: &str, options: &ScanOptions) -> Result<Vec<RankedNote>, String> {
+    let terms: BTreeSet<String> = search_terms(query).into_iter().collect();
+    if terms.is_empty() {
+        return Err("the search query has no words to rank by".to_string());
+    }
+    let alternatives: Vec<String> = terms.iter().map(|term| regex::escape(term)).collect();
+    let pattern = Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).map_err(|e| e.to_string())?;
+
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut notes = Vec::new();
+    for path in paths {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let words = search_terms(&content[body_start(&content)..]);
+        let mut frequencies: HashMap<&str, usize> = HashMap::new();
+        for word in &words {
+            if let Some(term) = terms.get(word) {
+                *frequencies.entry(term).or_insert(0) += 1;
+            }
+        }
+        notes.push((relative_path(vault_path, &path), content, words.len(), frequencies));
+    }
+
+    let average_length = notes.iter().map(|(_, _, length, _)| *length).sum::<usize>() as f64 / notes.len().max(1) as f64;
+    let idf: HashMap<&str, f64> = terms
+        .iter()
+        .map(|term| {
+            let containing = notes.iter().filter(|(_, _, _, tf)| tf.contains_key(term.as_str())).count() as f64;
+            let idf = ((notes.len() as f64 - containing + 0.5) / (containing + 0.5) + 1.0).ln();
+            (term.as_str(), idf)
+        })
+        .collect();
+
+    let mut ranked: Vec<RankedNote> = notes
+        .iter()
+        .filter(|(_, _, _, frequencies)| !frequencies.is_empty())
+        .map(|(path, content, length, frequencies)| {
+            let normalization = BM25_K1 * (1.0 - BM25_B + BM25_B * *length as f64 / average_length.max(1.0));
+            let score: f64 = frequencies
+                .iter()
+                .map(|(term, &tf)| idf[term] * tf as f64 * (BM25_K1 + 1.0) / (tf as f64 + normalization))
+                .sum();
+            RankedNote {
+                path: path.clone(),
+                score: (score * 1000.0).round() / 1000.0,
+                matches: note_matches(path, content, &pattern),
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    Ok(ranked)
+}
+
+/// Scores from fzf's matching algorithm: a point value per matched character, plus
+/// bonuses for matching at word boundaries and in runs, minus penalties for gaps.
+const FUZZY_MATCH: i64 = 16;
+const FUZZY_GAP_START: i64 = -3;
+const FUZZY_GAP_EXTENSION: i64 = -1;
+const FUZZY_BOUNDARY: i64 = FUZZY_MATCH / 2;
+const FUZZY_CAMEL: i64 = FUZZY_BOUNDARY - 1;
+const FUZZY_CONSECUTIVE: i64 = -(FUZZY_GAP_START + FUZZY_GAP_EXTENSION);
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Letter,
+    Number,
+}
+
+fn char_class(c: char) -> CharClass {
+    match c {
+        c if c.is_whitespace() => CharClass::White,
+        '/' | ',' | ':' | ';' | '|' => CharClass::Delimiter,
+        c if c.is_lowercase() => CharClass::Lower,
+        c if c.is_uppercase() => CharClass::Upper,
+        c if c.is_numeric() => CharClass::Number,
+        c if c.is_alphabetic() => CharClass::Letter,
+        _ => CharClass::NonWord,
+    }
+}
+
+fn fuzzy_bonus(previous: CharClass, class: CharClass) -> i64 {
+    use CharClass::*;
+    let word = |class| matches!(class, Lower | Upper | Letter | Number);
+    match (previous, class) {
+        (White, class) if word(class) => FUZZY_BOUNDARY + 2,
+        (Delimiter, class) if word(class) => FUZZY_BOUNDARY + 1,
+        (NonWord, class) if word(class) => FUZZY_BOUNDARY,
+        (Lower, Upper) => FUZZY_CAMEL,
+        (previous, Number) if previous != Number => FUZZY_CAMEL,
+        (_, White | Delimiter | NonWord) => FUZZY_BOUNDARY,
+        _ => 0,
+    }
+}
+
+/// How well `pattern` matches `text` as a subsequence, as fzf's v1 algorithm scores it:
+/// the shortest window ending at the first complete match is scored. Case-insensitive
+/// unless `pattern` has an uppercase letter. None when `text` doesn't contain it.
+fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    let text: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // The first position where the whole pattern has been seen, then back from there to
+    // the latest start, for the shortest window
+    let mut matched = 0;
+    let end = text.iter().position(|&c| {
+        if fold(c) == pattern[matched] {
+            matched += 1;
+        }
+        matched == pattern.len()
+    })?;
+    let mut remaining = pattern.len();
+    let start = (0..=end).rev().find(|&i| {
+        if fold(text[i]) == pattern[remaining - 1] {
+            remaining -= 1;
+        }
+        remaining == 0
+    })?;
+
+    let mut score = 0;
+    let mut in_gap = false;
+    let mut consecutive = 0;
+    let mut first_bonus = 0;
+    let mut matched = 0;
+    let mut previous = if start == 0 { CharClass::White } else { char_class(text[start - 1]) };
+    for &c in &text[start..=end] {
+        let class = char_class(c);
+        if matched < pattern.len() && fold(c) == pattern[matched] {
+            let mut bonus = fuzzy_bonus(previous, class);
+            if consecutive == 0 {
+                first_bonus = bonus;
+            } else {
+                // A run keeps the bonus of the boundary it started at
+                if bonus >= FUZZY_BOUNDARY && bonus > first_bonus {
+                    first_bonus = bonus;
+                }
+                bonus = bonus.max(first_bonus).max(FUZZY_CONSECUTIVE);
+            }
+            score += FUZZY_MATCH + if matched == 0 { bonus * 2 } else { bonus };
+            in_gap = false;
+            consecutive += 1;
+            matched += 1;
+        } else {
+            score += if in_gap { FUZZY_GAP_EXTENSION } else { FUZZY_GAP_START };
+            in_gap = true;
+            consecutive = 0;
+            first_bonus = 0;
+        }
+        previous = class;
+    }
+    Some(score)
+}
+
+/// Notes whose name or one of whose aliases fuzzily matches `query`, best first. Ties go to
+/// the shorter name, so `plan` ranks `Plan` above `Planning`.
+pub fn find_notes(vault_path: &Path, query: &str, options: &ScanOptions) -> Vec<FoundNote> {
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut found = Vec::new();
+    for path in paths {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let path = relative_path(vault_path, &path);
+        let aliases = Frontmatter::of(&content).list("aliases", "alias", &[',']);
+        let best = std::iter::once(note_stem(&path).to_string())
+            .chain(aliases)
+            .filter_map(|candidate| Some((fuzzy_score(query, &candidate)?, candidate)))
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.chars().count().cmp(&a.1.chars().count())));
+        if let Some((score, matched)) = best {
+            found.push(FoundNote { path, matched, score });
+        }
+    }
+    found.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.matched.chars().count().cmp(&b.matched.chars().count()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_skips_frontmatter_and_numbers_file_lines() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-search-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Note.md"), "---\ntitle: Rust\n---\nIntro\nWhy rust? Because RUST.").unwrap();
+
+        let matches = search_notes(&root, "rust", false, &ScanOptions::default()).unwrap();
+        let found: Vec<(usize, usize, &str, [usize; 2])> =
+            matches.iter().map(|m| (m.line, m.column, m.snippet.as_str(), m.highlight)).collect();
+        assert_eq!(found, [(5, 5, "Why rust? Because RUST.", [4, 8]), (5, 19, "Why rust? Because RUST.", [18, 22])]);
+
+        let matches = search_notes(&root, r"(\w+)\? Because (x)?", true, &ScanOptions::default()).unwrap();
+        assert_eq!(matches[0].captures, [Some("rust".to_string()), None]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ranked_search_prefers_denser_and_rarer_matches() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-ranked-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Dense.md"), "Tokio tokio runtime").unwrap();
+        fs::write(root.join("Sparse.md"), "A runtime is mentioned once among many other words in this note").unwrap();
+        fs::write(root.join("Other.md"), "Nothing relevant").unwrap();
+
+        let ranked = rank_notes(&root, "tokio runtime", &ScanOptions::default()).unwrap();
+        let paths: Vec<&str> = ranked.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["Dense.md", "Sparse.md"]);
+        assert!(ranked[0].score > ranked[1].score);
+        assert_eq!(ranked[0].matches.len(), 3);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn fuzzy_scores_prefer_boundaries_and_runs() {
+        assert_eq!(fuzzy_score("xyz", "Plan"), None);
+        assert!(fuzzy_score("mn", "Meeting Notes") > fuzzy_score("mn", "Command"));
+        assert!(fuzzy_score("plan", "Plan") > fuzzy_score("plan", "Pale lantern"));
+        assert!(fuzzy_score("Plan", "plan").is_none());
+        assert!(fuzzy_score("rev", "Quarterly Review") > fuzzy_score("rev", "Unreviewed"));
+    }
+}
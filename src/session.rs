@@ -0,0 +1,176 @@
+//! Writing sessions: snapshots of the vault and what changed since one started.
+
+use crate::config::ScanOptions;
+use crate::dates::unix_now;
+use crate::scan::{markdown_files, read_note, relative_path};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SessionAction {
+    Start,
+    Status,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionSnapshot {
+    started: u64,
+    notes: BTreeMap<String, HashMap<String, usize>>,
+}
+
+#[derive(Serialize)]
+struct SessionNoteChange {
+    path: String,
+    status: &'static str,
+    added: usize,
+    removed: usize,
+    net: i64,
+}
+
+#[derive(Serialize)]
+pub struct SessionOutput {
+    started: u64,
+    duration_seconds: u64,
+    total_added: usize,
+    total_removed: usize,
+    net: i64,
+    notes: Vec<SessionNoteChange>,
+}
+
+fn state_dir(vault_path: &Path) -> PathBuf {
+    vault_path.join(".obsidian-cli")
+}
+
+fn word_frequencies(content: &str) -> HashMap<String, usize> {
+    let mut words = HashMap::new();
+    for word in content.split_whitespace() {
+        *words.entry(word.to_string()).or_insert(0) += 1;
+    }
+    words
+}
+
+fn snapshot_vault(vault_path: &Path, options: &ScanOptions) -> BTreeMap<String, HashMap<String, usize>> {
+    markdown_files(vault_path)
+        .iter()
+        .filter_map(|path| {
+            let content = read_note(path, options)?;
+            Some((relative_path(vault_path, path), word_frequencies(&content)))
+        })
+        .collect()
+}
+
+pub fn start_session(vault_path: &Path, options: &ScanOptions) -> Result<SessionOutput, String> {
+    let session_path = state_dir(vault_path).join("session.json");
+    if session_path.exists() {
+        return Err("a writing session is already running; stop it first".to_string());
+    }
+
+    let snapshot = SessionSnapshot {
+        started: unix_now(),
+        notes: snapshot_vault(vault_path, options),
+    };
+    let json = serde_json::to_string(&snapshot).map_err(|e| e.to_string())?;
+    fs::create_dir_all(state_dir(vault_path)).map_err(|e| format!("failed to create state dir: {}", e))?;
+    fs::write(&session_path, json).map_err(|e| format!("failed to write {}: {}", session_path.display(), e))?;
+
+    Ok(SessionOutput {
+        started: snapshot.started,
+        duration_seconds: 0,
+        total_added: 0,
+        total_removed: 0,
+        net: 0,
+        notes: Vec::new(),
+    })
+}
+
+/// Compares the session's starting snapshot to the vault as it is now. Added and removed
+/// words are counted per distinct word, so rewording a sentence shows up as both.
+pub fn session_changes(vault_path: &Path, stop: bool, options: &ScanOptions) -> Result<SessionOutput, String> {
+    let session_path = state_dir(vault_path).join("session.json");
+    let content = fs::read_to_string(&session_path).map_err(|_| "no writing session is running".to_string())?;
+    let snapshot: SessionSnapshot =
+        serde_json::from_str(&content).map_err(|e| format!("corrupt session file {}: {}", session_path.display(), e))?;
+    let current = snapshot_vault(vault_path, options);
+    let empty = HashMap::new();
+
+    let paths: BTreeSet<&String> = snapshot.notes.keys().chain(current.keys()).collect();
+    let mut notes = Vec::new();
+    for path in paths {
+        let before = snapshot.notes.get(path);
+        let after = current.get(path);
+        let status = match (before, after) {
+            (None, Some(_)) => "created",
+            (Some(_), None) => "deleted",
+            _ => "modified",
+        };
+        let before = before.unwrap_or(&empty);
+        let after = after.unwrap_or(&empty);
+
+        let added: usize = after
+            .iter()
+            .map(|(word, count)| count.saturating_sub(*before.get(word).unwrap_or(&0)))
+            .sum();
+        let removed: usize = before
+            .iter()
+            .map(|(word, count)| count.saturating_sub(*after.get(word).unwrap_or(&0)))
+            .sum();
+        if added == 0 && removed == 0 && status == "modified" {
+            continue;
+        }
+        notes.push(SessionNoteChange {
+            path: path.clone(),
+            status,
+            added,
+            removed,
+            net: added as i64 - removed as i64,
+        });
+    }
+
+    if stop {
+        fs::remove_file(&session_path).map_err(|e| format!("failed to remove {}: {}", session_path.display(), e))?;
+    }
+
+    let total_added = notes.iter().map(|n| n.added).sum();
+    let total_removed = notes.iter().map(|n| n.removed).sum();
+    Ok(SessionOutput {
+        started: snapshot.started,
+        duration_seconds: unix_now().saturating_sub(snapshot.started),
+        total_added,
+        total_removed,
+        net: total_added as i64 - total_removed as i64,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_count_words_added_and_removed_per_note() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-session-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Draft.md"), "the quick brown fox").unwrap();
+        fs::write(root.join("Old.md"), "gone soon").unwrap();
+        fs::write(root.join("Same.md"), "untouched").unwrap();
+
+        let options = ScanOptions::default();
+        start_session(&root, &options).unwrap();
+        assert!(start_session(&root, &options).is_err());
+        fs::write(root.join("Draft.md"), "the quick red fox jumps").unwrap();
+        fs::remove_file(root.join("Old.md")).unwrap();
+        fs::write(root.join("New.md"), "fresh words").unwrap();
+
+        let output = session_changes(&root, true, &options).unwrap();
+        let notes: Vec<(&str, &str, usize, usize)> = output.notes.iter().map(|n| (n.path.as_str(), n.status, n.added, n.removed)).collect();
+        assert_eq!(notes, [("Draft.md", "modified", 2, 1), ("New.md", "created", 2, 0), ("Old.md", "deleted", 0, 2)]);
+        assert_eq!((output.total_added, output.total_removed, output.net), (4, 3, 1));
+        assert_eq!(session_changes(&root, false, &options).err().as_deref(), Some("no writing session is running"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+//! Vault statistics, folder sizes, file listings and the parsing benchmark.
+
+use crate::config::{Config, ScanOptions};
+use crate::index::{NoteIndex, indexed_notes, update_index};
+use crate::links::{LinkInfo, collect_all_links, find_backlinks, find_orphans};
+use crate::parse::{TagSyntax, extract_links_from_file, extract_tags_from_file};
+use crate::scan::{markdown_files, read_note, relative_path, walk_vault};
+use crate::tags::collect_all_tags;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Serialize)]
+pub struct StatsOutput {
+    pub(crate) total_notes: usize,
+    pub(crate) total_tags: usize,
+    pub(crate) total_links: usize,
+    pub(crate) broken_links: usize,
+    pub(crate) orphaned_notes: usize,
+    /// Notes in the archive folder, counted here instead of in the totals above
+    /// unless archived notes are included
+    pub(crate) archived_notes: usize,
+}
+
+#[derive(Serialize)]
+pub struct FileInfo {
+    pub(crate) path: String,
+    pub(crate) word_count: usize,
+    pub(crate) link_count: usize,
+    pub(crate) tag_count: usize,
+    pub(crate) modified: String,
+}
+
+#[derive(Serialize)]
+pub struct FilesOutput {
+    pub files: Vec<FileInfo>,
+}
+
+#[derive(Serialize)]
+struct SizeBucket {
+    name: String,
+    bytes: u64,
+    files: usize,
+}
+
+#[derive(Serialize)]
+struct SizedFile {
+    path: String,
+    bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct SizeAuditOutput {
+    total_bytes: u64,
+    total_files: usize,
+    obsidian_config_bytes: u64,
+    plugins_bytes: u64,
+    trash_bytes: u64,
+    by_folder: Vec<SizeBucket>,
+    by_type: Vec<SizeBucket>,
+    largest_files: Vec<SizedFile>,
+    max_file_bytes: u64,
+    oversized_files: Vec<SizedFile>,
+}
+
+#[derive(Serialize)]
+struct BenchParseRun {
+    threads: usize,
+    millis: f64,
+    mb_per_sec: f64,
+    files_per_sec: f64,
+    /// Throughput relative to the single-threaded run
+    speedup: f64,
+}
+
+#[derive(Serialize)]
+struct BenchQuery {
+    query: &'static str,
+    millis: f64,
+}
+
+#[derive(Serialize)]
+pub struct BenchOutput {
+    notes: usize,
+    bytes: u64,
+    /// Reading every note from disk
+    read_millis: f64,
+    /// Building the note index from scratch, including reading notes
+    index_millis: f64,
+    parse: Vec<BenchParseRun>,
+    queries: Vec<BenchQuery>,
+}
+
+#[derive(Serialize)]
+pub struct FolderStats {
+    path: String,
+    notes: usize,
+    words: usize,
+    broken_links: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    folders: Vec<FolderStats>,
+}
+
+pub fn collect_all_files(vault_path: &Path, options: &ScanOptions) -> Result<Vec<FileInfo>, String> {
+    let mut files = Vec::new();
+
+    for (path, note) in indexed_notes(vault_path, options).notes {
+        if note.hidden(options) {
+            continue;
+        }
+        files.push(FileInfo {
+            path,
+            word_count: note.word_count,
+            link_count: note.links.len(),
+            tag_count: note.tags.len(),
+            modified: format!("{:?}", note.modified),
+        });
+    }
+
+    Ok(files)
+}
+
+pub fn calculate_stats(vault_path: &Path, options: &ScanOptions) -> Result<StatsOutput, String> {
+    let tag_counts = collect_all_tags(vault_path, options)?;
+    let (links, all_notes) = collect_all_links(vault_path, options)?;
+    let orphans = find_orphans(vault_path, options)?;
+
+    let archived_notes = all_notes.iter().filter(|note| options.in_archive(note)).count();
+    let links: Vec<&LinkInfo> = links.iter().filter(|l| !options.skips_archived(&l.source)).collect();
+    let broken_links = links.iter().filter(|l| !l.exists && !l.placeholder).count();
+
+    Ok(StatsOutput {
+        total_notes: all_notes.iter().filter(|note| !options.skips_archived(note)).count(),
+        total_tags: tag_counts.len(),
+        total_links: links.len(),
+        broken_links,
+        orphaned_notes: orphans.len(),
+        archived_notes,
+    })
+}
+
+pub fn size_audit(vault_path: &Path, config: &Config) -> Result<SizeAuditOutput, String> {
+    let mut files = Vec::new();
+    for entry in walk_vault(vault_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            files.push(SizedFile {
+                path: relative_path(vault_path, entry.path()),
+                bytes: metadata.len(),
+            });
+        }
+    }
+
+    let mut by_folder: BTreeMap<String, (u64, usize)> = BTreeMap::new();
+    let mut by_type: BTreeMap<String, (u64, usize)> = BTreeMap::new();
+    let mut obsidian_config_bytes = 0;
+    let mut plugins_bytes = 0;
+    let mut trash_bytes = 0;
+
+    for file in &files {
+        let folder = match file.path.split_once('/') {
+            Some((folder, _)) => format!("{}/", folder),
+            None => "/".to_string(),
+        };
+        let bucket = by_folder.entry(folder).or_insert((0, 0));
+        bucket.0 += file.bytes;
+        bucket.1 += 1;
+
+        let extension = Path::new(&file.path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "(none)".to_string());
+        let bucket = by_type.entry(extension).or_insert((0, 0));
+        bucket.0 += file.bytes;
+        bucket.1 += 1;
+
+        if file.path.starts_with(".obsidian/") {
+            obsidian_config_bytes += file.bytes;
+            if file.path.starts_with(".obsidian/plugins/") {
+                plugins_bytes += file.bytes;
+            }
+        } else if file.path.starts_with(".trash/") {
+            trash_bytes += file.bytes;
+        }
+    }
+
+    let into_buckets = |map: BTreeMap<String, (u64, usize)>| {
+        let mut buckets: Vec<SizeBucket> = map
+            .into_iter()
+            .map(|(name, (bytes, files))| SizeBucket { name, bytes, files })
+            .collect();
+        buckets.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        buckets
+    };
+
+    files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    let max_file_bytes = config.size_audit.max_file_bytes;
+    let oversized_files = files
+        .iter()
+        .filter(|f| f.bytes > max_file_bytes)
+        .map(|f| SizedFile {
+            path: f.path.clone(),
+            bytes: f.bytes,
+        })
+        .collect();
+
+    let total_bytes = files.iter().map(|f| f.bytes).sum();
+    let total_files = files.len();
+    files.truncate(config.size_audit.largest_files);
+
+    Ok(SizeAuditOutput {
+        total_bytes,
+        total_files,
+        obsidian_config_bytes,
+        plugins_bytes,
+        trash_bytes,
+        by_folder: into_buckets(by_folder),
+        by_type: into_buckets(by_type),
+        largest_files: files,
+        max_file_bytes,
+        oversized_files,
+    })
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1_000.0
+}
+
+/// Extracts links, tags and word counts from every note using `threads` workers.
+fn parse_notes(contents: &[String], threads: usize, syntax: &TagSyntax) -> usize {
+    let chunk = contents.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = contents
+            .chunks(chunk)
+            .map(|notes| {
+                scope.spawn(move || {
+                    notes
+                        .iter()
+                        .map(|content| {
+                            extract_links_from_file(content).len()
+                                + extract_tags_from_file(content, syntax).len()
+                                + content.split_whitespace().count()
+                        })
+                        .sum::<usize>()
+                })
+            })
+            .collect();
+        workers.into_iter().map(|worker| worker.join().unwrap_or_default()).sum()
+    })
+}
+
+pub fn run_bench(vault_path: &Path, max_threads: Option<usize>, options: &ScanOptions) -> Result<BenchOutput, String> {
+    let max_threads = max_threads
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+
+    let started = Instant::now();
+    let contents: Vec<String> = markdown_files(vault_path).iter().filter_map(|path| read_note(path, options)).collect();
+    let read_millis = millis(started.elapsed());
+    let bytes: u64 = contents.iter().map(|c| c.len() as u64).sum();
+
+    let started = Instant::now();
+    update_index(vault_path, &mut NoteIndex::default(), options);
+    let index_millis = millis(started.elapsed());
+
+    let mut parse = Vec::new();
+    let mut single_thread_seconds = None;
+    let mut threads = 1;
+    while threads <= max_threads {
+        let started = Instant::now();
+        std::hint::black_box(parse_notes(&contents, threads, &options.tag_syntax));
+        let seconds = started.elapsed().as_secs_f64().max(f64::EPSILON);
+        let single = *single_thread_seconds.get_or_insert(seconds);
+        parse.push(BenchParseRun {
+            threads,
+            millis: millis(Duration::from_secs_f64(seconds)),
+            mb_per_sec: bytes as f64 / 1_000_000.0 / seconds,
+            files_per_sec: contents.len() as f64 / seconds,
+            speedup: single / seconds,
+        });
+        threads = if threads == max_threads { threads + 1 } else { (threads * 2).min(max_threads) };
+    }
+
+    let sample = markdown_files(vault_path).first().map(|path| relative_path(vault_path, path)).unwrap_or_default();
+    let mut queries = Vec::new();
+    let mut time = |query: &'static str, run: &dyn Fn() -> Result<(), String>| -> Result<(), String> {
+        let started = Instant::now();
+        run()?;
+        queries.push(BenchQuery {
+            query,
+            millis: millis(started.elapsed()),
+        });
+        Ok(())
+    };
+    time("tags", &|| collect_all_tags(vault_path, options).map(drop))?;
+    time("links", &|| collect_all_links(vault_path, options).map(drop))?;
+    time("orphans", &|| find_orphans(vault_path, options).map(drop))?;
+    time("backlinks", &|| find_backlinks(vault_path, &sample, options).map(drop))?;
+    time("stats", &|| calculate_stats(vault_path, options).map(drop))?;
+
+    Ok(BenchOutput {
+        notes: contents.len(),
+        bytes,
+        read_millis,
+        index_millis,
+        parse,
+        queries,
+    })
+}
+
+impl FolderStats {
+    pub fn new(path: String) -> Self {
+        FolderStats {
+            path,
+            notes: 0,
+            words: 0,
+            broken_links: 0,
+            folders: Vec::new(),
+        }
+    }
+
+    /// Counts a note in this folder and in each folder below it on the way to the note,
+    /// stopping `depth` levels down so deeper notes count towards their ancestor there.
+    fn add(&mut self, folders: &[&str], depth: usize, words: usize, broken_links: usize) {
+        self.notes += 1;
+        self.words += words;
+        self.broken_links += broken_links;
+
+        let Some((name, rest)) = folders.split_first().filter(|_| depth > 0) else {
+            return;
+        };
+        let path = if self.path == "." { name.to_string() } else { format!("{}/{}", self.path, name) };
+        let index = match self.folders.iter().position(|f| f.path == path) {
+            Some(index) => index,
+            None => {
+                self.folders.push(FolderStats::new(path));
+                self.folders.len() - 1
+            }
+        };
+        self.folders[index].add(rest, depth - 1, words, broken_links);
+    }
+
+    fn sort(&mut self) {
+        self.folders.sort_by(|a, b| a.path.cmp(&b.path));
+        self.folders.iter_mut().for_each(FolderStats::sort);
+    }
+}
+
+pub fn folder_tree(vault_path: &Path, depth: Option<usize>, options: &ScanOptions) -> Result<FolderStats, String> {
+    let files = collect_all_files(vault_path, options)?;
+    let (links, _) = collect_all_links(vault_path, options)?;
+    let mut broken: HashMap<&str, usize> = HashMap::new();
+    for link in links.iter().filter(|l| !l.exists && !l.placeholder) {
+        *broken.entry(link.source.as_str()).or_insert(0) += 1;
+    }
+
+    let mut root = FolderStats::new(".".to_string());
+    for file in &files {
+        let folders: Vec<&str> = file.path.split('/').collect();
+        let folders = &folders[..folders.len() - 1];
+        root.add(folders, depth.unwrap_or(usize::MAX), file.word_count, broken.get(file.path.as_str()).copied().unwrap_or(0));
+    }
+    root.sort();
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SizeAuditConfig;
+    use std::fs;
+
+    #[test]
+    fn size_audit_splits_config_plugins_and_trash_and_flags_big_files() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-size-audit-{}", std::process::id()));
+        fs::create_dir_all(root.join(".obsidian/plugins/dataview")).unwrap();
+        fs::create_dir_all(root.join(".trash")).unwrap();
+        fs::create_dir_all(root.join("Media")).unwrap();
+        fs::write(root.join(".obsidian/app.json"), "{}").unwrap();
+        fs::write(root.join(".obsidian/plugins/dataview/main.js"), "x".repeat(30)).unwrap();
+        fs::write(root.join(".trash/Old.md"), "old").unwrap();
+        fs::write(root.join("Media/clip.MP4"), "v".repeat(50)).unwrap();
+        fs::write(root.join("Home.md"), "home").unwrap();
+
+        let config = Config { size_audit: SizeAuditConfig { max_file_bytes: 40, largest_files: 2 }, ..Config::default() };
+        let audit = size_audit(&root, &config).unwrap();
+        assert_eq!((audit.total_bytes, audit.total_files), (89, 5));
+        assert_eq!((audit.obsidian_config_bytes, audit.plugins_bytes, audit.trash_bytes), (32, 30, 3));
+        let folders: Vec<(&str, u64)> = audit.by_folder.iter().map(|b| (b.name.as_str(), b.bytes)).collect();
+        assert_eq!(folders, [("Media/", 50), (".obsidian/", 32), ("/", 4), (".trash/", 3)]);
+        assert_eq!(audit.by_type[0].name, "mp4");
+        let largest: Vec<&str> = audit.largest_files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(largest, ["Media/clip.MP4", ".obsidian/plugins/dataview/main.js"]);
+        assert_eq!(audit.oversized_files.len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn bench_parses_with_doubling_thread_counts_up_to_the_limit() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-bench-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let contents: Vec<String> = (0..5).map(|i| format!("#tag{} words [[Note {}]]", i, i + 1)).collect();
+        for (i, content) in contents.iter().enumerate() {
+            fs::write(root.join(format!("Note {}.md", i)), content).unwrap();
+        }
+        let syntax = TagSyntax::default();
+        // A link, a tag and four words per note
+        assert_eq!(parse_notes(&contents, 1, &syntax), 5 * 6);
+        assert_eq!(parse_notes(&contents, 4, &syntax), parse_notes(&contents, 1, &syntax));
+
+        let bench = run_bench(&root, Some(5), &ScanOptions::default()).unwrap();
+        assert_eq!(bench.notes, 5);
+        assert_eq!(bench.parse.iter().map(|run| run.threads).collect::<Vec<_>>(), [1, 2, 4, 5]);
+        assert_eq!(bench.parse[0].speedup, 1.0);
+        assert_eq!(bench.queries.iter().map(|q| q.query).collect::<Vec<_>>(), ["tags", "links", "orphans", "backlinks", "stats"]);
+
+        if let Some(cache) = crate::index::cache_dir(&root) {
+            let _ = fs::remove_dir_all(cache);
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn folder_tree_rolls_deeper_notes_up_to_the_depth_limit() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-folder-tree-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects/Web/Old")).unwrap();
+        fs::write(root.join("Home.md"), "one two").unwrap();
+        fs::write(root.join("Projects/Plan.md"), "[[Missing]] [[Home]]").unwrap();
+        fs::write(root.join("Projects/Web/Site.md"), "three").unwrap();
+        fs::write(root.join("Projects/Web/Old/Draft.md"), "[[Gone]]").unwrap();
+
+        let summary = |folder: &FolderStats| (folder.path.clone(), folder.notes, folder.words, folder.broken_links);
+        let options = ScanOptions::default();
+        let tree = folder_tree(&root, None, &options).unwrap();
+        assert_eq!(summary(&tree), (".".to_string(), 4, 6, 2));
+        let projects = &tree.folders[0];
+        assert_eq!(summary(projects), ("Projects".to_string(), 3, 4, 2));
+        assert_eq!(summary(&projects.folders[0].folders[0]), ("Projects/Web/Old".to_string(), 1, 1, 1));
+
+        let tree = folder_tree(&root, Some(1), &options).unwrap();
+        assert_eq!(summary(&tree.folders[0]), ("Projects".to_string(), 3, 4, 2));
+        assert!(tree.folders[0].folders.is_empty());
+
+        if let Some(cache) = crate::index::cache_dir(&root) {
+            let _ = fs::remove_dir_all(cache);
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn archived_notes_are_left_out_of_stats_and_orphans_by_default() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-archive-stats-{}", std::process::id()));
+        fs::create_dir_all(root.join("Archive/2020")).unwrap();
+        fs::write(root.join("Home.md"), "[[Old Plan]] #idea").unwrap();
+        fs::write(root.join("Lonely.md"), "").unwrap();
+        fs::write(root.join("Archive/Old Plan.md"), "[[Gone]]").unwrap();
+        fs::write(root.join("Archive/2020/Forgotten.md"), "#old").unwrap();
+
+        let options = ScanOptions { archive_folder: "Archive".to_string(), ..ScanOptions::default() };
+        let stats = calculate_stats(&root, &options).unwrap();
+        assert_eq!((stats.total_notes, stats.archived_notes, stats.total_links, stats.broken_links), (2, 2, 1, 0));
+        assert_eq!(find_orphans(&root, &options).unwrap(), ["Lonely.md"]);
+
+        let options = ScanOptions { include_archived: true, ..options };
+        let stats = calculate_stats(&root, &options).unwrap();
+        assert_eq!((stats.total_notes, stats.archived_notes, stats.total_links, stats.broken_links), (4, 2, 2, 1));
+        assert_eq!(find_orphans(&root, &options).unwrap(), ["Archive/2020/Forgotten.md", "Lonely.md"]);
+
+        if let Some(cache) = crate::index::cache_dir(&root) {
+            let _ = fs::remove_dir_all(cache);
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
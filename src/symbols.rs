@@ -0,0 +1,123 @@
+//! Emoji and other symbols used in notes.
+
+use crate::config::ScanOptions;
+use crate::scan::{markdown_files, read_note, relative_path};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct SymbolCount {
+    symbol: String,
+    count: usize,
+    note_count: usize,
+}
+
+#[derive(Serialize)]
+struct NoteSymbols {
+    path: String,
+    symbols: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+pub struct SymbolsOutput {
+    symbols: Vec<SymbolCount>,
+    notes: Vec<NoteSymbols>,
+}
+
+fn is_emoji_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF // emoticons, pictographs, transport, supplemental symbols
+        | 0x2600..=0x27BF // miscellaneous symbols and dingbats (✅, ☑, ✔)
+        | 0x2B00..=0x2BFF // arrows and stars (⭐, ⬆)
+        | 0x2300..=0x23FF // technical symbols used by the Tasks plugin (⏫, ⏳, ⌛)
+    )
+}
+
+fn is_emoji_modifier(c: char) -> bool {
+    // Variation selector-16 and skin tone modifiers attach to the preceding emoji
+    matches!(c as u32, 0xFE0F | 0x1F3FB..=0x1F3FF)
+}
+
+fn extract_symbols(content: &str) -> Vec<String> {
+    let mut symbols = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !is_emoji_char(c) {
+            continue;
+        }
+        let mut symbol = c.to_string();
+        while let Some(&next) = chars.peek() {
+            if is_emoji_modifier(next) {
+                symbol.push(next);
+                chars.next();
+            } else if next == '\u{200D}' {
+                // Zero-width joiner sequences like 👩‍💻 form a single symbol
+                symbol.push(next);
+                chars.next();
+                if let Some(joined) = chars.next_if(|&n| is_emoji_char(n)) {
+                    symbol.push(joined);
+                }
+            } else {
+                break;
+            }
+        }
+        symbols.push(symbol);
+    }
+
+    symbols
+}
+
+pub fn collect_symbols(vault_path: &Path, options: &ScanOptions) -> Result<SymbolsOutput, String> {
+    let mut totals: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut notes = Vec::new();
+
+    for path in markdown_files(vault_path) {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+
+        let mut note_symbols: BTreeMap<String, usize> = BTreeMap::new();
+        for symbol in extract_symbols(&content) {
+            *note_symbols.entry(symbol).or_insert(0) += 1;
+        }
+        if note_symbols.is_empty() {
+            continue;
+        }
+
+        for (symbol, count) in &note_symbols {
+            let total = totals.entry(symbol.clone()).or_insert((0, 0));
+            total.0 += count;
+            total.1 += 1;
+        }
+        notes.push(NoteSymbols {
+            path: relative_path(vault_path, &path),
+            symbols: note_symbols,
+        });
+    }
+
+    let mut symbols: Vec<SymbolCount> = totals
+        .into_iter()
+        .map(|(symbol, (count, note_count))| SymbolCount {
+            symbol,
+            count,
+            note_count,
+        })
+        .collect();
+    symbols.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.symbol.cmp(&b.symbol)));
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(SymbolsOutput { symbols, notes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_keep_modifiers_and_joined_sequences_together() {
+        let symbols = extract_symbols("- [x] ✔️ ship it 👍🏽\n👩‍💻 pairing ⏫ café → ok");
+        assert_eq!(symbols, ["✔\u{FE0F}", "👍🏽", "👩\u{200D}💻", "⏫"]);
+    }
+}
@@ -0,0 +1,309 @@
+//! A naive Bayes model trained on tagged notes, for suggesting tags.
+
+use crate::config::ScanOptions;
+use crate::index::cache_dir;
+use crate::scan::{body_start, find_note_path, markdown_files, read_note, relative_path};
+use crate::search::search_terms;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Bump whenever the shape or meaning of a saved tag model changes.
+const TAG_MODEL_VERSION: u32 = 1;
+/// Most words a tag model keeps, the ones used by the most notes
+const TAG_MODEL_VOCABULARY: usize = 10_000;
+/// Added to every word's weight so words a tag has never been seen with don't rule it out
+const TAG_MODEL_SMOOTHING: f64 = 0.01;
+
+/// A naive Bayes classifier for each tag over TF-IDF weighted words, trained on the vault's
+/// tagged notes. Only summed weights are kept, so the file stays small for large vaults.
+#[derive(Serialize, Deserialize)]
+struct TagModel {
+    version: u32,
+    /// Tagged notes the model was trained on
+    notes: usize,
+    idf: BTreeMap<String, f64>,
+    /// Each word's weight summed over every training note
+    word_totals: BTreeMap<String, f64>,
+    tags: BTreeMap<String, TagClass>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TagClass {
+    notes: usize,
+    /// Each word's weight summed over the notes with the tag
+    word_sums: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+pub struct TrainedTag {
+    tag: String,
+    notes: usize,
+}
+
+#[derive(Serialize)]
+pub struct TrainTagsOutput {
+    model: String,
+    /// Tagged notes the model learned from
+    notes: usize,
+    vocabulary: usize,
+    tags: Vec<TrainedTag>,
+}
+
+#[derive(Serialize)]
+pub struct TagPrediction {
+    tag: String,
+    /// The model's probability, from 0 to 1, that the tag belongs on the note
+    confidence: f64,
+}
+
+#[derive(Serialize)]
+pub struct NoteTagPredictions {
+    path: String,
+    tags: Vec<TagPrediction>,
+}
+
+#[derive(Serialize)]
+pub struct SuggestTagsOutput {
+    model: String,
+    notes: Vec<NoteTagPredictions>,
+}
+
+fn tag_model_path(vault_path: &Path) -> Result<PathBuf, String> {
+    cache_dir(vault_path)
+        .map(|dir| dir.join("tag-model.json"))
+        .ok_or_else(|| "no cache directory available (HOME is not set)".to_string())
+}
+
+/// Counts of the words in a note's body that a tag model can learn from: inline tags,
+/// numbers and words shorter than three letters are left out.
+fn tag_model_words(content: &str) -> HashMap<String, usize> {
+    static INLINE_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|\s)#[^\s#]+").unwrap());
+    let body = INLINE_TAG.replace_all(&content[body_start(content)..], " ");
+    let mut counts = HashMap::new();
+    for word in search_terms(&body) {
+        if word.chars().count() >= 3 && !word.chars().all(|c| c.is_numeric()) {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+impl TagModel {
+    /// TF-IDF weights of the note's words in the vocabulary, scaled to unit length so long
+    /// notes don't count for more.
+    fn features(&self, words: &HashMap<String, usize>) -> Vec<(&str, f64)> {
+        let mut features: Vec<(&str, f64)> = words
+            .iter()
+            .filter_map(|(word, count)| {
+                let (word, idf) = self.idf.get_key_value(word)?;
+                Some((word.as_str(), (1.0 + *count as f64).ln() * idf))
+            })
+            .collect();
+        let length = features.iter().map(|(_, weight)| weight * weight).sum::<f64>().sqrt();
+        if length > 0.0 {
+            features.iter_mut().for_each(|(_, weight)| *weight /= length);
+        }
+        features
+    }
+
+    /// The probability of each tag for a note with these words, most likely first.
+    fn predict(&self, words: &HashMap<String, usize>) -> Vec<TagPrediction> {
+        let features = self.features(words);
+        let vocabulary = self.idf.len() as f64 * TAG_MODEL_SMOOTHING;
+        let total: f64 = self.word_totals.values().sum();
+        let mut predictions: Vec<TagPrediction> = self
+            .tags
+            .iter()
+            .map(|(tag, class)| {
+                let tagged: f64 = class.word_sums.values().sum();
+                let mut log_odds = ((class.notes as f64 + 1.0) / ((self.notes - class.notes) as f64 + 1.0)).ln();
+                for (word, weight) in &features {
+                    let with = class.word_sums.get(*word).copied().unwrap_or(0.0);
+                    let all = self.word_totals.get(*word).copied().unwrap_or(0.0);
+                    let given_tag = (with + TAG_MODEL_SMOOTHING) / (tagged + vocabulary);
+                    let given_other = ((all - with).max(0.0) + TAG_MODEL_SMOOTHING) / ((total - tagged).max(0.0) + vocabulary);
+                    log_odds += weight * (given_tag / given_other).ln();
+                }
+                TagPrediction {
+                    tag: tag.clone(),
+                    confidence: 1.0 / (1.0 + (-log_odds).exp()),
+                }
+            })
+            .collect();
+        predictions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.tag.cmp(&b.tag)));
+        predictions
+    }
+}
+
+/// Trains a tag model on the vault's tagged notes, learning every tag used by at least
+/// `min_notes` of them, and saves it to `out` or the cache directory.
+pub fn train_tag_model(vault_path: &Path, min_notes: usize, out: Option<&Path>, options: &ScanOptions) -> Result<TrainTagsOutput, String> {
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let notes: Vec<(BTreeSet<String>, HashMap<String, usize>)> = paths
+        .iter()
+        .filter(|path| !options.skips_archived(&relative_path(vault_path, path)))
+        .filter_map(|path| read_note(path, options))
+        .map(|content| (options.note_tags(&content).into_iter().collect(), tag_model_words(&content)))
+        .collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for (_, words) in &notes {
+        for word in words.keys() {
+            *document_frequency.entry(word).or_insert(0) += 1;
+        }
+    }
+    let mut vocabulary: Vec<(&str, usize)> = document_frequency.into_iter().filter(|(_, notes)| *notes >= 2).collect();
+    vocabulary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    vocabulary.truncate(TAG_MODEL_VOCABULARY);
+    let idf = vocabulary
+        .into_iter()
+        .map(|(word, frequency)| (word.to_string(), ((notes.len() as f64 + 1.0) / (frequency as f64 + 1.0)).ln() + 1.0))
+        .collect();
+
+    let tagged: Vec<&(BTreeSet<String>, HashMap<String, usize>)> = notes.iter().filter(|(tags, _)| !tags.is_empty()).collect();
+    let mut tag_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for (tags, _) in &tagged {
+        for tag in tags {
+            *tag_counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    let mut model = TagModel {
+        version: TAG_MODEL_VERSION,
+        notes: tagged.len(),
+        idf,
+        word_totals: BTreeMap::new(),
+        tags: tag_counts
+            .into_iter()
+            .filter(|(_, notes)| *notes >= min_notes)
+            .map(|(tag, notes)| (tag.to_string(), TagClass { notes, word_sums: BTreeMap::new() }))
+            .collect(),
+    };
+    if model.tags.is_empty() {
+        return Err(format!("no tag is used by {} or more notes to learn from", min_notes));
+    }
+    for (tags, words) in tagged {
+        let features: Vec<(String, f64)> = model.features(words).into_iter().map(|(word, weight)| (word.to_string(), weight)).collect();
+        for (word, weight) in &features {
+            *model.word_totals.entry(word.clone()).or_insert(0.0) += weight;
+        }
+        for tag in tags {
+            if let Some(class) = model.tags.get_mut(tag) {
+                for (word, weight) in &features {
+                    *class.word_sums.entry(word.clone()).or_insert(0.0) += weight;
+                }
+            }
+        }
+    }
+
+    let path = match out {
+        Some(path) => path.to_path_buf(),
+        None => tag_model_path(vault_path)?,
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string(&model).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+
+    Ok(TrainTagsOutput {
+        model: path.display().to_string(),
+        notes: model.notes,
+        vocabulary: model.idf.len(),
+        tags: model.tags.iter().map(|(tag, class)| TrainedTag { tag: tag.clone(), notes: class.notes }).collect(),
+    })
+}
+
+/// Tags the model predicts for `note`, or for every note without tags, with at least
+/// `threshold` confidence and at most `limit` per note.
+pub fn suggest_tags(
+    vault_path: &Path,
+    note: Option<&str>,
+    model: Option<&Path>,
+    threshold: f64,
+    limit: usize,
+    options: &ScanOptions,
+) -> Result<SuggestTagsOutput, String> {
+    let path = match model {
+        Some(path) => path.to_path_buf(),
+        None => tag_model_path(vault_path)?,
+    };
+    let content = fs::read_to_string(&path).map_err(|_| match model {
+        Some(_) => format!("cannot read tag model {}", path.display()),
+        None => "no tag model trained for this vault yet; run `obsidian-cli ml train-tags` first".to_string(),
+    })?;
+    let tag_model: TagModel = serde_json::from_str(&content)
+        .ok()
+        .filter(|m: &TagModel| m.version == TAG_MODEL_VERSION)
+        .ok_or_else(|| format!("{} is not a tag model from this version; run `obsidian-cli ml train-tags` again", path.display()))?;
+
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let visible: HashSet<String> = files.iter().map(|p| relative_path(vault_path, p)).collect();
+    let wanted = match note {
+        Some(name) => Some(find_note_path(vault_path, name, &visible).ok_or_else(|| format!("note not found: {}", name))?),
+        None => None,
+    };
+
+    let mut notes = Vec::new();
+    for file in files {
+        let relative = relative_path(vault_path, &file);
+        if wanted.as_ref().is_some_and(|wanted| *wanted != relative) || (wanted.is_none() && options.skips_archived(&relative)) {
+            continue;
+        }
+        let Some(content) = read_note(&file, options) else {
+            continue;
+        };
+        let tags = options.note_tags(&content);
+        if wanted.is_none() && !tags.is_empty() {
+            continue;
+        }
+        let predictions: Vec<TagPrediction> = tag_model
+            .predict(&tag_model_words(&content))
+            .into_iter()
+            .filter(|p| p.confidence >= threshold && !tags.contains(&p.tag))
+            .take(limit)
+            .collect();
+        if !predictions.is_empty() || wanted.is_some() {
+            notes.push(NoteTagPredictions { path: relative, tags: predictions });
+        }
+    }
+    Ok(SuggestTagsOutput {
+        model: path.display().to_string(),
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_model_predicts_tags_from_words() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-tag-model-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        for i in 0..3 {
+            fs::write(root.join(format!("r{}.md", i)), format!("The borrow checker and crate lifetimes, day {} #rust", i)).unwrap();
+            fs::write(root.join(format!("c{}.md", i)), format!("---\ntags: [cooking]\n---\nThe oven, flour and butter, day {}", i)).unwrap();
+        }
+        fs::write(root.join("new.md"), "A lifetime error from the borrow checker").unwrap();
+        let model = root.join("model.json");
+        let options = ScanOptions::default();
+
+        let trained = train_tag_model(&root, 3, Some(&model), &options).unwrap();
+        assert_eq!((trained.notes, trained.tags.len()), (6, 2));
+        let suggested = suggest_tags(&root, None, Some(&model), 0.7, 3, &options).unwrap();
+        let found: Vec<(&str, Vec<&str>)> = suggested
+            .notes
+            .iter()
+            .map(|n| (n.path.as_str(), n.tags.iter().map(|t| t.tag.as_str()).collect()))
+            .collect();
+        assert_eq!(found, [("new.md", vec!["rust"])]);
+        assert!(train_tag_model(&root, 4, Some(&model), &options).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,637 @@
+//! Tag counts, hierarchies and co-occurrence, tag expressions, and renaming tags.
+
+use crate::config::ScanOptions;
+use crate::generate::{GENERATED_START, GeneratedNote, write_generated_note};
+use crate::index::indexed_notes;
+use crate::links::link_target;
+use crate::parse::{TagSyntax, extract_frontmatter, mask_code};
+use crate::scan::{is_visible_entry, markdown_files, read_note, relative_path, walk_vault};
+use crate::write::{LineChange, VaultWriter};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+#[derive(Serialize)]
+pub struct TagsOutput {
+    pub tags: Vec<TagCount>,
+}
+
+#[derive(Serialize)]
+struct AliasUsage {
+    /// The tag as written
+    tag: String,
+    canonical: String,
+    notes: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct TagAliasesOutput {
+    aliases: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usages: Option<Vec<AliasUsage>>,
+}
+
+#[derive(Serialize)]
+pub struct TagTreeNode {
+    tag: String,
+    /// Notes tagged with exactly this tag
+    count: usize,
+    /// Notes tagged with this tag or any tag nested under it
+    total: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<TagTreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct TagPair {
+    pub first: String,
+    pub second: String,
+    /// Notes tagged with both
+    pub count: usize,
+    /// `count` over the notes tagged with either; near 1 for tags that are almost always
+    /// used together and so may be worth merging
+    pub overlap: f64,
+}
+
+#[derive(Serialize)]
+pub struct TagCooccurrenceOutput {
+    pub pairs: Vec<TagPair>,
+}
+
+#[derive(Serialize)]
+pub struct TagTreeOutput {
+    tags: Vec<TagTreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct TagSearchOutput {
+    pub tag: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TagRenameNote {
+    path: String,
+    replacements: usize,
+    /// The lines the rename changes, as a diff
+    changes: Vec<LineChange>,
+}
+
+#[derive(Serialize)]
+pub struct TagRenameOutput {
+    dry_run: bool,
+    from: String,
+    to: String,
+    notes: Vec<TagRenameNote>,
+    /// Private notes renamed too, left out of `notes`
+    private_notes: usize,
+}
+
+#[derive(Serialize)]
+pub struct TagPagesOutput {
+    folder: String,
+    pages: Vec<GeneratedNote>,
+    /// Generated tag pages whose tag no longer appears in any note
+    stale: Vec<String>,
+}
+
+pub fn collect_all_tags(vault_path: &Path, options: &ScanOptions) -> Result<BTreeMap<String, usize>, String> {
+    let mut tag_counts = BTreeMap::new();
+
+    for note in indexed_notes(vault_path, options).notes.into_values() {
+        if note.hidden(options) {
+            continue;
+        }
+        for tag in note.tags {
+            *tag_counts.entry(options.canonical_tag(&tag)).or_insert(0) += 1;
+        }
+    }
+
+    Ok(tag_counts)
+}
+
+/// Nested tags as a tree, each with the notes tagged with it and the notes tagged with it
+/// or anything below it, so `project` totals `project/alpha` and `project/beta`.
+pub fn tag_tree(vault_path: &Path, options: &ScanOptions) -> TagTreeOutput {
+    let mut own: BTreeMap<String, usize> = BTreeMap::new();
+    let mut total: BTreeMap<String, usize> = BTreeMap::new();
+    for note in indexed_notes(vault_path, options).notes.into_values() {
+        if note.hidden(options) {
+            continue;
+        }
+        let tags: BTreeSet<String> = note.tags.iter().map(|t| options.canonical_tag(t)).collect();
+        let mut ancestors = BTreeSet::new();
+        for tag in &tags {
+            *own.entry(tag.clone()).or_insert(0) += 1;
+            ancestors.extend(tag.match_indices('/').map(|(i, _)| &tag[..i]).chain([tag.as_str()]));
+        }
+        for tag in ancestors {
+            *total.entry(tag.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    fn children(parent: Option<&str>, own: &BTreeMap<String, usize>, total: &BTreeMap<String, usize>) -> Vec<TagTreeNode> {
+        total
+            .iter()
+            .filter(|(tag, _)| tag.rsplit_once('/').map(|(up, _)| up) == parent)
+            .map(|(tag, &total_count)| TagTreeNode {
+                tag: tag.clone(),
+                count: own.get(tag).copied().unwrap_or(0),
+                total: total_count,
+                tags: children(Some(tag), own, total),
+            })
+            .collect()
+    }
+    TagTreeOutput {
+        tags: children(None, &own, &total),
+    }
+}
+
+/// Pairs of tags used together in the same notes, most frequent first.
+pub fn tag_cooccurrence(vault_path: &Path, min_count: usize, options: &ScanOptions) -> Vec<TagPair> {
+    let mut notes_per_tag: HashMap<String, usize> = HashMap::new();
+    let mut pairs: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for note in indexed_notes(vault_path, options).notes.into_values() {
+        if note.hidden(options) {
+            continue;
+        }
+        let tags: Vec<String> = note.tags.iter().map(|t| options.canonical_tag(t)).collect::<BTreeSet<_>>().into_iter().collect();
+        for (i, first) in tags.iter().enumerate() {
+            *notes_per_tag.entry(first.clone()).or_insert(0) += 1;
+            for second in &tags[i + 1..] {
+                *pairs.entry((first.clone(), second.clone())).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<TagPair> = pairs
+        .into_iter()
+        .filter(|(_, count)| *count >= min_count)
+        .map(|((first, second), count)| {
+            let either = notes_per_tag[&first] + notes_per_tag[&second] - count;
+            TagPair {
+                overlap: (count as f64 / either as f64 * 1000.0).round() / 1000.0,
+                first,
+                second,
+                count,
+            }
+        })
+        .collect();
+    pairs.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.overlap.total_cmp(&a.overlap)));
+    pairs
+}
+
+/// The configured tag aliases and, with `report`, every alias still written in notes.
+pub fn tag_aliases(vault_path: &Path, report: bool, options: &ScanOptions) -> TagAliasesOutput {
+    let usages = report.then(|| {
+        let mut by_tag: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (path, note) in indexed_notes(vault_path, options).notes {
+            if note.hidden(options) {
+                continue;
+            }
+            for tag in note.tags.iter().filter(|t| options.canonical_tag(t) != **t) {
+                by_tag.entry(tag.clone()).or_default().insert(path.clone());
+            }
+        }
+        by_tag
+            .into_iter()
+            .map(|(tag, notes)| AliasUsage {
+                canonical: options.canonical_tag(&tag),
+                tag,
+                notes: notes.into_iter().collect(),
+            })
+            .collect()
+    });
+    TagAliasesOutput {
+        aliases: options.tag_aliases.clone(),
+        usages,
+    }
+}
+
+/// A boolean combination of tags, like `(writing AND draft) NOT archive`.
+#[derive(Debug, PartialEq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    /// Parses tags joined by `AND`, `OR` and `NOT` (upper case, so they can't be mistaken
+    /// for tags), with parentheses for grouping. `NOT` between two tags means "and not",
+    /// a leading `-` negates a tag, and a `#` before a tag is optional.
+    pub fn parse(text: &str) -> Result<TagExpr, String> {
+        let mut tokens = Vec::new();
+        for word in text.split_whitespace() {
+            let mut rest = word;
+            while let Some(after) = rest.strip_prefix('(') {
+                tokens.push("(");
+                rest = after;
+            }
+            let closing = rest.len() - rest.trim_end_matches(')').len();
+            if !rest[..rest.len() - closing].is_empty() {
+                tokens.push(&rest[..rest.len() - closing]);
+            }
+            tokens.extend(std::iter::repeat_n(")", closing));
+        }
+        let mut position = 0;
+        let expr = Self::parse_or(&tokens, &mut position)?;
+        match tokens.get(position) {
+            Some(token) => Err(format!("unexpected '{}' in tag expression", token)),
+            None => Ok(expr),
+        }
+    }
+
+    fn parse_or(tokens: &[&str], position: &mut usize) -> Result<TagExpr, String> {
+        let mut left = Self::parse_and(tokens, position)?;
+        while tokens.get(*position) == Some(&"OR") {
+            *position += 1;
+            left = TagExpr::Or(Box::new(left), Box::new(Self::parse_and(tokens, position)?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(tokens: &[&str], position: &mut usize) -> Result<TagExpr, String> {
+        let mut left = Self::parse_unary(tokens, position)?;
+        loop {
+            let right = match tokens.get(*position) {
+                Some(&"AND") => {
+                    *position += 1;
+                    Self::parse_unary(tokens, position)?
+                }
+                Some(&"NOT") => Self::parse_unary(tokens, position)?,
+                _ => return Ok(left),
+            };
+            left = TagExpr::And(Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(tokens: &[&str], position: &mut usize) -> Result<TagExpr, String> {
+        let token = tokens.get(*position).copied();
+        *position += 1;
+        match token {
+            Some("NOT") => Ok(TagExpr::Not(Box::new(Self::parse_unary(tokens, position)?))),
+            Some("(") => {
+                let expr = Self::parse_or(tokens, position)?;
+                if tokens.get(*position) != Some(&")") {
+                    return Err("expected ) in tag expression".to_string());
+                }
+                *position += 1;
+                Ok(expr)
+            }
+            Some(token) if token != ")" && !matches!(token, "AND" | "OR") => {
+                let (negated, tag) = match token.strip_prefix('-') {
+                    Some(tag) => (true, tag.trim_start_matches('#')),
+                    None => (false, token.trim_start_matches('#')),
+                };
+                if tag.is_empty() {
+                    return Err(format!("expected a tag name in '{}'", token));
+                }
+                let tag = TagExpr::Tag(tag.to_string());
+                Ok(if negated { TagExpr::Not(Box::new(tag)) } else { tag })
+            }
+            Some(token) => Err(format!("expected a tag before '{}'", token)),
+            None => Err("expected a tag at the end of the tag expression".to_string()),
+        }
+    }
+
+    /// Whether a note whose tags pass `has` matches the expression.
+    pub fn matches(&self, has: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            TagExpr::Tag(tag) => has(tag),
+            TagExpr::Not(expr) => !expr.matches(has),
+            TagExpr::And(a, b) => a.matches(has) && b.matches(has),
+            TagExpr::Or(a, b) => a.matches(has) || b.matches(has),
+        }
+    }
+}
+
+/// Whether `tag` matches a tag pattern, where `*` stands for any run of characters and `?`
+/// for one. With `include_subtags` the pattern also matches tags nested under a match.
+pub fn tag_matches(pattern: &str, tag: &str, include_subtags: bool) -> bool {
+    fn glob(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+            Some((&c, rest)) => text.split_first().is_some_and(|(&t, text)| (c == '?' || c == t) && glob(rest, text)),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let mut prefix = String::new();
+    for part in tag.split('/') {
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        prefix.push_str(part);
+        let whole = prefix.len() == tag.len();
+        if (whole || include_subtags) && glob(&pattern, &prefix.chars().collect::<Vec<_>>()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Notes whose tags match a tag expression; a single tag is the simplest one.
+pub fn find_notes_with_tag(vault_path: &PathBuf, expression: &str, include_subtags: bool, options: &ScanOptions) -> Result<Vec<String>, String> {
+    let expr = TagExpr::parse(expression)?;
+    let mut matching_files = Vec::new();
+
+    for entry in walk_vault(vault_path)
+        .into_iter()
+        .filter_entry(is_visible_entry)
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            match read_note(path, options) {
+                Some(content) => {
+                    let tags = options.note_tags(&content);
+                    let has = |pattern: &str| {
+                        let pattern = options.canonical_tag(pattern);
+                        tags.iter().any(|tag| tag_matches(&pattern, tag, include_subtags))
+                    };
+                    if expr.matches(&has) {
+                        let relative_path = path.strip_prefix(vault_path)
+                            .unwrap_or(path)
+                            .to_string_lossy()
+                            .to_string();
+                        matching_files.push(relative_path);
+                    }
+                }
+                None => {
+                    continue;
+                }
+            }
+        }
+    }
+
+    Ok(matching_files)
+}
+
+/// Replaces `old` with `new` in `content` where `scan` (the content, or a masked copy of it)
+/// has `old`, in any case, as a whole tag or as the parent of a nested one: right after text
+/// `starts_tag` accepts and before a character `ends_tag` accepts or the end. Returns the new
+/// text and the number of replacements.
+fn replace_tag(content: &str, scan: &str, old: &str, new: &str, starts_tag: impl Fn(&str) -> bool, ends_tag: impl Fn(char) -> bool) -> (String, usize) {
+    let old = old.to_lowercase();
+    let mut replaced = String::with_capacity(content.len());
+    let mut last = 0;
+    let mut count = 0;
+    for (start, _) in scan.char_indices() {
+        let end = start + old.len();
+        let matches = scan.get(start..end).is_some_and(|candidate| candidate.to_lowercase() == old);
+        if !matches || start < last || !starts_tag(&scan[..start]) || !scan[end..].chars().next().is_none_or(&ends_tag) {
+            continue;
+        }
+        replaced.push_str(&content[last..start]);
+        replaced.push_str(new);
+        last = end;
+        count += 1;
+    }
+    replaced.push_str(&content[last..]);
+    (replaced, count)
+}
+
+/// Renames a tag in a note's inline tags and in its frontmatter `tags` (or `tag`) list,
+/// nested tags under it included, so `#project/alpha` becomes `#work/alpha`.
+pub(crate) fn rename_tag_in_note(content: &str, old: &str, new: &str, syntax: &TagSyntax) -> (String, usize) {
+    let mut renamed = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut body = content;
+    if let Some(frontmatter) = extract_frontmatter(content) {
+        let end = frontmatter.len() + 8;
+        let mut in_tags = false;
+        for line in content[..end].split_inclusive('\n') {
+            let continues = line.starts_with([' ', '\t', '-']);
+            if !continues {
+                in_tags = false;
+            }
+            let value_start = match line.split_once(':') {
+                Some((key, _)) if !continues && matches!(key.trim(), "tags" | "tag") => {
+                    in_tags = true;
+                    key.len() + 1
+                }
+                _ => 0,
+            };
+            if !in_tags {
+                renamed.push_str(line);
+                continue;
+            }
+            let value = &line[value_start..];
+            let starts_tag = |before: &str| before.chars().next_back().is_none_or(|c| c.is_whitespace() || "[,\"'#".contains(c));
+            let ends_tag = |c: char| c.is_whitespace() || "],\"'/".contains(c);
+            let (value, replaced) = replace_tag(value, value, old, new, starts_tag, ends_tag);
+            renamed.push_str(&line[..value_start]);
+            renamed.push_str(&value);
+            count += replaced;
+        }
+        body = &content[end..];
+    }
+
+    let masked;
+    let scan = if syntax.ignore_code {
+        masked = mask_code(content);
+        &masked[content.len() - body.len()..]
+    } else {
+        body
+    };
+    let starts_tag = |before: &str| {
+        before
+            .strip_suffix('#')
+            .is_some_and(|before| before.chars().next_back().is_none_or(char::is_whitespace))
+    };
+    let (body, replaced) = replace_tag(body, scan, old, new, starts_tag, |c| c == '/' || !syntax.is_tag_char(c));
+    renamed.push_str(&body);
+    (renamed, count + replaced)
+}
+
+/// Renames a tag, and every tag nested under it, in every note's text and frontmatter. Tags
+/// match in any case, as in the tag listing, so `#Book` is renamed along with `#book`.
+pub fn rename_tag(vault_path: &Path, old: &str, new: &str, writer: &mut VaultWriter, options: &ScanOptions) -> Result<TagRenameOutput, String> {
+    let (old, new) = (old.trim_start_matches('#'), new.trim_start_matches('#'));
+    for tag in [old, new] {
+        if tag.is_empty() || !tag.chars().all(|c| options.tag_syntax.is_tag_char(c)) {
+            return Err(format!("'{}' is not a valid tag", tag));
+        }
+    }
+    if old == new {
+        return Err("the new tag is the same as the old one".to_string());
+    }
+
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut notes = Vec::new();
+    let mut private_notes = 0;
+    // Private notes are renamed too, so the tag doesn't live on in them
+    for path in paths {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let (renamed, count) = rename_tag_in_note(&content, old, new, &options.tag_syntax);
+        if count == 0 {
+            continue;
+        }
+        writer.write(vault_path, &path, &renamed)?;
+        if options.hides(&content) {
+            private_notes += 1;
+            continue;
+        }
+        let changes = content
+            .lines()
+            .zip(renamed.lines())
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(i, (before, after))| LineChange {
+                line: i + 1,
+                before: before.to_string(),
+                after: after.to_string(),
+            })
+            .collect();
+        notes.push(TagRenameNote {
+            path: relative_path(vault_path, &path),
+            replacements: count,
+            changes,
+        });
+    }
+
+    Ok(TagRenameOutput {
+        dry_run: writer.dry_run,
+        from: old.to_string(),
+        to: new.to_string(),
+        notes,
+        private_notes,
+    })
+}
+
+/// Writes a page per tag (nested tags become nested folders) under `folder`. Pages for
+/// tags that have disappeared are reported as stale rather than deleted.
+pub fn materialize_tags(vault_path: &Path, folder: &str, writer: &mut VaultWriter, options: &ScanOptions) -> Result<TagPagesOutput, String> {
+    let folder = folder.trim_matches('/');
+    if folder.is_empty() {
+        return Err("the tag page folder must not be the vault root".to_string());
+    }
+    let prefix = format!("{}/", folder);
+
+    let index = indexed_notes(vault_path, options);
+    let all_notes: HashSet<String> = index.notes.keys().cloned().collect();
+    let mut tagged: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (note, indexed) in &index.notes {
+        if note.starts_with(&prefix) || indexed.hidden(options) {
+            continue;
+        }
+        for tag in indexed.tags.iter().map(|t| options.canonical_tag(t)).collect::<BTreeSet<_>>() {
+            tagged.entry(tag).or_default().push(note.clone());
+        }
+    }
+
+    let mut pages = Vec::new();
+    for (tag, notes) in &tagged {
+        let mut generated = String::new();
+        for note in notes {
+            generated.push_str(&format!("- [[{}]]\n", link_target(note, &all_notes)));
+        }
+        let relative = format!("{}{}.md", prefix, tag);
+        pages.push(write_generated_note(vault_path, &relative, tag, &generated, notes.len(), writer)?);
+    }
+
+    let mut stale = Vec::new();
+    for path in markdown_files(&vault_path.join(folder)) {
+        let relative = relative_path(vault_path, &path);
+        let tag = relative[prefix.len()..].strip_suffix(".md").unwrap_or_default();
+        if !tagged.contains_key(tag) && fs::read_to_string(&path).is_ok_and(|c| c.contains(GENERATED_START)) {
+            stale.push(relative);
+        }
+    }
+    stale.sort();
+
+    Ok(TagPagesOutput {
+        folder: folder.to_string(),
+        pages,
+        stale,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_expressions_combine_with_and_or_not() {
+        let expr = TagExpr::parse("(writing AND #draft) NOT archive OR -todo").unwrap();
+        let check = |tags: &[&str]| expr.matches(&|tag: &str| tags.contains(&tag));
+        assert!(check(&["writing", "draft"]));
+        assert!(!check(&["writing", "draft", "archive", "todo"]));
+        assert!(!check(&["writing", "todo"]));
+        assert!(check(&["project/alpha"]));
+        assert_eq!(TagExpr::parse("a NOT b NOT c").unwrap(), TagExpr::parse("a AND NOT b AND NOT c").unwrap());
+        assert!(TagExpr::parse("(writing AND draft").is_err());
+        assert!(TagExpr::parse("writing OR").is_err());
+        assert!(TagExpr::parse("").is_err());
+    }
+
+    #[test]
+    fn tag_patterns_match_globs_and_subtags() {
+        assert!(tag_matches("project", "project", false));
+        assert!(!tag_matches("project", "project/alpha", false));
+        assert!(tag_matches("project", "project/alpha", true));
+        assert!(!tag_matches("project", "projects", true));
+        assert!(tag_matches("project/*", "project/alpha/x", false));
+        assert!(!tag_matches("project/*", "project", false));
+        assert!(tag_matches("*/draft", "blog/draft", false));
+        assert!(tag_matches("v?", "v2", false));
+        assert!(!tag_matches("v?", "v10", false));
+    }
+
+    #[test]
+    fn tag_rename_covers_inline_nested_and_frontmatter_tags() {
+        let note = "---\ntags: [project, projects]\ntag:\n  - \"#project/alpha\"\ntitle: project\n---\n#project #projects #project/beta a#project `#project`\n";
+        let syntax = TagSyntax {
+            ignore_code: true,
+            ..TagSyntax::default()
+        };
+        let (renamed, count) = rename_tag_in_note(note, "project", "work", &syntax);
+        assert_eq!(
+            renamed,
+            "---\ntags: [work, projects]\ntag:\n  - \"#work/alpha\"\ntitle: project\n---\n#work #projects #work/beta a#project `#project`\n"
+        );
+        assert_eq!(count, 4);
+
+        let (renamed, count) = rename_tag_in_note("---\ntags: [Book]\n---\n#Book #BOOK/fiction #bookish\n", "book", "novel", &syntax);
+        assert_eq!(renamed, "---\ntags: [novel]\n---\n#novel #novel/fiction #bookish\n");
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn tag_pages_list_tagged_notes_and_report_stale_pages() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-tag-pages-{}", std::process::id()));
+        fs::create_dir_all(root.join("Tags")).unwrap();
+        fs::write(root.join("Site.md"), "#project/web #idea").unwrap();
+        fs::write(root.join("Garden.md"), "---\ntags: [idea]\n---\n").unwrap();
+        fs::write(root.join("Tags/old.md"), format!("{}\n- [[Gone]]\n<!-- obsidian-cli:generated:end -->\n", GENERATED_START)).unwrap();
+        fs::write(root.join("Tags/notes.md"), "Written by hand").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let options = ScanOptions::default();
+        assert!(materialize_tags(&root, "/", &mut writer, &options).is_err());
+        let output = materialize_tags(&root, "Tags/", &mut writer, &options).unwrap();
+        assert_eq!(output.pages.len(), 2);
+        assert_eq!(output.stale, ["Tags/old.md"]);
+        let page = fs::read_to_string(root.join("Tags/idea.md")).unwrap();
+        assert!(page.starts_with("# idea\n") && page.contains("\n- [[Garden]]\n- [[Site]]\n"));
+        assert!(fs::read_to_string(root.join("Tags/project/web.md")).unwrap().contains("\n- [[Site]]\n"));
+
+        if let Some(cache) = crate::index::cache_dir(&root) {
+            let _ = fs::remove_dir_all(cache);
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
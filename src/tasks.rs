@@ -0,0 +1,230 @@
+//! Tasks in checkbox lists, with the Tasks plugin's dates and priorities.
+
+use crate::config::{ScanOptions, WeekStart};
+use crate::dates::{format_date, parse_iso_date, today};
+use crate::scan::{markdown_files, read_note, relative_path};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TaskSort {
+    Due,
+    Scheduled,
+    Start,
+    Priority,
+}
+
+#[derive(Serialize, Clone)]
+pub struct TaskItem {
+    path: String,
+    line: usize,
+    status: char,
+    pub(crate) done: bool,
+    text: String,
+    priority: Option<&'static str>,
+    due: Option<String>,
+    scheduled: Option<String>,
+    start: Option<String>,
+    completed: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TasksOutput {
+    pub tasks: Vec<TaskItem>,
+}
+
+#[derive(Serialize)]
+struct AgendaDay {
+    date: String,
+    /// The first day of the week this day is in
+    week: String,
+    tasks: Vec<TaskItem>,
+}
+
+#[derive(Serialize)]
+pub struct AgendaOutput {
+    today: String,
+    overdue: Vec<TaskItem>,
+    days: Vec<AgendaDay>,
+}
+
+const TASK_PRIORITIES: &[(&str, &str)] = &[
+    ("🔺", "highest"),
+    ("⏫", "high"),
+    ("🔼", "medium"),
+    ("🔽", "low"),
+    ("⏬", "lowest"),
+];
+
+/// Sort rank matching the Tasks plugin, where tasks without a priority sit between medium and low.
+fn priority_rank(priority: Option<&str>) -> u8 {
+    match priority {
+        Some("highest") => 0,
+        Some("high") => 1,
+        Some("medium") => 2,
+        None => 3,
+        Some("low") => 4,
+        _ => 5,
+    }
+}
+
+fn parse_task(line: &str) -> Option<(char, String)> {
+    static TASK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s+\[(.)\]\s+(.*)$").unwrap());
+    let cap = TASK_REGEX.captures(line)?;
+    Some((cap[1].chars().next()?, cap[2].to_string()))
+}
+
+pub(crate) fn extract_tasks(content: &str, path: &str) -> Vec<TaskItem> {
+    static DATE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(📅|⏳|🛫|✅)\u{FE0F}?\s*(\d{4}-\d{2}-\d{2})").unwrap());
+    let mut tasks = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let Some((status, body)) = parse_task(line) else {
+            continue;
+        };
+
+        let mut task = TaskItem {
+            path: path.to_string(),
+            line: index + 1,
+            status,
+            done: matches!(status, 'x' | 'X' | '-'),
+            text: body.clone(),
+            priority: TASK_PRIORITIES.iter().find(|(emoji, _)| body.contains(emoji)).map(|(_, name)| *name),
+            due: None,
+            scheduled: None,
+            start: None,
+            completed: None,
+        };
+        for cap in DATE_REGEX.captures_iter(&body) {
+            let date = Some(cap[2].to_string());
+            match &cap[1] {
+                "📅" => task.due = date,
+                "⏳" => task.scheduled = date,
+                "🛫" => task.start = date,
+                _ => task.completed = date,
+            }
+        }
+
+        // Keep only the description, the metadata already has its own fields
+        let metadata_start = body
+            .char_indices()
+            .find(|(_, c)| matches!(c, '📅' | '⏳' | '🛫' | '✅' | '➕' | '🔁') || TASK_PRIORITIES.iter().any(|(emoji, _)| emoji.starts_with(*c)))
+            .map_or(body.len(), |(i, _)| i);
+        task.text = body[..metadata_start].trim().to_string();
+        tasks.push(task);
+    }
+
+    tasks
+}
+
+pub fn collect_tasks(vault_path: &Path, include_done: bool, options: &ScanOptions) -> Vec<TaskItem> {
+    let mut tasks = Vec::new();
+    for path in markdown_files(vault_path) {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let relative = relative_path(vault_path, &path);
+        tasks.extend(extract_tasks(&content, &relative).into_iter().filter(|t| include_done || !t.done));
+    }
+    tasks.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    tasks
+}
+
+pub fn sort_tasks(tasks: &mut [TaskItem], key: TaskSort) {
+    let date = |task: &TaskItem| -> Option<i64> {
+        let field = match key {
+            TaskSort::Due => &task.due,
+            TaskSort::Scheduled => &task.scheduled,
+            TaskSort::Start => &task.start,
+            TaskSort::Priority => return None,
+        };
+        field.as_deref().and_then(parse_iso_date)
+    };
+
+    // Stable sort keeps path/line order among equal keys; undated tasks go last
+    match key {
+        TaskSort::Priority => tasks.sort_by_key(|t| priority_rank(t.priority)),
+        _ => tasks.sort_by_key(|t| (date(t).is_none(), date(t), priority_rank(t.priority))),
+    }
+}
+
+pub fn build_agenda(tasks: Vec<TaskItem>, days: u32, week_start: WeekStart) -> AgendaOutput {
+    let today = today();
+    let mut overdue = Vec::new();
+    let mut by_day: BTreeMap<i64, Vec<TaskItem>> = (0..days as i64).map(|d| (today + d, Vec::new())).collect();
+
+    for task in tasks.into_iter().filter(|t| !t.done) {
+        let due = task.due.as_deref().and_then(parse_iso_date);
+        let day = due.or_else(|| task.scheduled.as_deref().and_then(parse_iso_date));
+        if due.is_some_and(|d| d < today) {
+            overdue.push(task);
+        } else if let Some(bucket) = day.and_then(|d| by_day.get_mut(&d)) {
+            bucket.push(task);
+        }
+    }
+
+    for bucket in by_day.values_mut() {
+        bucket.sort_by_key(|t| priority_rank(t.priority));
+    }
+    sort_tasks(&mut overdue, TaskSort::Due);
+
+    AgendaOutput {
+        today: format_date(today),
+        overdue,
+        days: by_day
+            .into_iter()
+            .map(|(day, tasks)| AgendaDay {
+                date: format_date(day),
+                week: format_date(week_start.week_of(day)),
+                tasks,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tasks_plugin_metadata_is_parsed() {
+        let tasks = extract_tasks("- [ ] Ship it ⏫ 📅 2026-01-05 ⏳ 2026-01-03\n- [x] Done ✅ 2026-01-01\n* not a task", "a.md");
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].text, "Ship it");
+        assert_eq!(tasks[0].priority, Some("high"));
+        assert_eq!(tasks[0].due.as_deref(), Some("2026-01-05"));
+        assert_eq!(tasks[0].scheduled.as_deref(), Some("2026-01-03"));
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].completed.as_deref(), Some("2026-01-01"));
+    }
+
+    #[test]
+    fn tasks_sort_by_date_then_priority_and_fill_the_agenda() {
+        let day = |offset: i64| format_date(today() + offset);
+        let content = format!(
+            "- [ ] Late 📅 {}\n- [ ] Soon 🔽 📅 {}\n- [ ] Urgent 🔺 📅 {}\n- [ ] Planned ⏳ {}\n- [ ] Someday\n- [x] Finished 📅 {}\n",
+            day(-2),
+            day(1),
+            day(1),
+            day(2),
+            day(1)
+        );
+        let texts = |tasks: &[TaskItem]| tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+        let mut tasks = extract_tasks(&content, "a.md");
+        sort_tasks(&mut tasks, TaskSort::Due);
+        assert_eq!(texts(&tasks), ["Late", "Urgent", "Finished", "Soon", "Planned", "Someday"]);
+        sort_tasks(&mut tasks, TaskSort::Priority);
+        assert_eq!(texts(&tasks)[0], "Urgent");
+
+        let agenda = build_agenda(tasks, 3, WeekStart::Monday);
+        assert_eq!(agenda.today, day(0));
+        assert_eq!(texts(&agenda.overdue), ["Late"]);
+        let days: Vec<(String, Vec<String>)> = agenda.days.iter().map(|d| (d.date.clone(), texts(&d.tasks))).collect();
+        assert_eq!(days, [(day(0), vec![]), (day(1), vec!["Urgent".to_string(), "Soon".to_string()]), (day(2), vec!["Planned".to_string()])]);
+        assert_eq!(agenda.days[0].week, format_date(WeekStart::Monday.week_of(today())));
+    }
+}
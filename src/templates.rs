@@ -0,0 +1,624 @@
+//! Templates: listing and checking them, creating notes from them and applying them.
+
+use crate::config::{Config, ScanOptions};
+use crate::dates::{civil_from_days, unix_now};
+use crate::links::{collect_all_links, is_same_note, link_target};
+use crate::parse::{Frontmatter, anchor_matches_heading, extract_frontmatter, extract_headings, mask_code};
+use crate::properties::frontmatter_entries;
+use crate::query;
+use crate::scan::{join_path, markdown_files, normalize_path, note_stem, read_note, relative_path};
+use crate::search::{note_facts, rank_notes, search_terms};
+use crate::workspace::read_obsidian_json;
+use crate::write::VaultWriter;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TemplatesAction {
+    List,
+    Check,
+    Leftovers,
+}
+
+#[derive(Serialize)]
+struct TemplateInfo {
+    path: String,
+    variables: Vec<String>,
+    references: usize,
+}
+
+#[derive(Serialize)]
+pub struct TemplatesOutput {
+    templates: Vec<TemplateInfo>,
+    unreferenced: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct TemplateIssue {
+    template: String,
+    line: usize,
+    message: String,
+}
+
+#[derive(Serialize)]
+pub struct TemplateCheckOutput {
+    pub issues: Vec<TemplateIssue>,
+}
+
+#[derive(Serialize)]
+struct TemplateLeftover {
+    line: usize,
+    /// The variable, like "date" or "tp.file.title"
+    placeholder: String,
+    /// The unreplaced text as it appears in the note
+    text: String,
+}
+
+#[derive(Serialize)]
+struct NoteLeftovers {
+    path: String,
+    count: usize,
+    leftovers: Vec<TemplateLeftover>,
+}
+
+#[derive(Serialize)]
+pub struct TemplateLeftoversOutput {
+    total: usize,
+    by_placeholder: BTreeMap<String, usize>,
+    notes: Vec<NoteLeftovers>,
+}
+
+#[derive(Serialize)]
+pub struct AppliedTemplate {
+    path: String,
+    /// Frontmatter keys added from the template
+    keys: Vec<String>,
+    /// Headings of the sections added from the template
+    sections: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ApplyTemplateOutput {
+    dry_run: bool,
+    template: String,
+    /// Notes that were missing some of the template's keys or sections
+    notes: Vec<AppliedTemplate>,
+    /// Matching notes left alone because their frontmatter isn't valid YAML
+    skipped: Vec<String>,
+}
+
+const CORE_TEMPLATE_VARIABLES: &[&str] = &["title", "date", "time"];
+const TEMPLATER_MODULES: &[&str] = &[
+    "app", "config", "date", "file", "frontmatter", "hooks", "obsidian", "system", "user", "web",
+];
+
+fn template_files(vault_path: &Path, config: &Config) -> Vec<PathBuf> {
+    let folder = vault_path.join(&config.templates_folder);
+    if !folder.is_dir() {
+        return Vec::new();
+    }
+    let mut files = markdown_files(&folder);
+    files.sort();
+    files
+}
+
+fn extract_template_variables(content: &str) -> Vec<String> {
+    let mut variables = BTreeSet::new();
+
+    // Core Templates plugin: {{title}}, {{date:YYYY-MM-DD}}
+    let core_regex = Regex::new(r"\{\{\s*([^}:]+?)\s*(?::[^}]*)?\}\}").unwrap();
+    for cap in core_regex.captures_iter(content) {
+        variables.insert(cap[1].to_string());
+    }
+
+    // Templater: <% tp.date.now() %>
+    let templater_regex = Regex::new(r"\btp\.[a-zA-Z_]+(?:\.[a-zA-Z_]+)*").unwrap();
+    for m in templater_regex.find_iter(content) {
+        variables.insert(m.as_str().to_string());
+    }
+
+    variables.into_iter().collect()
+}
+
+/// Unreplaced template syntax in a note: core `{{date}}`-style variables and Templater
+/// `<% ... %>` commands outside code. Returns (line, variable, text) for each.
+fn find_leftovers(content: &str) -> Vec<(usize, String, String)> {
+    // Templates often fill in frontmatter, so only code is skipped
+    let mut masked = mask_code(content);
+    if let Some(frontmatter) = extract_frontmatter(content) {
+        let end = frontmatter.len() + 8;
+        masked.replace_range(..end, &content[..end]);
+    }
+    static CORE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*([^}:]+?)\s*(?::[^}]*)?\}\}").unwrap());
+    static TEMPLATER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<%[-_*]?(.*?)[-_]?%>").unwrap());
+    static MODULE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\btp\.[a-zA-Z_]+(?:\.[a-zA-Z_]+)*").unwrap());
+
+    let mut found: Vec<(usize, String, String)> = Vec::new();
+    for cap in CORE_REGEX.captures_iter(&masked) {
+        let whole = cap.get(0).unwrap();
+        found.push((whole.start(), cap[1].to_string(), content[whole.range()].to_string()));
+    }
+    for cap in TEMPLATER_REGEX.captures_iter(&masked) {
+        let whole = cap.get(0).unwrap();
+        let variable = MODULE_REGEX.find(&cap[1]).map_or("templater", |m| m.as_str());
+        found.push((whole.start(), variable.to_string(), content[whole.range()].to_string()));
+    }
+    found.sort_by_key(|(start, _, _)| *start);
+    found
+        .into_iter()
+        .map(|(start, variable, text)| (content[..start].matches('\n').count() + 1, variable, text))
+        .collect()
+}
+
+/// Notes outside the templates folder that still contain template syntax, a sign the
+/// template was inserted but never filled in.
+pub fn find_template_leftovers(vault_path: &Path, config: &Config, options: &ScanOptions) -> Result<TemplateLeftoversOutput, String> {
+    let templates: HashSet<PathBuf> = template_files(vault_path, config).into_iter().collect();
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+
+    let mut by_placeholder = BTreeMap::new();
+    let mut notes = Vec::new();
+    for path in paths.iter().filter(|p| !templates.contains(*p)) {
+        let Some(content) = read_note(path, options) else {
+            continue;
+        };
+        let leftovers: Vec<TemplateLeftover> = find_leftovers(&content)
+            .into_iter()
+            .map(|(line, placeholder, text)| TemplateLeftover { line, placeholder, text })
+            .collect();
+        if leftovers.is_empty() {
+            continue;
+        }
+        for leftover in &leftovers {
+            *by_placeholder.entry(leftover.placeholder.clone()).or_insert(0) += 1;
+        }
+        notes.push(NoteLeftovers {
+            path: relative_path(vault_path, path),
+            count: leftovers.len(),
+            leftovers,
+        });
+    }
+
+    Ok(TemplateLeftoversOutput {
+        total: by_placeholder.values().sum(),
+        by_placeholder,
+        notes,
+    })
+}
+
+fn check_template(content: &str) -> Vec<(usize, String)> {
+    let mut issues = Vec::new();
+    let core_regex = Regex::new(r"\{\{\s*([^}:]+?)\s*(?::[^}]*)?\}\}").unwrap();
+    let module_regex = Regex::new(r"\btp\.([a-zA-Z_]+)").unwrap();
+
+    // Templater tags may span lines, so track open tags across the whole file
+    let mut open_tag: Option<usize> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+
+        if line.matches("{{").count() != line.matches("}}").count() {
+            issues.push((line_number, "unbalanced {{ }} braces".to_string()));
+        }
+        for cap in core_regex.captures_iter(line) {
+            let name = &cap[1];
+            if !CORE_TEMPLATE_VARIABLES.contains(&name) {
+                issues.push((line_number, format!("unknown template variable {{{{{}}}}}", name)));
+            }
+        }
+
+        let mut rest = line;
+        loop {
+            match open_tag {
+                None => match rest.find("<%") {
+                    Some(pos) => {
+                        open_tag = Some(line_number);
+                        rest = &rest[pos + 2..];
+                    }
+                    None => break,
+                },
+                Some(_) => match rest.find("%>") {
+                    Some(pos) => {
+                        let command = &rest[..pos];
+                        if command.matches('(').count() != command.matches(')').count() {
+                            issues.push((line_number, "unbalanced parentheses in Templater command".to_string()));
+                        }
+                        if command.matches('"').count() % 2 != 0 {
+                            issues.push((line_number, "unterminated string in Templater command".to_string()));
+                        }
+                        open_tag = None;
+                        rest = &rest[pos + 2..];
+                    }
+                    None => break,
+                },
+            }
+        }
+
+        for cap in module_regex.captures_iter(line) {
+            if !TEMPLATER_MODULES.contains(&&cap[1]) {
+                issues.push((line_number, format!("unknown Templater module tp.{}", &cap[1])));
+            }
+        }
+    }
+
+    if let Some(line_number) = open_tag {
+        issues.push((line_number, "unterminated Templater tag <%".to_string()));
+    }
+
+    issues
+}
+
+pub fn list_templates(vault_path: &Path, config: &Config, options: &ScanOptions) -> Result<TemplatesOutput, String> {
+    let (links, _) = collect_all_links(vault_path, options)?;
+    let mut templates = Vec::new();
+
+    for path in template_files(vault_path, config) {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let relative = relative_path(vault_path, &path);
+        let normalized = normalize_path(vault_path, &relative);
+        let references = links
+            .iter()
+            .filter(|l| l.exists && l.source != relative && is_same_note(&normalize_path(vault_path, &l.target), &normalized))
+            .count();
+
+        templates.push(TemplateInfo {
+            path: relative,
+            variables: extract_template_variables(&content),
+            references,
+        });
+    }
+
+    let unreferenced = templates
+        .iter()
+        .filter(|t| t.references == 0)
+        .map(|t| t.path.clone())
+        .collect();
+
+    Ok(TemplatesOutput {
+        templates,
+        unreferenced,
+    })
+}
+
+pub fn check_templates(vault_path: &Path, config: &Config) -> Result<Vec<TemplateIssue>, String> {
+    let mut issues = Vec::new();
+
+    for path in template_files(vault_path, config) {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let template = relative_path(vault_path, &path);
+        for (line, message) in check_template(&content) {
+            issues.push(TemplateIssue {
+                template: template.clone(),
+                line,
+                message,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// The sections of a note body, from each heading up to the next, as (heading, text).
+/// Text before the first heading isn't part of any section.
+fn note_sections(body: &str) -> Vec<(String, &str)> {
+    static HEADING_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^ {0,3}#{1,6}[ \t]+(.+?)(?:[ \t]+#+)?[ \t]*$").unwrap());
+    let masked = mask_code(body);
+    let headings: Vec<(usize, String)> = HEADING_REGEX
+        .captures_iter(&masked)
+        .map(|cap| (cap.get(0).unwrap().start(), body[cap.get(1).unwrap().range()].to_string()))
+        .collect();
+    let ends = headings.iter().skip(1).map(|(start, _)| *start).chain([body.len()]);
+    headings.iter().zip(ends).map(|((start, heading), end)| (heading.clone(), &body[*start..end])).collect()
+}
+
+/// A template by vault path, or by path or name within the templates folder.
+fn find_template(vault_path: &Path, config: &Config, name: &str) -> Result<PathBuf, String> {
+    let templates_folder = vault_path.join(&config.templates_folder);
+    let wanted = name.strip_suffix(".md").unwrap_or(name);
+    template_files(vault_path, config)
+        .into_iter()
+        .find(|path| {
+            let in_folder = relative_path(&templates_folder, path);
+            [relative_path(vault_path, path), in_folder.clone()]
+                .iter()
+                .any(|name| name.strip_suffix(".md") == Some(wanted))
+                || note_stem(&in_folder) == wanted
+        })
+        .or_else(|| Some(vault_path.join(format!("{}.md", wanted))).filter(|path| path.is_file()))
+        .ok_or_else(|| format!("template not found: {}", name))
+}
+
+/// Fills in the core Templates plugin's `{{title}}`, `{{date}}` and `{{time}}`, with
+/// `{{date:FORMAT}}`-style formats using `YYYY`, `MM`, `DD`, `HH`, `mm` and `ss`. Dates and
+/// times are in UTC; other variables are left as they are.
+fn fill_template(template: &str, title: &str, now: u64) -> String {
+    static VARIABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{\s*(title|date|time)\s*(?::([^}]*))?\}\}").unwrap());
+    static TOKEN_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new("YYYY|MM|DD|HH|mm|ss").unwrap());
+    let (year, month, day) = civil_from_days((now / 86_400) as i64);
+    let seconds = now % 86_400;
+    VARIABLE_REGEX
+        .replace_all(template, |cap: &regex::Captures| {
+            let format = match (&cap[1], cap.get(2)) {
+                ("title", _) => return title.to_string(),
+                (_, Some(format)) => format.as_str().trim(),
+                ("date", None) => "YYYY-MM-DD",
+                _ => "HH:mm",
+            };
+            TOKEN_REGEX
+                .replace_all(format, |token: &regex::Captures| match &token[0] {
+                    "YYYY" => format!("{:04}", year),
+                    "MM" => format!("{:02}", month),
+                    "DD" => format!("{:02}", day),
+                    "HH" => format!("{:02}", seconds / 3600),
+                    "mm" => format!("{:02}", seconds / 60 % 60),
+                    _ => format!("{:02}", seconds % 60),
+                })
+                .into_owned()
+        })
+        .into_owned()
+}
+
+/// Link targets of the `limit` notes most relevant to a new note's title, ranked as by
+/// `search --ranked`, for a "Related" section. Templates are never suggested.
+pub fn related_links(vault_path: &Path, config: &Config, title: &str, limit: usize, options: &ScanOptions) -> Result<Vec<String>, String> {
+    if search_terms(title).is_empty() {
+        return Ok(Vec::new());
+    }
+    let all_notes: HashSet<String> = markdown_files(vault_path).iter().map(|p| relative_path(vault_path, p)).collect();
+    let templates_folder = format!("{}/", config.templates_folder.trim_matches('/'));
+    Ok(rank_notes(vault_path, title, options)?
+        .into_iter()
+        .filter(|note| !note.path.starts_with(&templates_folder))
+        .take(limit)
+        .map(|note| link_target(&note.path, &all_notes))
+        .collect())
+}
+
+/// Creates the note `title` in `folder`, or else in Obsidian's folder for new notes (or the
+/// vault root), with the template's content when one is given and a "Related" section
+/// linking to `related` when it isn't empty. Returns its vault path.
+pub fn new_note(
+    vault_path: &Path,
+    config: &Config,
+    title: &str,
+    folder: Option<&str>,
+    template: Option<&str>,
+    related: &[String],
+    writer: &mut VaultWriter,
+) -> Result<String, String> {
+    let title = title.trim();
+    if title.is_empty() || title.starts_with('.') || title.contains(['*', '"', '\\', '/', '<', '>', ':', '|', '?', '#', '^', '[', ']']) {
+        return Err(format!("'{}' can't be used as a note name", title));
+    }
+    let folder = match folder {
+        Some(folder) => folder.to_string(),
+        None => read_obsidian_json(vault_path, "app.json")?
+            .filter(|app| app.get("newFileLocation").and_then(|v| v.as_str()) == Some("folder"))
+            .and_then(|app| app.get("newFileFolderPath").and_then(|v| v.as_str()).map(str::to_string))
+            .unwrap_or_default(),
+    };
+    let relative = join_path(folder.trim_matches('/'), &format!("{}.md", title));
+    let path = vault_path.join(&relative);
+    if path.exists() {
+        return Err(format!("{} already exists", relative));
+    }
+    let mut content = match template {
+        Some(template) => {
+            let template = find_template(vault_path, config, template)?;
+            let text = fs::read_to_string(&template).map_err(|e| format!("failed to read {}: {}", template.display(), e))?;
+            fill_template(&text, title, unix_now())
+        }
+        None => String::new(),
+    };
+    if !related.is_empty() {
+        if !content.is_empty() {
+            content = format!("{}\n\n", content.trim_end());
+        }
+        content.push_str("## Related\n");
+        for link in related {
+            content.push_str(&format!("- [[{}]]\n", link));
+        }
+    }
+    writer.write(vault_path, &path, &content)?;
+    Ok(relative)
+}
+
+/// Adds a template's frontmatter keys and sections to every note matching `condition` (a
+/// query `WHERE` clause) that lacks them, for giving old notes the structure of new ones.
+/// Keys and headings the note already has are left as they are, whatever their value or
+/// content; missing keys go at the end of the frontmatter and missing sections at the end
+/// of the note, except a heading that is just the note's name. Template variables are
+/// filled in as by [`fill_template`].
+pub fn apply_template(
+    vault_path: &Path,
+    config: &Config,
+    template: &str,
+    condition: Option<&str>,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<ApplyTemplateOutput, String> {
+    let condition = condition.map(query::parse_condition).transpose()?;
+    let templates_folder = vault_path.join(&config.templates_folder);
+    let template_path = find_template(vault_path, config, template)?;
+    let template_text = fs::read_to_string(&template_path).map_err(|e| format!("failed to read {}: {}", template_path.display(), e))?;
+    let template = relative_path(vault_path, &template_path);
+    let now = unix_now();
+
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let mut notes = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let relative = relative_path(vault_path, &path);
+        if path == template_path || path.starts_with(&templates_folder) {
+            continue;
+        }
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        if let Some(condition) = &condition
+            && !condition.holds(&note_facts(&path, relative.clone(), &content, options))
+        {
+            continue;
+        }
+        let frontmatter = extract_frontmatter(&content);
+        if frontmatter.as_deref().is_some_and(|fm| Frontmatter::parse(fm).is_err()) {
+            skipped.push(relative);
+            continue;
+        }
+
+        let filled = fill_template(&template_text, note_stem(&relative), now);
+        let (template_frontmatter, template_body) = match extract_frontmatter(&filled) {
+            Some(fm) => (fm.clone(), filled[fm.len() + 8..].to_string()),
+            None => (String::new(), filled.clone()),
+        };
+
+        let existing: HashSet<String> = frontmatter_entries(frontmatter.as_deref().unwrap_or_default()).into_iter().map(|(key, _)| key).collect();
+        let (keys, lines): (Vec<String>, Vec<String>) = frontmatter_entries(&template_frontmatter)
+            .into_iter()
+            .filter(|(key, _)| !existing.contains(key))
+            .unzip();
+        let headings = extract_headings(&content);
+        let sections: Vec<(String, &str)> = note_sections(&template_body)
+            .into_iter()
+            // A heading naming the note is its title, not a section to add at the end
+            .filter(|(heading, _)| !anchor_matches_heading(heading, &headings) && heading.trim() != note_stem(&relative))
+            .collect();
+        if keys.is_empty() && sections.is_empty() {
+            continue;
+        }
+
+        let mut updated = match (&frontmatter, lines.is_empty()) {
+            (_, true) => content.clone(),
+            (Some(fm), false) => {
+                let kept = fm.trim_end_matches('\n');
+                let joined = if kept.trim().is_empty() { lines.join("\n") } else { format!("{}\n{}", kept, lines.join("\n")) };
+                format!("---\n{}\n---\n{}", joined, &content[fm.len() + 9..])
+            }
+            (None, false) => format!("---\n{}\n---\n{}", lines.join("\n"), content),
+        };
+        for (_, text) in &sections {
+            let kept = updated.trim_end_matches('\n').len();
+            updated.truncate(kept);
+            if !updated.is_empty() {
+                updated.push_str("\n\n");
+            }
+            updated.push_str(text.trim_end());
+            updated.push('\n');
+        }
+        writer.write(vault_path, &path, &updated)?;
+        notes.push(AppliedTemplate {
+            path: relative,
+            keys,
+            sections: sections.into_iter().map(|(heading, _)| heading).collect(),
+        });
+    }
+
+    Ok(ApplyTemplateOutput {
+        dry_run: writer.dry_run,
+        template,
+        notes,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::{format_date, today};
+
+    #[test]
+    fn template_leftovers_skip_code() {
+        let content = "---\ncreated: {{date}}\n---\n# {{title}}\nCreated {{date:YYYY-MM-DD}}\n`{{time}}`\n<% tp.file.creation_date() %> <%* tR += x %>";
+        let found: Vec<(usize, String)> = find_leftovers(content).into_iter().map(|(line, variable, _)| (line, variable)).collect();
+        assert_eq!(found, [(2, "date".into()), (4, "title".into()), (5, "date".into()), (7, "tp.file.creation_date".into()), (7, "templater".into())]);
+    }
+
+    #[test]
+    fn new_notes_can_link_to_related_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-new-related-{}", std::process::id()));
+        fs::create_dir_all(root.join("Templates")).unwrap();
+        fs::write(root.join("Templates/Runtime.md"), "runtime runtime async\n").unwrap();
+        fs::write(root.join("Tokio.md"), "An async runtime, the runtime most crates use\n").unwrap();
+        fs::write(root.join("Cooking.md"), "Bread\n").unwrap();
+        fs::write(root.join("Smol.md"), "A small async runtime\n").unwrap();
+
+        let config = Config::default();
+        let related = related_links(&root, &config, "Async runtime choices", 5, &ScanOptions::default()).unwrap();
+        assert_eq!(related, ["Smol", "Tokio"]);
+        let mut writer = VaultWriter::new(false, false);
+        let path = new_note(&root, &config, "Async runtime choices", None, Some("Runtime"), &related, &mut writer).unwrap();
+        assert_eq!(fs::read_to_string(root.join(path)).unwrap(), "runtime runtime async\n\n## Related\n- [[Smol]]\n- [[Tokio]]\n");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn applying_a_template_adds_only_missing_keys_and_sections() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-apply-template-{}", std::process::id()));
+        fs::create_dir_all(root.join("Templates")).unwrap();
+        fs::write(root.join("Templates/Person.md"), "---\ntags: [person]\nbirthday:\nmet: {{date}}\n---\n# {{title}}\n## Contact\n- Email:\n\n## Notes\n").unwrap();
+        fs::write(root.join("Alice.md"), "---\ntags: [person]\n---\nNice.\n\n## Notes\nFrom work.\n").unwrap();
+        fs::write(root.join("Bob.md"), "---\ntags: [person]\nbirthday: 1990-01-01\n---\n").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let condition = Some("tag = 'person' AND missing(frontmatter.birthday)");
+        let output = apply_template(&root, &Config::default(), "Person", condition, &mut writer, &ScanOptions::default()).unwrap();
+        assert_eq!(output.notes.len(), 1);
+        let today = format_date(today());
+        assert_eq!(
+            fs::read_to_string(root.join("Alice.md")).unwrap(),
+            format!("---\ntags: [person]\nbirthday:\nmet: {}\n---\nNice.\n\n## Notes\nFrom work.\n\n## Contact\n- Email:\n", today)
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn template_variables_are_filled_in() {
+        // 2024-03-05 14:07:09 UTC
+        let now = 1_709_647_629;
+        let filled = fill_template("# {{title}}\n{{date}} {{ time }} {{date:DD/MM/YYYY HH:mm:ss}} {{tp.file.title}}", "Standup", now);
+        assert_eq!(filled, "# Standup\n2024-03-05 14:07 05/03/2024 14:07:09 {{tp.file.title}}");
+    }
+
+    #[test]
+    fn templates_are_listed_with_references_and_checked() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-templates-check-{}", std::process::id()));
+        fs::create_dir_all(root.join("Templates")).unwrap();
+        fs::write(root.join("Templates/Daily.md"), "# {{title}}\n{{date:YYYY}} {{weather}}\n").unwrap();
+        fs::write(root.join("Templates/Meeting.md"), "<% tp.date.now(\"YYYY\" %>\n<% tp.nope.x %>\n<% tp.file.title\n").unwrap();
+        fs::write(root.join("Home.md"), "Made from [[Templates/Daily]]\n").unwrap();
+
+        let config = Config::default();
+        let output = list_templates(&root, &config, &ScanOptions::default()).unwrap();
+        let listed: Vec<(&str, usize)> = output.templates.iter().map(|t| (t.path.as_str(), t.references)).collect();
+        assert_eq!(listed, [("Templates/Daily.md", 1), ("Templates/Meeting.md", 0)]);
+        assert_eq!(output.unreferenced, ["Templates/Meeting.md"]);
+
+        let issues: Vec<(String, usize, String)> =
+            check_templates(&root, &config).unwrap().into_iter().map(|i| (i.template, i.line, i.message)).collect();
+        let daily = |line, message: &str| ("Templates/Daily.md".to_string(), line, message.to_string());
+        let meeting = |line, message: &str| ("Templates/Meeting.md".to_string(), line, message.to_string());
+        assert_eq!(
+            issues,
+            [
+                daily(2, "unknown template variable {{weather}}"),
+                meeting(1, "unbalanced parentheses in Templater command"),
+                meeting(2, "unknown Templater module tp.nope"),
+                meeting(3, "unterminated Templater tag <%"),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+//! External links: listing, checking and titling them.
+
+use crate::config::ScanOptions;
+use crate::parse::mask_code;
+use crate::scan::{markdown_files, read_note, relative_path};
+use crate::write::VaultWriter;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// An `http(s)` URL in a note, with what checking it found when it was checked.
+#[derive(Serialize)]
+pub struct ExternalLink {
+    pub source: String,
+    pub line: usize,
+    pub url: String,
+    /// The HTTP status the server answered with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Why the URL couldn't be reached, for timeouts, DNS and connection failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unreachable or answering with an error status (400 or above)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ExternalLinksOutput {
+    pub links: Vec<ExternalLink>,
+    /// With --check, the number of distinct URLs checked and how many are dead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_count: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct EnrichedUrl {
+    url: String,
+    title: String,
+}
+
+#[derive(Serialize)]
+struct FailedUrl {
+    url: String,
+    error: String,
+}
+
+#[derive(Serialize)]
+struct EnrichedNote {
+    path: String,
+    links: usize,
+}
+
+#[derive(Serialize)]
+pub struct UrlEnrichOutput {
+    dry_run: bool,
+    urls: Vec<EnrichedUrl>,
+    failed: Vec<FailedUrl>,
+    notes: Vec<EnrichedNote>,
+}
+
+/// Byte ranges of URLs written out as plain text, skipping code, frontmatter, markdown
+/// links, `<autolinks>` and URLs inside wikilinks.
+fn bare_urls(content: &str) -> Vec<(usize, usize)> {
+    static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://[^\s<>\[\]()`|]+").unwrap());
+    let masked = mask_code(content);
+    let mut urls = Vec::new();
+    for m in URL_REGEX.find_iter(&masked) {
+        let before = &masked[..m.start()];
+        if before.ends_with("](") || before.ends_with('<') || before.ends_with('(') {
+            continue;
+        }
+        if before.rfind("[[").is_some_and(|open| !before[open..].contains("]]")) {
+            continue;
+        }
+        let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+        urls.push((m.start(), m.start() + url.len()));
+    }
+    urls
+}
+
+fn decode_html_entities(text: &str) -> String {
+    static ENTITY_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|amp|lt|gt|quot|apos|nbsp);").unwrap());
+    ENTITY_REGEX
+        .replace_all(text, |cap: &regex::Captures| {
+            let entity = &cap[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity[1..].parse().ok(),
+                }
+                .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| cap[0].to_string(), |c| c.to_string())
+        })
+        .into_owned()
+}
+
+/// Title of an HTML page, ready to use as markdown link text.
+fn page_title(html: &str) -> Option<String> {
+    let title_regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    let title = decode_html_entities(title_regex.captures(html)?.get(1)?.as_str());
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.replace('[', "\\[").replace(']', "\\]"))
+}
+
+/// Largest page body read when looking for a title
+const MAX_PAGE_BYTES: u64 = 1024 * 1024;
+
+fn fetch_title(agent: &ureq::Agent, url: &str) -> Result<String, String> {
+    let response = agent.get(url).call().map_err(|e| e.to_string())?;
+    let mut body = Vec::new();
+    std::io::Read::read_to_end(&mut std::io::Read::take(response.into_reader(), MAX_PAGE_BYTES), &mut body)
+        .map_err(|e| e.to_string())?;
+    page_title(&String::from_utf8_lossy(&body)).ok_or_else(|| "page has no title".to_string())
+}
+
+/// Runs `fetch` for each of `urls` on `jobs` threads, each taking the next unfetched URL.
+fn fetch_all<T: Send>(
+    urls: &[String],
+    jobs: usize,
+    timeout: Duration,
+    fetch: impl Fn(&ureq::Agent, &str) -> Result<T, String> + Sync,
+) -> Vec<Result<T, String>> {
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let results = std::sync::Mutex::new((0..urls.len()).map(|_| Err(String::new())).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, urls.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let Some(url) = urls.get(index) else {
+                        break;
+                    };
+                    let result = fetch(&agent, url);
+                    if let Ok(mut results) = results.lock() {
+                        results[index] = result;
+                    }
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap_or_default()
+}
+
+/// The HTTP status `url` answers a HEAD request with, retried as a GET for servers that
+/// don't allow HEAD. Error statuses are returned too; only failing to get an answer is an error.
+fn check_url(agent: &ureq::Agent, url: &str) -> Result<u16, String> {
+    let status = |result: Result<ureq::Response, ureq::Error>| match result {
+        Ok(response) => Ok(response.status()),
+        Err(ureq::Error::Status(status, _)) => Ok(status),
+        Err(e) => Err(e.to_string()),
+    };
+    match status(agent.head(url).call())? {
+        405 | 501 => status(agent.get(url).call()),
+        answered => Ok(answered),
+    }
+}
+
+/// Every `http(s)` URL written in the note, bare, in Markdown links or in `<autolinks>`,
+/// with its line, skipping code and frontmatter.
+fn external_urls(content: &str) -> Vec<(usize, String)> {
+    static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://[^\s<>\[\]()`|\x22]+").unwrap());
+    let masked = mask_code(content);
+    let mut urls = Vec::new();
+    for (index, line) in masked.lines().enumerate() {
+        for m in URL_REGEX.find_iter(line) {
+            let before = &line[..m.start()];
+            if before.rfind("[[").is_some_and(|open| !before[open..].contains("]]")) {
+                continue;
+            }
+            let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+            urls.push((index + 1, url.to_string()));
+        }
+    }
+    urls
+}
+
+/// Every external URL in the vault's notes. With `check`, each distinct URL is requested
+/// once, `jobs` at a time, to find the dead ones.
+pub fn external_links(vault_path: &Path, check: bool, jobs: usize, timeout: Duration, options: &ScanOptions) -> ExternalLinksOutput {
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut links = Vec::new();
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let source = relative_path(vault_path, &path);
+        for (line, url) in external_urls(&content) {
+            links.push(ExternalLink {
+                source: source.clone(),
+                line,
+                url,
+                status: None,
+                error: None,
+                dead: None,
+            });
+        }
+    }
+    if !check {
+        return ExternalLinksOutput { links, checked: None, dead_count: None };
+    }
+
+    let unique: Vec<String> = links.iter().map(|link| link.url.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+    let results: HashMap<&str, Result<u16, String>> = unique.iter().map(String::as_str).zip(fetch_all(&unique, jobs, timeout, check_url)).collect();
+    for link in &mut links {
+        match &results[link.url.as_str()] {
+            Ok(status) => link.status = Some(*status),
+            Err(error) => link.error = Some(error.clone()),
+        }
+        link.dead = Some(link.status.is_none_or(|status| status >= 400));
+    }
+    let dead_count = results.values().filter(|result| !result.as_ref().is_ok_and(|status| *status < 400)).count();
+    ExternalLinksOutput {
+        links,
+        checked: Some(unique.len()),
+        dead_count: Some(dead_count),
+    }
+}
+
+pub fn enrich_urls(vault_path: &Path, jobs: usize, timeout: Duration, writer: &mut VaultWriter, options: &ScanOptions) -> Result<UrlEnrichOutput, String> {
+    let mut files = markdown_files(vault_path);
+    files.sort();
+    let mut notes = Vec::new();
+    let mut unique: BTreeSet<String> = BTreeSet::new();
+    for path in files {
+        let Some(content) = read_note(&path, options) else {
+            continue;
+        };
+        let urls = bare_urls(&content);
+        if urls.is_empty() {
+            continue;
+        }
+        unique.extend(urls.iter().map(|&(start, end)| content[start..end].to_string()));
+        notes.push((path, content, urls));
+    }
+
+    let unique: Vec<String> = unique.into_iter().collect();
+    let mut titles: HashMap<String, String> = HashMap::new();
+    let mut failed = Vec::new();
+    for (url, result) in unique.iter().zip(fetch_all(&unique, jobs, timeout, fetch_title)) {
+        match result {
+            Ok(title) => {
+                titles.insert(url.clone(), title);
+            }
+            Err(error) => failed.push(FailedUrl { url: url.clone(), error }),
+        }
+    }
+
+    let mut enriched = Vec::new();
+    for (path, content, urls) in notes {
+        let mut updated = content.clone();
+        let mut links = 0;
+        for &(start, end) in urls.iter().rev() {
+            if let Some(title) = titles.get(&content[start..end]) {
+                updated.replace_range(start..end, &format!("[{}]({})", title, &content[start..end]));
+                links += 1;
+            }
+        }
+        if links > 0 {
+            writer.write(vault_path, &path, &updated)?;
+            enriched.push(EnrichedNote {
+                path: relative_path(vault_path, &path),
+                links,
+            });
+        }
+    }
+
+    let mut urls: Vec<EnrichedUrl> = titles.into_iter().map(|(url, title)| EnrichedUrl { url, title }).collect();
+    urls.sort_by(|a, b| a.url.cmp(&b.url));
+
+    Ok(UrlEnrichOutput {
+        dry_run: writer.dry_run,
+        urls,
+        failed,
+        notes: enriched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_urls_skip_existing_links_and_code() {
+        let content = "See https://a.example/x. and [b](https://b.example) <https://c.example>\n`https://d.example` [[https://e.example]] https://f.example/y?q=1";
+        let urls: Vec<&str> = bare_urls(content).into_iter().map(|(start, end)| &content[start..end]).collect();
+        assert_eq!(urls, ["https://a.example/x", "https://f.example/y?q=1"]);
+        let external: Vec<(usize, String)> = external_urls(content);
+        let expected = [(1, "https://a.example/x"), (1, "https://b.example"), (1, "https://c.example"), (2, "https://f.example/y?q=1")];
+        assert_eq!(external, expected.map(|(line, url)| (line, url.to_string())));
+        assert_eq!(page_title("<html><TITLE>\n  A &amp; B [draft]\n</TITLE>").as_deref(), Some("A & B \\[draft\\]"));
+    }
+}
@@ -0,0 +1,221 @@
+//! Typed access to a vault for tools that embed vault analysis instead of running the binary.
+
+use crate::config::{Config, ScanOptions, load_config, resolve_vault_root};
+use crate::parse::{Frontmatter, extract_links_with_embeds, extract_markdown_links, percent_decode, split_link_anchor};
+use crate::scan::{
+    NoteResolver, find_note_path, markdown_files, note_aliases, note_stem, read_note, relative_path, resolve_markdown_link,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// An Obsidian vault, scanned with the options from its `.obsidian-cli.toml`.
+pub struct Vault {
+    root: PathBuf,
+    config: Config,
+    options: ScanOptions,
+}
+
+/// A note in a vault, read once when it was listed.
+pub struct Note {
+    path: String,
+    content: String,
+    /// Every note in the vault, for resolving this note's links
+    resolver: Arc<NoteResolver>,
+    options: Arc<ScanOptions>,
+}
+
+/// A link from one note to another: a `[[wikilink]]` or a Markdown `[text](Note.md)` link,
+/// either of them possibly an `!embed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    /// Vault-relative path of the note the link is in
+    pub source: String,
+    /// The link as written, without heading or alias; Markdown destinations are decoded
+    pub target: String,
+    /// The heading in `[[Note#Heading]]` or `[text](Note.md#Heading)`
+    pub heading: Option<String>,
+    /// Vault-relative path of the note the link points to, if it exists
+    pub resolved: Option<String>,
+    /// Whether the note embeds the target rather than linking to it
+    pub embed: bool,
+}
+
+/// A tag and how many notes (or, for a single note, how many times) it is used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub count: usize,
+}
+
+impl Vault {
+    /// Opens the vault containing `path`, walking up to the folder with `.obsidian` the same
+    /// way the CLI does, and loads its config.
+    pub fn open(path: impl AsRef<Path>) -> Result<Vault, String> {
+        let root = resolve_vault_root(path.as_ref(), &[], false)?;
+        let config = load_config(&root, None)?;
+        let options = ScanOptions::from_config(&config);
+        Ok(Vault { root, config, options })
+    }
+
+    /// Opens the vault at exactly `root` with the given options.
+    pub fn with_options(root: impl Into<PathBuf>, config: Config, options: ScanOptions) -> Vault {
+        Vault { root: root.into(), config, options }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn options(&self) -> &ScanOptions {
+        &self.options
+    }
+
+    /// Every note the scan options let through, sorted by path.
+    pub fn notes(&self) -> Vec<Note> {
+        let mut paths = markdown_files(&self.root);
+        paths.sort();
+        let mut resolver = NoteResolver::new(paths.iter().map(|p| relative_path(&self.root, p)));
+        let contents: Vec<(String, String)> = paths
+            .iter()
+            .filter_map(|path| Some((relative_path(&self.root, path), read_note(path, &self.options)?)))
+            .collect();
+        for (path, content) in &contents {
+            resolver.add_aliases(path, note_aliases(content));
+        }
+        let resolver = Arc::new(resolver);
+        let options = Arc::new(self.options.clone());
+
+        contents
+            .into_iter()
+            .map(|(path, content)| Note {
+                path,
+                content,
+                resolver: Arc::clone(&resolver),
+                options: Arc::clone(&options),
+            })
+            .collect()
+    }
+
+    /// Finds a note by path or by name, as a `[[link]]` to it would.
+    pub fn note(&self, name: &str) -> Option<Note> {
+        let notes = self.notes();
+        let visible: HashSet<String> = notes.iter().map(|n| n.path.clone()).collect();
+        let path = find_note_path(&self.root, name, &visible)?;
+        notes.into_iter().find(|n| n.path == path)
+    }
+
+    /// Every link between notes in the vault.
+    pub fn links(&self) -> Vec<Link> {
+        self.notes().iter().flat_map(Note::links).collect()
+    }
+
+    /// Every tag in the vault with the number of notes using it, sorted by name.
+    pub fn tags(&self) -> Vec<Tag> {
+        let mut counts = BTreeMap::new();
+        for note in self.notes() {
+            for tag in note.tags() {
+                *counts.entry(tag.name).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().map(|(name, count)| Tag { name, count }).collect()
+    }
+}
+
+impl Note {
+    /// Vault-relative path, like `Projects/Plan.md`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The note's name as used in links: its file name without `.md`.
+    pub fn name(&self) -> &str {
+        note_stem(&self.path)
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// The note's frontmatter properties; empty when it has none or they aren't valid YAML.
+    pub fn frontmatter(&self) -> Frontmatter {
+        Frontmatter::of(&self.content)
+    }
+
+    /// The note's outgoing links: its wikilinks in the order they appear, then its Markdown
+    /// links in the order they appear.
+    pub fn links(&self) -> Vec<Link> {
+        let wikilinks = extract_links_with_embeds(&self.content).into_iter().map(|(link, embed)| {
+            let (target, heading) = split_link_anchor(&link);
+            let resolved = if target.is_empty() {
+                Some(self.path.clone())
+            } else {
+                self.resolver.resolve(target).cloned()
+            };
+            self.link(target, heading, resolved, embed)
+        });
+        let markdown_links = extract_markdown_links(&self.content).into_iter().map(|(destination, embed)| {
+            let resolved = resolve_markdown_link(&self.path, &destination, &self.resolver);
+            let destination = percent_decode(&destination);
+            let (target, heading) = split_link_anchor(&destination);
+            self.link(target, heading, resolved, embed)
+        });
+        wikilinks.chain(markdown_links).collect()
+    }
+
+    fn link(&self, target: &str, heading: Option<&str>, resolved: Option<String>, embed: bool) -> Link {
+        Link {
+            source: self.path.clone(),
+            target: target.to_string(),
+            heading: heading.map(str::to_string),
+            resolved,
+            embed,
+        }
+    }
+
+    /// The note's inline and frontmatter tags with how often each is used, sorted by name.
+    /// Aliases from the config are counted as the tag they stand for.
+    pub fn tags(&self) -> Vec<Tag> {
+        let mut counts = BTreeMap::new();
+        for tag in self.options.note_tags(&self.content) {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+        counts.into_iter().map(|(name, count)| Tag { name, count }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn vault_api_walks_notes_links_and_tags() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-vault-api-{}", std::process::id()));
+        fs::create_dir_all(root.join(".obsidian")).unwrap();
+        fs::create_dir_all(root.join("Projects")).unwrap();
+        fs::write(root.join("Home.md"), "#idea See [[Plan#Goals|goals]] and [[Missing]], ![[Plan]] or [the plan](Projects/Plan.md#Goals)").unwrap();
+        fs::write(root.join("Projects/Plan.md"), "---\ntags: [idea, work]\n---\nBack to [[Home]]").unwrap();
+
+        let vault = Vault::open(&root).unwrap();
+        let paths: Vec<String> = vault.notes().iter().map(|n| n.path().to_string()).collect();
+        assert_eq!(paths, ["Home.md", "Projects/Plan.md"]);
+        let home = vault.note("Home").unwrap();
+        assert_eq!(home.name(), "Home");
+        let links = home.links();
+        assert_eq!(links[0].target, "Plan");
+        assert_eq!(links[0].heading.as_deref(), Some("Goals"));
+        assert_eq!(links[0].resolved.as_deref(), Some("Projects/Plan.md"));
+        assert_eq!(links[1].resolved, None);
+        assert!(links[2].embed && !links[0].embed);
+        assert_eq!((links[3].target.as_str(), links[3].heading.as_deref()), ("Projects/Plan.md", Some("Goals")));
+        assert_eq!(links[3].resolved.as_deref(), Some("Projects/Plan.md"));
+        assert_eq!(vault.tags(), [Tag { name: "idea".into(), count: 2 }, Tag { name: "work".into(), count: 1 }]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
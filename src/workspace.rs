@@ -0,0 +1,188 @@
+//! Obsidian's own state in `.obsidian/`: bookmarks and recently opened notes.
+
+use crate::config::ScanOptions;
+use crate::dates::file_modified;
+use crate::scan::{hidden_notes, markdown_files, read_note, relative_path};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub struct Bookmark {
+    #[serde(rename = "type")]
+    kind: String,
+    group: Option<String>,
+    title: Option<String>,
+    path: Option<String>,
+    subpath: Option<String>,
+    query: Option<String>,
+    exists: bool,
+}
+
+#[derive(Serialize)]
+pub struct BookmarksOutput {
+    pub bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Serialize)]
+pub struct RecentNote {
+    path: String,
+    modified: String,
+}
+
+#[derive(Serialize)]
+pub struct RecentOutput {
+    pub source: &'static str,
+    pub recent: Vec<RecentNote>,
+}
+
+const RECENT_LIMIT: usize = 20;
+
+pub(crate) fn read_obsidian_json(vault_path: &Path, name: &str) -> Result<Option<serde_json::Value>, String> {
+    let path = vault_path.join(".obsidian").join(name);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("invalid JSON in {}: {}", path.display(), e))
+}
+
+fn flatten_bookmarks(vault_path: &Path, items: &[serde_json::Value], group: Option<&str>, hidden: &HashSet<String>, out: &mut Vec<Bookmark>) {
+    let field = |item: &serde_json::Value, key: &str| item.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+
+    for item in items {
+        let kind = field(item, "type").unwrap_or_else(|| "file".to_string());
+        let title = field(item, "title");
+
+        if kind == "group" {
+            let nested = match (group, &title) {
+                (Some(parent), Some(title)) => Some(format!("{}/{}", parent, title)),
+                (None, Some(title)) => Some(title.clone()),
+                (parent, None) => parent.map(|p| p.to_string()),
+            };
+            if let Some(children) = item.get("items").and_then(|v| v.as_array()) {
+                flatten_bookmarks(vault_path, children, nested.as_deref(), hidden, out);
+            }
+            continue;
+        }
+
+        let path = field(item, "path");
+        if path.as_ref().is_some_and(|p| hidden.contains(p)) {
+            continue;
+        }
+        let exists = path.as_ref().is_none_or(|p| vault_path.join(p).exists());
+        out.push(Bookmark {
+            kind,
+            group: group.map(|g| g.to_string()),
+            title,
+            path,
+            subpath: field(item, "subpath"),
+            query: field(item, "query"),
+            exists,
+        });
+    }
+}
+
+pub fn collect_bookmarks(vault_path: &Path, options: &ScanOptions) -> Result<Vec<Bookmark>, String> {
+    // Obsidian 1.2 replaced starred.json with bookmarks.json; both share the items layout
+    let json = match read_obsidian_json(vault_path, "bookmarks.json")? {
+        Some(json) => json,
+        None => match read_obsidian_json(vault_path, "starred.json")? {
+            Some(json) => json,
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    let mut bookmarks = Vec::new();
+    if let Some(items) = json.get("items").and_then(|v| v.as_array()) {
+        flatten_bookmarks(vault_path, items, None, &hidden_notes(vault_path, options), &mut bookmarks);
+    }
+    Ok(bookmarks)
+}
+
+pub fn collect_recent(vault_path: &Path, from_workspace: bool, options: &ScanOptions) -> Result<Vec<RecentNote>, String> {
+    if from_workspace {
+        let workspace = read_obsidian_json(vault_path, "workspace.json")?
+            .ok_or_else(|| "no .obsidian/workspace.json found in the vault".to_string())?;
+        let files = workspace
+            .get("lastOpenFiles")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        // lastOpenFiles is already ordered from most to least recently opened
+        let hidden = hidden_notes(vault_path, options);
+        return Ok(files
+            .iter()
+            .filter_map(|f| f.as_str())
+            .filter(|f| !hidden.contains(*f))
+            .take(RECENT_LIMIT)
+            .map(|f| RecentNote {
+                path: f.to_string(),
+                modified: file_modified(&vault_path.join(f)),
+            })
+            .collect());
+    }
+
+    let mut notes: Vec<(std::time::SystemTime, PathBuf)> = markdown_files(vault_path)
+        .into_iter()
+        .filter(|p| read_note(p, options).is_some())
+        .filter_map(|p| fs::metadata(&p).and_then(|m| m.modified()).ok().map(|m| (m, p)))
+        .collect();
+    notes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    Ok(notes
+        .into_iter()
+        .take(RECENT_LIMIT)
+        .map(|(modified, path)| RecentNote {
+            path: relative_path(vault_path, &path),
+            modified: format!("{:?}", modified),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_flatten_groups_and_recent_files_skip_hidden_notes() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-workspace-{}", std::process::id()));
+        fs::create_dir_all(root.join(".obsidian")).unwrap();
+        fs::write(root.join("Home.md"), "home").unwrap();
+        fs::write(root.join("Secret.md"), "#private").unwrap();
+        fs::write(
+            root.join(".obsidian/bookmarks.json"),
+            r##"{"items": [
+                {"type": "file", "path": "Home.md"},
+                {"type": "group", "title": "Work", "items": [
+                    {"type": "group", "title": "Old", "items": [{"type": "file", "path": "Gone.md", "subpath": "#Top"}]},
+                    {"type": "search", "query": "tag:#todo"},
+                    {"type": "file", "path": "Secret.md"}
+                ]}
+            ]}"##,
+        )
+        .unwrap();
+        fs::write(root.join(".obsidian/workspace.json"), r#"{"lastOpenFiles": ["Secret.md", "Home.md", "Gone.md"]}"#).unwrap();
+
+        let options = ScanOptions::default();
+        let bookmarks = collect_bookmarks(&root, &options).unwrap();
+        let listed: Vec<(&str, Option<&str>, Option<&str>, bool)> =
+            bookmarks.iter().map(|b| (b.kind.as_str(), b.group.as_deref(), b.path.as_deref(), b.exists)).collect();
+        assert_eq!(
+            listed,
+            [("file", None, Some("Home.md"), true), ("file", Some("Work/Old"), Some("Gone.md"), false), ("search", Some("Work"), None, true)]
+        );
+        assert_eq!(bookmarks[1].subpath.as_deref(), Some("#Top"));
+
+        let recent: Vec<String> = collect_recent(&root, true, &options).unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(recent, ["Home.md", "Gone.md"]);
+        let options = ScanOptions { include_private: true, ..options };
+        assert_eq!(collect_recent(&root, true, &options).unwrap()[0].path, "Secret.md");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
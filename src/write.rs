@@ -0,0 +1,908 @@
+//! Changes to vault files: the dry-run aware writer, renames, moves, deletes and merges.
+
+use crate::config::ScanOptions;
+use crate::links::{link_target, relative_link, relative_to_vault, rewrite_markdown_links, rewrite_wikilinks};
+use crate::parse::{self, extract_links_from_file, extract_markdown_links, split_link_anchor};
+use crate::scan::{
+    NoteResolver, attachment_files, hidden_notes, join_path, markdown_files, note_stem, relative_path,
+    resolve_markdown_link, vault_files,
+};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::hash::{DefaultHasher, Hasher};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeConflict {
+    /// Number the copy, as Obsidian does: `Plan 1.md`
+    Suffix,
+    /// Copy it into a folder named after the source vault (or --prefix)
+    Folder,
+}
+
+#[derive(Serialize)]
+struct DuplicateGroup {
+    canonical: String,
+    duplicates: Vec<String>,
+    size: u64,
+}
+
+#[derive(Serialize)]
+pub struct DedupeOutput {
+    dry_run: bool,
+    groups: Vec<DuplicateGroup>,
+    rewritten_notes: Vec<String>,
+    bytes_reclaimed: u64,
+}
+
+#[derive(Serialize)]
+pub struct MergedFile {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+pub struct MergeOutput {
+    dry_run: bool,
+    /// Files copied to the same path in the destination
+    copied: Vec<String>,
+    /// Files whose name the destination already uses for something else, with where they went
+    renamed: Vec<MergedFile>,
+    /// Files the destination already has byte for byte, which were skipped
+    identical: Vec<MergedFile>,
+    /// Copied notes whose links were rewritten to follow renamed files
+    rewritten_notes: Vec<String>,
+}
+
+/// Version of the `--plan-only` change set format.
+pub const CHANGE_SET_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub(crate) struct LineChange {
+    pub(crate) line: usize,
+    pub(crate) before: String,
+    pub(crate) after: String,
+}
+
+#[derive(Serialize)]
+pub struct RenameOutput {
+    dry_run: bool,
+    from: String,
+    to: String,
+    /// Notes whose wikilinks or Markdown links were rewritten, under their paths after the rename
+    rewritten_notes: Vec<String>,
+    links_rewritten: usize,
+}
+
+#[derive(Serialize)]
+pub struct DeleteOutput {
+    dry_run: bool,
+    note: String,
+    /// False when the note was kept because other notes still link to it
+    pub deleted: bool,
+    /// Notes linking to the note, before any were redirected
+    pub backlinks: Vec<String>,
+    /// Private notes linking to the note, left out of `backlinks`
+    pub private_backlinks: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirected_to: Option<String>,
+    rewritten_notes: Vec<String>,
+}
+
+/// A replacement of the original file's bytes `start..end`.
+#[derive(Serialize, Debug, PartialEq)]
+struct TextEdit {
+    start: usize,
+    end: usize,
+    before: String,
+    after: String,
+}
+
+#[derive(Serialize)]
+pub struct FileChange {
+    path: String,
+    /// "create", "modify", "move", "copy" (from outside the vault) or "trash"
+    action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination: Option<String>,
+    edits: Vec<TextEdit>,
+}
+
+#[derive(Serialize)]
+pub struct ChangeSet<'a> {
+    pub changes: &'a [FileChange],
+}
+
+/// Where `relative` goes in `.trash`: its own path, numbered like `Plan 1.md` when an earlier
+/// delete already left a note there, so trashing the same path twice keeps both copies.
+fn trash_destination(vault_path: &Path, relative: &str) -> String {
+    let (stem, extension) = match relative.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !stem.ends_with('/') && !extension.contains('/') => (stem, format!(".{}", extension)),
+        _ => (relative, String::new()),
+    };
+    let mut destination = format!(".trash/{}", relative);
+    let mut number = 0;
+    while vault_path.join(&destination).exists() {
+        number += 1;
+        destination = format!(".trash/{} {}{}", stem, number, extension);
+    }
+    destination
+}
+
+fn move_to_trash(vault_path: &Path, path: &Path, destination: &str) -> Result<(), String> {
+    let destination = vault_path.join(destination);
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::rename(path, &destination).map_err(|e| format!("failed to move {} to trash: {}", path.display(), e))
+}
+
+/// All file changes made by write commands go through here, so `--dry-run` and
+/// `--plan-only` behave the same for every command.
+pub struct VaultWriter {
+    pub dry_run: bool,
+    pub plan_only: bool,
+    pub changes: Vec<FileChange>,
+}
+
+impl VaultWriter {
+    pub fn new(dry_run: bool, plan_only: bool) -> Self {
+        VaultWriter {
+            dry_run: dry_run || plan_only,
+            plan_only,
+            changes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn write(&mut self, vault_path: &Path, path: &Path, content: &str) -> Result<(), String> {
+        let existing = fs::read_to_string(path).ok();
+        self.changes.push(FileChange {
+            path: relative_path(vault_path, path),
+            action: if existing.is_some() { "modify" } else { "create" },
+            destination: None,
+            edits: text_edits(existing.as_deref().unwrap_or_default(), content),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(path, content).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+    }
+
+    /// Moves a file, removing folders it leaves empty.
+    pub(crate) fn rename(&mut self, vault_path: &Path, from: &Path, to: &Path) -> Result<(), String> {
+        self.changes.push(FileChange {
+            path: relative_path(vault_path, from),
+            action: "move",
+            destination: Some(relative_path(vault_path, to)),
+            edits: Vec::new(),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::rename(from, to).map_err(|e| format!("failed to move {} to {}: {}", from.display(), to.display(), e))?;
+        let mut folder = from.parent();
+        while let Some(dir) = folder.filter(|dir| *dir != vault_path && fs::remove_dir(dir).is_ok()) {
+            folder = dir.parent();
+        }
+        Ok(())
+    }
+
+    /// Copies a file from outside the vault into it.
+    fn copy(&mut self, vault_path: &Path, from: &Path, to: &Path) -> Result<(), String> {
+        self.changes.push(FileChange {
+            path: from.display().to_string(),
+            action: "copy",
+            destination: Some(relative_path(vault_path, to)),
+            edits: Vec::new(),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| format!("failed to copy {} to {}: {}", from.display(), to.display(), e))
+    }
+
+    fn trash(&mut self, vault_path: &Path, path: &Path) -> Result<(), String> {
+        let relative = relative_path(vault_path, path);
+        let destination = trash_destination(vault_path, &relative);
+        self.changes.push(FileChange {
+            destination: Some(destination.clone()),
+            path: relative,
+            action: "trash",
+            edits: Vec::new(),
+        });
+        if self.dry_run {
+            return Ok(());
+        }
+        move_to_trash(vault_path, path, &destination)
+    }
+}
+
+/// Line-granular edits turning `before` into `after`. Rewrites that keep the line count
+/// get one edit per run of changed lines; anything else becomes a single edit spanning
+/// the changed middle of the file.
+fn text_edits(before: &str, after: &str) -> Vec<TextEdit> {
+    let old: Vec<&str> = before.split_inclusive('\n').collect();
+    let new: Vec<&str> = after.split_inclusive('\n').collect();
+    let offsets: Vec<usize> = old
+        .iter()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start)
+        })
+        .chain([before.len()])
+        .collect();
+    let edit = |old_lines: std::ops::Range<usize>, new_lines: std::ops::Range<usize>| TextEdit {
+        start: offsets[old_lines.start],
+        end: offsets[old_lines.end],
+        before: old[old_lines].concat(),
+        after: new[new_lines].concat(),
+    };
+
+    if old.len() == new.len() {
+        let mut edits = Vec::new();
+        let mut i = 0;
+        while i < old.len() {
+            if old[i] == new[i] {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < old.len() && old[i] != new[i] {
+                i += 1;
+            }
+            edits.push(edit(start..i, start..i));
+        }
+        return edits;
+    }
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    vec![edit(prefix..old.len() - suffix, prefix..new.len() - suffix)]
+}
+
+fn hash_file(path: &Path) -> Result<(u64, Vec<u8>), String> {
+    let bytes = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&bytes);
+    Ok((hasher.finish(), bytes))
+}
+
+pub fn dedupe_attachments(vault_path: &Path, writer: &mut VaultWriter) -> Result<DedupeOutput, String> {
+    let attachments = attachment_files(vault_path);
+
+    // Only files sharing a size can be identical, so avoid hashing everything else
+    let mut by_size: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for path in &attachments {
+        if let Ok(metadata) = fs::metadata(path) {
+            by_size.entry(metadata.len()).or_default().push(path.clone());
+        }
+    }
+
+    let mut identical: Vec<(u64, Vec<String>)> = Vec::new();
+    for (size, paths) in by_size {
+        if paths.len() < 2 {
+            continue;
+        }
+        let mut by_hash: HashMap<u64, Vec<(String, Vec<u8>)>> = HashMap::new();
+        for path in paths {
+            let (hash, bytes) = hash_file(&path)?;
+            by_hash.entry(hash).or_default().push((relative_path(vault_path, &path), bytes));
+        }
+        for (_, mut files) in by_hash {
+            files.sort_by(|a, b| a.0.cmp(&b.0));
+            // Guard against hash collisions by comparing the actual bytes
+            let first = files[0].1.clone();
+            let group: Vec<String> = files
+                .into_iter()
+                .filter(|(_, bytes)| *bytes == first)
+                .map(|(path, _)| path)
+                .collect();
+            if group.len() > 1 {
+                identical.push((size, group));
+            }
+        }
+    }
+
+    // Embeds may reference an attachment by bare file name or by vault path
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for path in &attachments {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        *name_counts.entry(name).or_insert(0) += 1;
+    }
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    let link_form = |path: &str| {
+        let name = file_name(path);
+        if name_counts.get(&name) == Some(&1) { name } else { path.to_string() }
+    };
+
+    let mut replacements: HashMap<String, String> = HashMap::new();
+    let mut groups = Vec::new();
+    let mut bytes_reclaimed = 0;
+    for (size, group) in identical {
+        let canonical = group[0].clone();
+        let duplicates = group[1..].to_vec();
+        for duplicate in &duplicates {
+            replacements.insert(duplicate.clone(), link_form(&canonical));
+            if name_counts.get(&file_name(duplicate)) == Some(&1) {
+                replacements.insert(file_name(duplicate), link_form(&canonical));
+            }
+        }
+        bytes_reclaimed += size * duplicates.len() as u64;
+        groups.push(DuplicateGroup {
+            canonical,
+            duplicates,
+            size,
+        });
+    }
+    groups.sort_by(|a, b| a.canonical.cmp(&b.canonical));
+
+    let mut rewritten_notes = Vec::new();
+    for path in markdown_files(vault_path) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let (rewritten, count) = rewrite_wikilinks(&content, |target| replacements.get(target.trim()).cloned());
+        if count == 0 {
+            continue;
+        }
+        writer.write(vault_path, &path, &rewritten)?;
+        rewritten_notes.push(relative_path(vault_path, &path));
+    }
+    rewritten_notes.sort();
+
+    for group in &groups {
+        for duplicate in &group.duplicates {
+            writer.trash(vault_path, &vault_path.join(duplicate))?;
+        }
+    }
+
+    Ok(DedupeOutput {
+        dry_run: writer.dry_run,
+        groups,
+        rewritten_notes,
+        bytes_reclaimed,
+    })
+}
+
+/// Copies every note and attachment of the vault at `source` into the one at `destination`.
+/// Files the destination already has byte for byte, at the same path or under the same name,
+/// are skipped. A file whose name the destination uses for different content is numbered or
+/// moved into a folder, per `conflict`, and the copied notes' wikilinks and embeds to it are
+/// rewritten to match. The destination's own notes are left as they are.
+pub fn merge_vaults(source: &Path, destination: &Path, conflict: MergeConflict, prefix: Option<&str>, writer: &mut VaultWriter) -> Result<MergeOutput, String> {
+    let absolute = |path: &Path| std::path::absolute(path).map_err(|e| format!("can't resolve {}: {}", path.display(), e));
+    let (from, to) = (absolute(source)?, absolute(destination)?);
+    if from.starts_with(&to) || to.starts_with(&from) {
+        return Err("the vaults can't be inside one another".to_string());
+    }
+    let prefix = match prefix {
+        Some(prefix) => prefix.trim_matches('/').to_string(),
+        None => from.file_name().map_or("Merged".to_string(), |name| name.to_string_lossy().into_owned()),
+    };
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+
+    let existing = vault_files(destination);
+    let mut taken: HashSet<String> = existing.iter().cloned().collect();
+    let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+    for path in &existing {
+        by_name.entry(file_name(path)).or_default().push(path.clone());
+    }
+    let mut name_counts: HashMap<String, usize> = by_name.iter().map(|(name, paths)| (name.clone(), paths.len())).collect();
+
+    let files = vault_files(source);
+    let mut placed: Vec<(String, String)> = Vec::new();
+    let mut renamed = Vec::new();
+    let mut identical = Vec::new();
+    for path in &files {
+        let bytes = fs::read(source.join(path)).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let name = file_name(path);
+        let same_name = by_name.get(&name).map(Vec::as_slice).unwrap_or_default();
+        let same_content = std::iter::once(path)
+            .filter(|path| taken.contains(*path))
+            .chain(same_name)
+            .find(|other| fs::read(destination.join(other)).is_ok_and(|other| other == bytes));
+        if let Some(other) = same_content {
+            identical.push(MergedFile {
+                from: path.clone(),
+                to: other.clone(),
+            });
+            continue;
+        }
+        if same_name.is_empty() && !taken.contains(path) {
+            placed.push((path.clone(), path.clone()));
+        } else {
+            let (folder, name) = match (conflict, path.rsplit_once('/')) {
+                (MergeConflict::Folder, Some((folder, name))) => (format!("{}/{}/", prefix, folder), name),
+                (MergeConflict::Folder, None) => (format!("{}/", prefix), path.as_str()),
+                (MergeConflict::Suffix, Some((folder, name))) => (format!("{}/", folder), name),
+                (MergeConflict::Suffix, None) => (String::new(), path.as_str()),
+            };
+            let (stem, extension) = match name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+                _ => (name, String::new()),
+            };
+            let mut candidate = match conflict {
+                MergeConflict::Folder => format!("{}{}", folder, name),
+                MergeConflict::Suffix => format!("{}{} 1{}", folder, stem, extension),
+            };
+            let mut number = 1;
+            while taken.contains(&candidate) || (conflict == MergeConflict::Suffix && name_counts.contains_key(&file_name(&candidate))) {
+                number += 1;
+                candidate = format!("{}{} {}{}", folder, stem, number, extension);
+            }
+            renamed.push(MergedFile {
+                from: path.clone(),
+                to: candidate.clone(),
+            });
+            placed.push((path.clone(), candidate));
+        }
+        let (_, placed_at) = placed.last().unwrap();
+        taken.insert(placed_at.clone());
+        *name_counts.entry(file_name(placed_at)).or_insert(0) += 1;
+    }
+
+    // Links to files that end up somewhere else follow them, by name when it is unique
+    let link_form = |path: &str| {
+        let name = file_name(path);
+        let target = if name_counts.get(&name) == Some(&1) { name } else { path.to_string() };
+        match target.strip_suffix(".md") {
+            Some(note) => note.to_string(),
+            None => target,
+        }
+    };
+    let moved: HashMap<&str, String> = renamed
+        .iter()
+        .chain(&identical)
+        .filter(|file| file.from != file.to)
+        .map(|file| (file.from.as_str(), link_form(&file.to)))
+        .collect();
+    let resolver = NoteResolver::new(files.iter().filter(|path| path.ends_with(".md"))).read_aliases(source);
+    let mut attachment_names: HashMap<String, Vec<&str>> = HashMap::new();
+    for path in files.iter().filter(|path| !path.ends_with(".md")) {
+        attachment_names.entry(file_name(path)).or_default().push(path);
+    }
+    let replacement = |target: &str| -> Option<String> {
+        let linked = match file_name(target).rsplit_once('.') {
+            Some((_, extension)) if extension != "md" => match attachment_names.get(&file_name(target)).map(Vec::as_slice) {
+                Some([only]) if *only == target || !target.contains('/') => *only,
+                Some(paths) => *paths.iter().find(|path| **path == target)?,
+                None => return None,
+            },
+            // Links by alias keep working, since the aliases are copied with the note
+            _ => resolver.resolve(target).filter(|note| note_stem(note) == note_stem(target))?,
+        };
+        moved.get(linked).filter(|replacement| *replacement != target).cloned()
+    };
+
+    let mut copied = Vec::new();
+    let mut rewritten_notes = Vec::new();
+    for (path, placed_at) in placed {
+        let target = destination.join(&placed_at);
+        match fs::read_to_string(source.join(&path)) {
+            Ok(content) if path.ends_with(".md") => {
+                let (updated, count) = rewrite_wikilinks(&content, replacement);
+                if count > 0 {
+                    rewritten_notes.push(placed_at.clone());
+                }
+                writer.write(destination, &target, &updated)?;
+            }
+            _ => writer.copy(destination, &source.join(&path), &target)?,
+        }
+        if path == placed_at {
+            copied.push(path);
+        }
+    }
+
+    Ok(MergeOutput {
+        dry_run: writer.dry_run,
+        copied,
+        renamed,
+        identical,
+        rewritten_notes,
+    })
+}
+
+/// Moves a note to `new` (a vault-relative path; `.md` is added when missing) and rewrites
+/// every wikilink, embed and Markdown link to it across the vault, as Obsidian does when a
+/// note is renamed. Links by bare name stay that way when the new name is unique, and links
+/// by alias are left alone. The moved note's own relative Markdown links are updated for its
+/// new folder.
+pub fn rename_note(vault_path: &Path, old: &str, new: &str, writer: &mut VaultWriter, options: &ScanOptions) -> Result<RenameOutput, String> {
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let notes: Vec<String> = paths.iter().map(|p| relative_path(vault_path, p)).collect();
+    let old_notes = NoteResolver::new(&notes).read_aliases(vault_path);
+    let from = old_notes
+        .resolve_path(old)
+        .or_else(|| old_notes.resolve(old))
+        .cloned()
+        .ok_or_else(|| format!("note not found: {}", old))?;
+    let to = relative_to_vault("", new)
+        .filter(|to| !to.is_empty())
+        .ok_or_else(|| format!("{} is outside the vault", new))?;
+    let to = if to.ends_with(".md") { to } else { format!("{}.md", to) };
+    if to == from {
+        return Err(format!("{} already has that name", from));
+    }
+    if vault_path.join(&to).exists() {
+        return Err(format!("{} already exists", to));
+    }
+    let new_notes = NoteResolver::new(notes.iter().map(|note| if *note == from { &to } else { note }));
+    let moved = |note: &str| if note == from { to.clone() } else { note.to_string() };
+    let folder = |note: &str| note.rsplit_once('/').map_or(String::new(), |(folder, _)| folder.to_string());
+
+    let rewrite_wikilink = |target: &str| {
+        let target = target.trim();
+        // Links by alias find no note by name, and keep working after the rename
+        let linked = old_notes.resolve(target).filter(|note| note_stem(note) == note_stem(target))?;
+        if *linked != from {
+            return None;
+        }
+        let name = note_stem(&to);
+        let replacement = if !target.contains('/') && new_notes.resolve(name) == Some(&to) {
+            name.to_string()
+        } else {
+            to.strip_suffix(".md").unwrap_or(&to).to_string()
+        };
+        (replacement != target).then_some(replacement)
+    };
+
+    let hidden = hidden_notes(vault_path, options);
+    let mut rewritten_notes = Vec::new();
+    let mut links_rewritten = 0;
+    for (path, note) in paths.iter().zip(&notes) {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let (content, wikilinks) = rewrite_wikilinks(&content, rewrite_wikilink);
+        let new_folder = folder(&moved(note));
+        let (content, markdown_links) = rewrite_markdown_links(&content, |destination| {
+            let path = destination.split('#').next().unwrap_or_default();
+            let linked = match resolve_markdown_link(note, destination, &old_notes) {
+                Some(linked) if linked == from || *note == from => linked,
+                Some(_) => return None,
+                // The moved note's relative links to attachments need its new folder too
+                None if *note == from && !path.starts_with('/') && !path.contains("://") => {
+                    let attachment = relative_to_vault(&folder(note), &parse::percent_decode(path))?;
+                    vault_path.join(&attachment).is_file().then_some(attachment)?
+                }
+                None => return None,
+            };
+            let target = moved(&linked);
+            let anchor = destination.find('#').map_or("", |i| &destination[i..]);
+            let written = if path.starts_with('/') {
+                format!("/{}", target)
+            } else if !path.contains('/') && new_notes.resolve(note_stem(&target)) == Some(&target) && folder(&target) != new_folder {
+                target.rsplit('/').next().unwrap_or(&target).to_string()
+            } else {
+                relative_link(&new_folder, &target)
+            };
+            (parse::percent_decode(path) != written).then(|| format!("{}{}", written, anchor))
+        });
+        if wikilinks + markdown_links == 0 {
+            continue;
+        }
+        writer.write(vault_path, path, &content)?;
+        links_rewritten += wikilinks + markdown_links;
+        if !hidden.contains(note) {
+            rewritten_notes.push(moved(note));
+        }
+    }
+    rewritten_notes.sort();
+    writer.rename(vault_path, &vault_path.join(&from), &vault_path.join(&to))?;
+
+    Ok(RenameOutput {
+        dry_run: writer.dry_run,
+        from,
+        to,
+        rewritten_notes,
+        links_rewritten,
+    })
+}
+
+/// Moves a note into `folder` (`""` or `/` for the vault root) keeping its name, rewriting
+/// links to it and its own relative links as [`rename_note`] does.
+pub fn move_note(vault_path: &Path, note: &str, folder: &str, writer: &mut VaultWriter, options: &ScanOptions) -> Result<RenameOutput, String> {
+    let notes: HashSet<String> = markdown_files(vault_path).iter().map(|p| relative_path(vault_path, p)).collect();
+    let resolver = NoteResolver::new(&notes).read_aliases(vault_path);
+    let from = resolver
+        .resolve_path(note)
+        .or_else(|| resolver.resolve(note))
+        .ok_or_else(|| format!("note not found: {}", note))?;
+    let name = from.rsplit('/').next().unwrap_or(from);
+    let destination = relative_to_vault("", folder).ok_or_else(|| format!("{} is outside the vault", folder))?;
+    if vault_path.join(&destination).is_file() {
+        return Err(format!("{} is a file, not a folder", folder));
+    }
+    rename_note(vault_path, from, &join_path(&destination, name), writer, options)
+}
+
+/// Moves a note to `.trash`, refusing while other notes link to it unless `force` is set or
+/// `redirect_to` names a note to point those links at instead.
+pub fn delete_note(
+    vault_path: &Path,
+    note: &str,
+    redirect_to: Option<&str>,
+    force: bool,
+    writer: &mut VaultWriter,
+    options: &ScanOptions,
+) -> Result<DeleteOutput, String> {
+    let mut paths = markdown_files(vault_path);
+    paths.sort();
+    let all_notes: HashSet<String> = paths.iter().map(|p| relative_path(vault_path, p)).collect();
+    let resolver = NoteResolver::new(&all_notes).read_aliases(vault_path);
+    let note = resolver.resolve(note).cloned().ok_or_else(|| format!("note not found: {}", note))?;
+    let redirect = match redirect_to {
+        Some(other) => {
+            let other = resolver.resolve(other).ok_or_else(|| format!("note not found: {}", other))?;
+            if *other == note {
+                return Err("a note can't be redirected to itself".to_string());
+            }
+            Some(other.clone())
+        }
+        None => None,
+    };
+    let hidden = hidden_notes(vault_path, options);
+
+    // Every note counts here, private or not, since deleting would break its links too
+    let mut linking = Vec::new();
+    for path in &paths {
+        let source = relative_path(vault_path, path);
+        if source == note {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let links_here = extract_links_from_file(&content)
+            .iter()
+            .any(|link| resolver.resolve(split_link_anchor(link).0) == Some(&note))
+            || extract_markdown_links(&content)
+                .iter()
+                .any(|(destination, _)| resolve_markdown_link(&source, destination, &resolver).as_ref() == Some(&note));
+        if links_here {
+            linking.push((path, source, content));
+        }
+    }
+    let backlinks: Vec<String> = linking
+        .iter()
+        .map(|(_, source, _)| source.clone())
+        .filter(|source| !hidden.contains(source))
+        .collect();
+    let private_backlinks = linking.len() - backlinks.len();
+
+    let deleted = linking.is_empty() || force || redirect.is_some();
+    let mut rewritten_notes = Vec::new();
+    if let Some(other) = &redirect {
+        let replacement = link_target(other, &all_notes);
+        for (path, source, content) in &linking {
+            let (rewritten, wikilinks) = rewrite_wikilinks(content, |target| {
+                (resolver.resolve(target.trim()) == Some(&note)).then(|| replacement.clone())
+            });
+            let folder = source.rsplit_once('/').map_or("", |(folder, _)| folder);
+            let (rewritten, markdown_links) = rewrite_markdown_links(&rewritten, |destination| {
+                (resolve_markdown_link(source, destination, &resolver).as_ref() == Some(&note)).then(|| {
+                    let anchor = destination.find('#').map_or("", |i| &destination[i..]);
+                    format!("{}{}", relative_link(folder, other), anchor)
+                })
+            });
+            if wikilinks + markdown_links > 0 {
+                writer.write(vault_path, path, &rewritten)?;
+                if !hidden.contains(source) {
+                    rewritten_notes.push(source.clone());
+                }
+            }
+        }
+    }
+    if deleted {
+        writer.trash(vault_path, &vault_path.join(&note))?;
+    }
+
+    Ok(DeleteOutput {
+        dry_run: writer.dry_run,
+        note,
+        deleted,
+        backlinks,
+        private_backlinks,
+        redirected_to: redirect,
+        rewritten_notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_edits_cover_only_changed_lines() {
+        let edits = text_edits("a\n[[x]]\nb\n[[x]]\n", "a\n[[y]]\nb\n[[y]]\n");
+        assert_eq!(edits.iter().map(|e| (e.start, e.end)).collect::<Vec<_>>(), [(2, 8), (10, 16)]);
+        assert_eq!(edits[0].after, "[[y]]\n");
+
+        let edits = text_edits("a\nb\nc\n", "a\nc\n");
+        assert_eq!(edits, [TextEdit { start: 2, end: 4, before: "b\n".to_string(), after: String::new() }]);
+        assert!(text_edits("same", "same").is_empty());
+    }
+
+    #[test]
+    fn merging_vaults_skips_identical_files_and_renames_collisions() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-merge-{}", std::process::id()));
+        let (old, main) = (root.join("old"), root.join("main"));
+        fs::create_dir_all(old.join("Projects")).unwrap();
+        fs::create_dir_all(main.join("Projects")).unwrap();
+        fs::write(old.join("Same.md"), "same").unwrap();
+        fs::write(main.join("Same.md"), "same").unwrap();
+        fs::write(old.join("Projects/Plan.md"), "old plan").unwrap();
+        fs::write(main.join("Projects/Plan.md"), "main plan").unwrap();
+        fs::write(old.join("Index.md"), "[[Plan#Goals|goals]] and [[Same]]").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = merge_vaults(&old, &main, MergeConflict::Suffix, None, &mut writer).unwrap();
+        assert_eq!(output.copied, ["Index.md"]);
+        assert_eq!(output.identical[0].to, "Same.md");
+        assert_eq!(output.renamed[0].to, "Projects/Plan 1.md");
+        assert_eq!(fs::read_to_string(main.join("Projects/Plan 1.md")).unwrap(), "old plan");
+        assert_eq!(fs::read_to_string(main.join("Index.md")).unwrap(), "[[Plan 1#Goals|goals]] and [[Same]]");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn renaming_a_note_rewrites_links_to_it() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-rename-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects")).unwrap();
+        fs::write(root.join("Projects/My Plan.md"), "[back](../Index.md)").unwrap();
+        fs::write(root.join("Index.md"), "[[My Plan#Goals|goals]] [p](Projects/My%20Plan.md) [[Elsewhere]]").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = rename_note(&root, "My Plan", "Plan", &mut writer, &ScanOptions::default()).unwrap();
+        assert_eq!(output.links_rewritten, 3);
+        assert!(!root.join("Projects").exists());
+        assert_eq!(fs::read_to_string(root.join("Index.md")).unwrap(), "[[Plan#Goals|goals]] [p](Plan.md) [[Elsewhere]]");
+        assert_eq!(fs::read_to_string(root.join("Plan.md")).unwrap(), "[back](Index.md)");
+        assert!(rename_note(&root, "Plan", "Index", &mut writer, &ScanOptions::default()).is_err());
+        assert_eq!(
+            rename_note(&root, "Plan", "../outside", &mut writer, &ScanOptions::default()).err().as_deref(),
+            Some("../outside is outside the vault")
+        );
+        assert!(root.join("Plan.md").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn renaming_a_note_rewrites_block_links_to_it() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-rename-blocks-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Alpha.md"), "A quote ^blk1\n").unwrap();
+        fs::write(root.join("Index.md"), "[[Alpha^blk1]] ![[Alpha#^blk1]] [[Alpha^blk1|quote]]").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = rename_note(&root, "Alpha", "Beta", &mut writer, &ScanOptions::default()).unwrap();
+        assert_eq!(output.links_rewritten, 3);
+        assert_eq!(fs::read_to_string(root.join("Index.md")).unwrap(), "[[Beta^blk1]] ![[Beta#^blk1]] [[Beta^blk1|quote]]");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn deleting_a_note_twice_keeps_both_trashed_copies() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-trash-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        for content in ["first", "second"] {
+            fs::write(root.join("Orphan.md"), content).unwrap();
+            let mut writer = VaultWriter::new(false, false);
+            assert!(delete_note(&root, "Orphan", None, false, &mut writer, &ScanOptions::default()).unwrap().deleted);
+        }
+        assert_eq!(fs::read_to_string(root.join(".trash/Orphan.md")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(root.join(".trash/Orphan 1.md")).unwrap(), "second");
+        // Trashed notes are no longer part of the vault
+        assert!(markdown_files(&root).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn deleting_a_note_checks_and_redirects_markdown_links() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-delete-{}", std::process::id()));
+        fs::create_dir_all(root.join("Daily")).unwrap();
+        fs::write(root.join("Daily/2025-10-15.md"), "log").unwrap();
+        fs::write(root.join("Beta.md"), "beta").unwrap();
+        fs::write(root.join("Gamma.md"), "gamma").unwrap();
+        fs::write(root.join("Index.md"), "[d](Daily/2025-10-15.md) [b](Beta.md#Top) and `[c](Beta.md)`").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = delete_note(&root, "2025-10-15", None, false, &mut writer, &ScanOptions::default()).unwrap();
+        assert!(!output.deleted);
+        assert_eq!(output.backlinks, ["Index.md"]);
+
+        let output = delete_note(&root, "Beta", Some("Gamma"), false, &mut writer, &ScanOptions::default()).unwrap();
+        assert!(output.deleted);
+        assert_eq!(output.rewritten_notes, ["Index.md"]);
+        assert_eq!(fs::read_to_string(root.join("Index.md")).unwrap(), "[d](Daily/2025-10-15.md) [b](Gamma.md#Top) and `[c](Beta.md)`");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn moving_a_note_keeps_it_inside_the_vault() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-move-{}", std::process::id()));
+        fs::create_dir_all(root.join("Projects")).unwrap();
+        fs::write(root.join("Projects/Gamma.md"), "gamma").unwrap();
+        fs::write(root.join("Index.md"), "[[Gamma]]").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        assert_eq!(
+            move_note(&root, "Gamma", "../../tmp", &mut writer, &ScanOptions::default()).err().as_deref(),
+            Some("../../tmp is outside the vault")
+        );
+        let output = move_note(&root, "Gamma", "Projects/../Archive/", &mut writer, &ScanOptions::default()).unwrap();
+        assert_eq!(output.to, "Archive/Gamma.md");
+        assert!(root.join("Archive/Gamma.md").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn duplicate_attachments_are_trashed_and_embeds_repointed() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-dedupe-{}", std::process::id()));
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(root.join("assets/logo.png"), "same bytes").unwrap();
+        fs::write(root.join("logo copy.png"), "same bytes").unwrap();
+        fs::write(root.join("other.png"), "diff bytes").unwrap();
+        fs::write(root.join("Note.md"), "![[logo copy.png]] ![[other.png]]").unwrap();
+
+        let mut writer = VaultWriter::new(false, false);
+        let output = dedupe_attachments(&root, &mut writer).unwrap();
+        assert_eq!(output.groups.len(), 1);
+        assert_eq!(output.groups[0].canonical, "assets/logo.png");
+        assert_eq!(output.groups[0].duplicates, ["logo copy.png"]);
+        assert_eq!(output.bytes_reclaimed, 10);
+        assert_eq!(output.rewritten_notes, ["Note.md"]);
+        assert_eq!(fs::read_to_string(root.join("Note.md")).unwrap(), "![[logo.png]] ![[other.png]]");
+        assert!(!root.join("logo copy.png").exists() && root.join(".trash/logo copy.png").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn plan_only_records_every_change_without_touching_the_vault() {
+        let root = std::env::temp_dir().join(format!("obsidian-cli-plan-only-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Plan.md"), "[[Old]]\n").unwrap();
+        fs::write(root.join("Old.md"), "old").unwrap();
+
+        let mut writer = VaultWriter::new(false, true);
+        assert!(writer.dry_run);
+        rename_note(&root, "Old", "New", &mut writer, &ScanOptions::default()).unwrap();
+        writer.write(&root, &root.join("Fresh.md"), "hello\n").unwrap();
+        let changes: Vec<(&str, &str, Option<&str>)> =
+            writer.changes.iter().map(|c| (c.path.as_str(), c.action, c.destination.as_deref())).collect();
+        assert_eq!(changes, [("Plan.md", "modify", None), ("Old.md", "move", Some("New.md")), ("Fresh.md", "create", None)]);
+        assert_eq!(writer.changes[0].edits[0].after, "[[New]]\n");
+        assert_eq!(fs::read_to_string(root.join("Plan.md")).unwrap(), "[[Old]]\n");
+        assert!(root.join("Old.md").exists() && !root.join("New.md").exists() && !root.join("Fresh.md").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}